@@ -0,0 +1,113 @@
+//! Compares opening scopes with individually reserved timer queries against [`GpuProfiler::reserve_scopes`].
+//!
+//! Run with `cargo bench --bench reserve_scopes`.
+//! Requires an adapter supporting timestamp queries inside encoders, otherwise the benchmark is skipped.
+
+use std::time::{Duration, Instant};
+
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
+
+const NUM_THREADS: usize = 8;
+const NUM_SCOPES_PER_THREAD: u32 = 1000;
+const NUM_FRAMES: usize = 20;
+
+fn create_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::PRIMARY,
+        ..Default::default()
+    });
+    let adapter = futures_lite::future::block_on(
+        instance.request_adapter(&wgpu::RequestAdapterOptions::default()),
+    )?;
+    let required_features =
+        wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS;
+    if !adapter.features().contains(required_features) {
+        return None;
+    }
+    futures_lite::future::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            required_features,
+            ..Default::default()
+        },
+        None,
+    ))
+    .ok()
+}
+
+/// Returns the time spent opening & closing scopes from all threads, summed over all frames.
+fn run(device: &wgpu::Device, queue: &wgpu::Queue, use_reservations: bool) -> Duration {
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        enable_debug_groups: false,
+        min_pool_capacity: NUM_THREADS as u32 * NUM_SCOPES_PER_THREAD * 2,
+        ..Default::default()
+    })
+    .unwrap();
+
+    let mut total = Duration::ZERO;
+    for _ in 0..NUM_FRAMES {
+        let start = Instant::now();
+        let command_buffers = std::thread::scope(|thread_scope| {
+            let threads = (0..NUM_THREADS)
+                .map(|_| {
+                    thread_scope.spawn(|| {
+                        let mut encoder = device
+                            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                        if use_reservations {
+                            let mut reserved =
+                                profiler.reserve_scopes(NUM_SCOPES_PER_THREAD, device);
+                            for _ in 0..NUM_SCOPES_PER_THREAD {
+                                let query = profiler.begin_reserved_query(
+                                    &mut reserved,
+                                    "scope",
+                                    &mut encoder,
+                                    device,
+                                );
+                                profiler.end_query(&mut encoder, query);
+                            }
+                        } else {
+                            for _ in 0..NUM_SCOPES_PER_THREAD {
+                                let query = profiler.begin_query("scope", &mut encoder, device);
+                                profiler.end_query(&mut encoder, query);
+                            }
+                        }
+                        encoder.finish()
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        total += start.elapsed();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        profiler.resolve_queries(&mut encoder);
+        queue.submit(command_buffers.into_iter().chain([encoder.finish()]));
+        profiler.end_frame().unwrap();
+        device.poll(wgpu::Maintain::Wait);
+        while profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .is_some()
+        {}
+    }
+    total
+}
+
+fn main() {
+    let Some((device, queue)) = create_device() else {
+        eprintln!("No adapter supporting timestamp queries inside encoders, skipping benchmark.");
+        return;
+    };
+
+    let num_scopes = (NUM_THREADS * NUM_SCOPES_PER_THREAD as usize * NUM_FRAMES) as f64;
+    for (name, use_reservations) in [("per-scope", false), ("reserve_scopes", true)] {
+        // Warm up, e.g. to create the query pools.
+        run(&device, &queue, use_reservations);
+        let total = run(&device, &queue, use_reservations);
+        println!(
+            "{name:>14}: {:.1} ns per scope ({NUM_THREADS} threads, {NUM_SCOPES_PER_THREAD} scopes per thread)",
+            total.as_secs_f64() * 1e9 / num_scopes
+        );
+    }
+}
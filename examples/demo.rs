@@ -13,12 +13,8 @@ fn scopes_to_console_recursive(results: &[GpuTimerQueryResult], indentation: u32
             print!("{:<width$}", "|", width = 4);
         }
 
-        if let Some(time) = &scope.time {
-            println!(
-                "{:.3}μs - {}",
-                (time.end - time.start) * 1000.0 * 1000.0,
-                scope.label
-            );
+        if let Some(duration) = scope.duration_us() {
+            println!("{:.3}μs - {}", duration, scope.label);
         } else {
             println!("n/a - {}", scope.label);
         }
@@ -150,15 +146,18 @@ impl GfxState {
             wgpu_profiler::CreationError::TracyClientNotRunning
             | wgpu_profiler::CreationError::TracyGpuContextCreationError(_) => {
                 println!("Failed to connect to Tracy. Continuing without Tracy integration.");
-                GpuProfiler::new(GpuProfilerSettings::default()).expect("Failed to create profiler")
+                GpuProfiler::new(GpuProfilerSettings::default())
+                    .expect("Failed to create profiler")
+                    .with_adapter_info(adapter.get_info())
             }
             _ => {
                 panic!("Failed to create profiler: {}", err);
             }
         });
         #[cfg(not(feature = "tracy"))]
-        let profiler =
-            GpuProfiler::new(GpuProfilerSettings::default()).expect("Failed to create profiler");
+        let profiler = GpuProfiler::new(GpuProfilerSettings::default())
+            .expect("Failed to create profiler")
+            .with_adapter_info(adapter.get_info());
 
         Self {
             surface,
@@ -210,12 +209,10 @@ impl ApplicationHandler<()> for State {
         };
 
         match event {
-            WindowEvent::Resized(size) => {
-                if size.width > 0 && size.height > 0 {
-                    surface_desc.width = size.width;
-                    surface_desc.height = size.height;
-                    surface.configure(device, surface_desc);
-                }
+            WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
+                surface_desc.width = size.width;
+                surface_desc.height = size.height;
+                surface.configure(device, surface_desc);
             }
             WindowEvent::CloseRequested => {
                 event_loop.exit();
@@ -252,8 +249,9 @@ impl ApplicationHandler<()> for State {
                 // Signal to the profiler that the frame is finished.
                 profiler.end_frame().unwrap();
                 // Query for oldest finished frame (this is almost certainly not the one we just submitted!) and display results in the command line.
-                self.latest_profiler_results =
-                    profiler.process_finished_frame(queue.get_timestamp_period());
+                self.latest_profiler_results = profiler
+                    .process_finished_frame(queue.get_timestamp_period())
+                    .map(|frame| frame.results);
                 console_output(&self.latest_profiler_results, device.features());
             }
 
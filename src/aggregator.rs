@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use crate::GpuTimerQueryResult;
+
+/// Separator between labels of nested scopes in label paths of [`GpuTimerQueryAggregator`].
+pub const LABEL_PATH_SEPARATOR: &str = "/";
+
+/// Timing statistics of a single scope over several frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeTimingStatistics {
+    /// Number of timed occurrences of the scope.
+    pub num_samples: u64,
+
+    /// Sum of all durations in seconds.
+    pub total: f64,
+
+    /// Shortest duration in seconds.
+    pub min: f64,
+
+    /// Longest duration in seconds.
+    pub max: f64,
+}
+
+impl ScopeTimingStatistics {
+    fn new(duration: f64) -> Self {
+        Self {
+            num_samples: 1,
+            total: duration,
+            min: duration,
+            max: duration,
+        }
+    }
+
+    fn add(&mut self, duration: f64) {
+        self.num_samples += 1;
+        self.total += duration;
+        self.min = self.min.min(duration);
+        self.max = self.max.max(duration);
+    }
+
+    /// Average duration in seconds.
+    pub fn average(&self) -> f64 {
+        self.total / self.num_samples as f64
+    }
+}
+
+/// Aggregates timings of scopes over several frames.
+///
+/// Scopes are identified by their label path, i.e. the labels of all their parent scopes and their own,
+/// joined with [`LABEL_PATH_SEPARATOR`].
+/// Scopes without timing information are not recorded, but their nested scopes are.
+#[derive(Debug, Clone, Default)]
+pub struct GpuTimerQueryAggregator {
+    statistics_by_label_path: BTreeMap<String, ScopeTimingStatistics>,
+    total_by_category: BTreeMap<&'static str, f64>,
+}
+
+impl GpuTimerQueryAggregator {
+    /// Creates an empty aggregator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the results of a frame, as returned by [`GpuProfiler::process_finished_frame`](crate::GpuProfiler::process_finished_frame).
+    pub fn add_frame(&mut self, results: &[GpuTimerQueryResult]) {
+        let mut label_path = String::new();
+        self.add_results_recursive(results, &mut label_path, None);
+    }
+
+    fn add_results_recursive(
+        &mut self,
+        results: &[GpuTimerQueryResult],
+        label_path: &mut String,
+        counted_category: Option<&'static str>,
+    ) {
+        for result in results {
+            let mut nested_counted_category = counted_category;
+            let parent_path_len = label_path.len();
+            if parent_path_len > 0 {
+                label_path.push_str(LABEL_PATH_SEPARATOR);
+            }
+            label_path.push_str(&result.label);
+
+            if let Some(duration) = result.duration_secs() {
+                if let Some(statistics) = self.statistics_by_label_path.get_mut(label_path.as_str())
+                {
+                    statistics.add(duration);
+                } else {
+                    self.statistics_by_label_path
+                        .insert(label_path.clone(), ScopeTimingStatistics::new(duration));
+                }
+
+                // Nested scopes of the same category are already covered by the enclosing scope.
+                if let Some(category) = result.category.filter(|c| Some(*c) != counted_category) {
+                    *self.total_by_category.entry(category).or_default() += duration;
+                    nested_counted_category = Some(category);
+                }
+            }
+            self.add_results_recursive(&result.nested_queries, label_path, nested_counted_category);
+
+            label_path.truncate(parent_path_len);
+        }
+    }
+
+    /// Statistics of a scope by its label path, if it was recorded.
+    pub fn get(&self, label_path: &str) -> Option<&ScopeTimingStatistics> {
+        self.statistics_by_label_path.get(label_path)
+    }
+
+    /// Iterates over all recorded scopes and their statistics, ordered by label path.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ScopeTimingStatistics)> {
+        self.statistics_by_label_path
+            .iter()
+            .map(|(label_path, statistics)| (label_path.as_str(), statistics))
+    }
+
+    /// Total duration in seconds of all scopes with a [`GpuTimerQueryResult::category`], by category.
+    ///
+    /// Gives a high level breakdown of where frame time is going, independent of individual scope labels.
+    /// Scopes whose closest enclosing counted scope has the same category are not counted again.
+    pub fn totals_by_category(&self) -> &BTreeMap<&'static str, f64> {
+        &self.total_by_category
+    }
+
+    /// Returns true if no scope has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.statistics_by_label_path.is_empty()
+    }
+
+    /// Removes all recorded statistics.
+    pub fn clear(&mut self) {
+        self.statistics_by_label_path.clear();
+        self.total_by_category.clear();
+    }
+}
@@ -1,22 +1,141 @@
-use std::{fs::File, io::Write, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::Write,
+    path::Path,
+    thread::ThreadId,
+};
 
-use crate::GpuTimerQueryResult;
+use crate::{
+    profiler_query::gpu_busy_time_of, summary::escape_json_string, GpuTimerQueryResult,
+    SubmitMarker,
+};
+
+/// Thread id used for the track of submit markers, not used by any actual thread.
+const SUBMIT_MARKER_TID: u64 = 0;
+
+/// How the individual begin & end timestamps of scopes are written, see [`ChromeTraceOptions::timestamp_markers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampMarkers {
+    /// Only write a duration event per scope.
+    #[default]
+    None,
+
+    /// Write `<label> begin` & `<label> end` instant events in addition to the duration event of each scope.
+    WithDuration,
+
+    /// Write `<label> begin` & `<label> end` instant events instead of the duration event of each scope.
+    InsteadOfDuration,
+}
+
+/// Additional content & encoding of a trace written by [`write_chrometrace_with_options`].
+///
+/// The default writes an uncompressed trace of just the results, same as [`write_chrometrace`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChromeTraceOptions<'a> {
+    /// Information about the adapter, stored in the trace's metadata.
+    ///
+    /// Makes traces self-describing when comparing them across machines.
+    /// The adapter info is written to the `otherData` section, which is shown in the metadata view of trace viewers.
+    /// See also [`GpuProfiler::adapter_info`](crate::GpuProfiler::adapter_info).
+    pub adapter_info: Option<&'a wgpu::AdapterInfo>,
+
+    /// Submit markers, written as instant events on a dedicated "CPU submits" track.
+    ///
+    /// See [`GpuProfiler::note_submit`](crate::GpuProfiler::note_submit) and [`FinishedFrame::submit_markers`](crate::FinishedFrame::submit_markers).
+    /// Note that CPU & GPU clocks aren't synchronized: markers are placed relative to each other,
+    /// with the earliest marker aligned to the start of the earliest GPU scope.
+    pub submit_markers: &'a [SubmitMarker],
+
+    /// Label of the frame, see [`FinishedFrame::label`](crate::FinishedFrame::label).
+    ///
+    /// The label is written as a global instant event at the start of the frame's earliest scope,
+    /// which makes captures taken under different conditions distinguishable when combined into a single trace.
+    pub frame_label: Option<&'a str>,
+
+    /// Whether to additionally or exclusively write the begin & end timestamps of scopes as instant events.
+    ///
+    /// Useful for debugging where a driver places timestamps, e.g. when the end timestamp of a scope
+    /// lands in an unexpected place due to asynchronous execution.
+    pub timestamp_markers: TimestampMarkers,
+
+    /// Whether to compress the trace with gzip.
+    ///
+    /// Traces with many scopes repeat the same labels over and over, so they compress very well.
+    /// Trace viewers like [Perfetto](https://ui.perfetto.dev) open gzip compressed traces directly.
+    #[cfg(feature = "gzip")]
+    pub gzip: bool,
+}
 
 /// Writes a .json trace file that can be viewed as a flame graph in Chrome or Edge via <chrome://tracing>
+///
+/// Every thread's track is named after the time the GPU was busy with the scopes recorded on it,
+/// which helps spotting imbalances when recording from several threads.
+/// Nested scopes are attributed to the thread of their outermost scope.
+///
+/// Timestamps are written as fractional microseconds with full precision,
+/// so that sub-microsecond scopes keep their distinct start & end times.
+///
+/// See [`write_chrometrace_with_options`] for writing additional information.
 pub fn write_chrometrace(
     target: &Path,
     profile_data: &[GpuTimerQueryResult],
+) -> std::io::Result<()> {
+    write_chrometrace_with_options(target, profile_data, &ChromeTraceOptions::default())
+}
+
+/// Like [`write_chrometrace`], but additionally writes everything set in `options`.
+pub fn write_chrometrace_with_options(
+    target: &Path,
+    profile_data: &[GpuTimerQueryResult],
+    options: &ChromeTraceOptions<'_>,
 ) -> std::io::Result<()> {
     let mut file = File::create(target)?;
+    #[cfg(feature = "gzip")]
+    if options.gzip {
+        let mut encoder = flate2::write::GzEncoder::new(
+            std::io::BufWriter::new(file),
+            flate2::Compression::default(),
+        );
+        write_trace(&mut encoder, profile_data, options)?;
+        return encoder.finish()?.flush();
+    }
+    write_trace(&mut file, profile_data, options)
+}
+
+fn write_trace(
+    file: &mut impl Write,
+    profile_data: &[GpuTimerQueryResult],
+    options: &ChromeTraceOptions<'_>,
+) -> std::io::Result<()> {
+    // Compression is handled by the caller.
+    let ChromeTraceOptions {
+        adapter_info,
+        submit_markers,
+        frame_label,
+        timestamp_markers,
+        ..
+    } = *options;
 
     writeln!(file, "{{")?;
+    if let Some(adapter_info) = adapter_info {
+        write_adapter_info(file, adapter_info)?;
+    }
     writeln!(file, "\"traceEvents\": [")?;
 
+    if let Some(frame_label) = frame_label {
+        write_frame_label(file, profile_data, frame_label, !submit_markers.is_empty())?;
+    }
+    if !submit_markers.is_empty() {
+        write_submit_markers(file, profile_data, submit_markers)?;
+    }
+    write_thread_summaries(file, profile_data)?;
+
     if !profile_data.is_empty() {
         for child in profile_data.iter().take(profile_data.len() - 1) {
-            write_results_recursive(&mut file, child, false)?;
+            write_results_recursive(file, child, false, timestamp_markers)?;
         }
-        write_results_recursive(&mut file, profile_data.last().unwrap(), true)?;
+        write_results_recursive(file, profile_data.last().unwrap(), true, timestamp_markers)?;
     }
 
     writeln!(file, "]")?;
@@ -25,10 +144,182 @@ pub fn write_chrometrace(
     Ok(())
 }
 
+fn write_adapter_info(
+    file: &mut impl Write,
+    adapter_info: &wgpu::AdapterInfo,
+) -> std::io::Result<()> {
+    writeln!(file, "\"otherData\": {{")?;
+    writeln!(
+        file,
+        "\"adapter_name\": \"{}\",",
+        escape_json_string(&adapter_info.name)
+    )?;
+    writeln!(file, "\"adapter_vendor\": {},", adapter_info.vendor)?;
+    writeln!(file, "\"adapter_device\": {},", adapter_info.device)?;
+    writeln!(
+        file,
+        "\"adapter_device_type\": \"{:?}\",",
+        adapter_info.device_type
+    )?;
+    writeln!(
+        file,
+        "\"driver\": \"{}\",",
+        escape_json_string(&adapter_info.driver)
+    )?;
+    writeln!(
+        file,
+        "\"driver_info\": \"{}\",",
+        escape_json_string(&adapter_info.driver_info)
+    )?;
+    writeln!(file, "\"backend\": \"{:?}\"", adapter_info.backend)?;
+    writeln!(file, "}},")?;
+
+    Ok(())
+}
+
+fn write_submit_markers(
+    file: &mut impl Write,
+    profile_data: &[GpuTimerQueryResult],
+    submit_markers: &[SubmitMarker],
+) -> std::io::Result<()> {
+    let pid = profile_data.first().map_or(0, |result| result.pid);
+    let gpu_start = earliest_start_time(profile_data);
+    let Some(first_marker_time) = submit_markers.iter().map(|marker| marker.time).min() else {
+        return Ok(());
+    };
+
+    write!(
+        file,
+        r#"{{ "pid":{pid}, "tid":{SUBMIT_MARKER_TID}, "ph":"M", "name":"thread_name", "args":{{ "name":"CPU submits" }} }}"#
+    )?;
+    for marker in submit_markers {
+        let time = gpu_start + (marker.time - first_marker_time).as_secs_f64();
+        write!(
+            file,
+            ",\n{{ \"pid\":{pid}, \"tid\":{SUBMIT_MARKER_TID}, \"ts\":{}, \"ph\":\"i\", \"s\":\"t\", \"name\":\"{}\" }}",
+            time * 1000.0 * 1000.0,
+            escape_json_string(&marker.label),
+        )?;
+    }
+    // Result events follow, if any.
+    if profile_data.is_empty() {
+        writeln!(file)
+    } else {
+        writeln!(file, ",")
+    }
+}
+
+/// Writes the frame label as a global instant event.
+fn write_frame_label(
+    file: &mut impl Write,
+    profile_data: &[GpuTimerQueryResult],
+    frame_label: &str,
+    more_events_follow: bool,
+) -> std::io::Result<()> {
+    let pid = profile_data.first().map_or(0, |result| result.pid);
+    write!(
+        file,
+        "{{ \"pid\":{pid}, \"tid\":0, \"ts\":{}, \"ph\":\"i\", \"s\":\"g\", \"name\":\"{}\" }}",
+        earliest_start_time(profile_data) * 1000.0 * 1000.0,
+        escape_json_string(frame_label),
+    )?;
+    if more_events_follow || !profile_data.is_empty() {
+        writeln!(file, ",")
+    } else {
+        writeln!(file)
+    }
+}
+
+/// Start of the earliest timed scope in seconds, zero if there is none.
+fn earliest_start_time(profile_data: &[GpuTimerQueryResult]) -> f64 {
+    let mut gpu_start = f64::INFINITY;
+    GpuTimerQueryResult::walk_all(profile_data, &mut |result, _, _| {
+        if let Some(time) = &result.time {
+            gpu_start = gpu_start.min(time.start);
+        }
+    });
+    if gpu_start == f64::INFINITY {
+        0.0
+    } else {
+        gpu_start
+    }
+}
+
+/// Names every thread's track after the GPU busy time of its scopes.
+///
+/// Expects result events to follow.
+fn write_thread_summaries(
+    file: &mut impl Write,
+    profile_data: &[GpuTimerQueryResult],
+) -> std::io::Result<()> {
+    let mut roots_by_thread = BTreeMap::<(u32, u64), Vec<&GpuTimerQueryResult>>::new();
+    for result in profile_data {
+        roots_by_thread
+            .entry((result.pid, thread_id_to_u64(result.tid)))
+            .or_default()
+            .push(result);
+    }
+
+    for ((pid, tid), roots) in roots_by_thread {
+        let busy_time = gpu_busy_time_of(roots);
+        if busy_time <= 0.0 {
+            continue;
+        }
+        writeln!(
+            file,
+            r#"{{ "pid":{pid}, "tid":{tid}, "ph":"M", "name":"thread_name", "args":{{ "name":"Thread {tid} ({:.3} ms GPU busy)" }} }},"#,
+            busy_time * 1000.0,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Formats extra metrics as event arguments, sorted by name for reproducible traces.
+fn format_extra_metrics(extra_metrics: &HashMap<String, f64>) -> String {
+    if extra_metrics.is_empty() {
+        return String::new();
+    }
+    let mut metrics = extra_metrics.iter().collect::<Vec<_>>();
+    metrics.sort_by_key(|(name, _)| *name);
+    let args = metrics
+        .into_iter()
+        .map(|(name, value)| format!(r#""{}":{}"#, escape_json_string(name), json_number(*value)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(r#", "args":{{ {args} }}"#)
+}
+
+/// JSON has no representation for non-finite numbers.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_owned()
+    }
+}
+
+/// Converts a [`ThreadId`] to an integer for use in trace files.
+pub(crate) fn thread_id_to_u64(tid: ThreadId) -> u64 {
+    // note: ThreadIds are under the control of Rust’s standard library
+    // and there may not be any relationship between ThreadId and the underlying platform’s notion of a thread identifier
+    //
+    // There's a proposal for stabilization of ThreadId::as_u64, which
+    // would eliminate the need for this hack: https://github.com/rust-lang/rust/pull/110738
+    //
+    // for now, we use this hack to convert to integer
+    format!("{:?}", tid)
+        .replace("ThreadId(", "")
+        .replace(')', "")
+        .parse::<u64>()
+        .unwrap_or(u64::MAX)
+}
+
 fn write_results_recursive(
-    file: &mut File,
+    file: &mut impl Write,
     result: &GpuTimerQueryResult,
     last: bool,
+    timestamp_markers: TimestampMarkers,
 ) -> std::io::Result<()> {
     let GpuTimerQueryResult {
         label,
@@ -36,46 +327,59 @@ fn write_results_recursive(
         tid,
         time,
         nested_queries,
+        backend: _,
+        clock_calibration: _,
+        unit_count: _,
+        category,
+        num_samples: _,
+        extra_metrics,
     } = result;
 
     if let Some(time) = time {
-        // note: ThreadIds are under the control of Rust’s standard library
-        // and there may not be any relationship between ThreadId and the underlying platform’s notion of a thread identifier
-        //
-        // There's a proposal for stabilization of ThreadId::as_u64, which
-        // would eliminate the need for this hack: https://github.com/rust-lang/rust/pull/110738
-        //
-        // for now, we use this hack to convert to integer
-        let tid_to_int = |tid| {
-            format!("{:?}", tid)
-                .replace("ThreadId(", "")
-                .replace(')', "")
-                .parse::<u64>()
-                .unwrap_or(u64::MAX)
-        };
-        write!(
-            file,
-            r#"{{ "pid":{}, "tid":{}, "ts":{}, "dur":{}, "ph":"X", "name":"{}" }}{}"#,
-            pid,
-            tid_to_int(tid),
-            time.start * 1000.0 * 1000.0,
-            (time.end - time.start) * 1000.0 * 1000.0,
-            label,
-            if last && nested_queries.is_empty() {
-                "\n"
-            } else {
-                ",\n"
+        // Derive the duration from the converted timestamps, so that start + duration lines up exactly with the end.
+        let start_us = time.start * 1000.0 * 1000.0;
+        let end_us = time.end * 1000.0 * 1000.0;
+        let tid = thread_id_to_u64(*tid);
+        let label = escape_json_string(label);
+        let category = category
+            .map(|category| format!(r#", "cat":"{}""#, escape_json_string(category)))
+            .unwrap_or_default();
+
+        let mut events = Vec::with_capacity(3);
+        if timestamp_markers != TimestampMarkers::InsteadOfDuration {
+            events.push(format!(
+                r#"{{ "pid":{pid}, "tid":{tid}, "ts":{start_us}, "dur":{}, "ph":"X", "name":"{label}"{category}{} }}"#,
+                end_us - start_us,
+                format_extra_metrics(extra_metrics),
+            ));
+        }
+        if timestamp_markers != TimestampMarkers::None {
+            for (ts, suffix) in [(start_us, "begin"), (end_us, "end")] {
+                events.push(format!(
+                    r#"{{ "pid":{pid}, "tid":{tid}, "ts":{ts}, "ph":"i", "s":"t", "name":"{label} {suffix}"{category} }}"#
+                ));
             }
-        )?;
+        }
+
+        let num_events = events.len();
+        for (i, event) in events.into_iter().enumerate() {
+            let is_last_event = last && nested_queries.is_empty() && i + 1 == num_events;
+            write!(file, "{event}{}", if is_last_event { "\n" } else { ",\n" })?;
+        }
     }
     if nested_queries.is_empty() {
         return Ok(());
     }
 
     for child in nested_queries.iter().take(nested_queries.len() - 1) {
-        write_results_recursive(file, child, false)?;
+        write_results_recursive(file, child, false, timestamp_markers)?;
     }
-    write_results_recursive(file, nested_queries.last().unwrap(), last)?;
+    write_results_recursive(
+        file,
+        nested_queries.last().unwrap(),
+        last,
+        timestamp_markers,
+    )?;
 
     Ok(())
     // { "pid":1, "tid":1, "ts":546867, "dur":121564, "ph":"X", "name":"DoThings"
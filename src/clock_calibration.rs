@@ -0,0 +1,98 @@
+use std::time::{Duration, SystemTime};
+
+/// A GPU timestamp matched up with the system clock.
+///
+/// Allows converting result times to wall clock time, see [`GpuTimerQueryResult::absolute_time`].
+/// GPU and system clock drift apart over time, so long running applications should re-calibrate periodically,
+/// see [`GpuProfiler::set_clock_calibration`].
+///
+/// [`GpuTimerQueryResult::absolute_time`]: crate::GpuTimerQueryResult::absolute_time
+/// [`GpuProfiler::set_clock_calibration`]: crate::GpuProfiler::set_clock_calibration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockCalibration {
+    /// GPU time in seconds, in the same time base as [`GpuTimerQueryResult::time`](crate::GpuTimerQueryResult::time).
+    pub gpu_time: f64,
+
+    /// System time at which the GPU timestamp was taken.
+    pub system_time: SystemTime,
+}
+
+impl ClockCalibration {
+    /// Writes a GPU timestamp and blocks until it is read back, matching it up with the current system time.
+    ///
+    /// Requires [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS`].
+    /// The system time is taken right after submitting the timestamp write, so the calibration is only as precise
+    /// as the latency until the GPU executes the submission. Calibrate while the GPU is idle for best results.
+    pub fn measure(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let map_buffer = submit_calibration_timestamp(device, queue);
+        let system_time = SystemTime::now();
+
+        map_buffer.slice(..).map_async(wgpu::MapMode::Read, |_| ());
+        device.poll(wgpu::Maintain::Wait);
+
+        let view = map_buffer.slice(..).get_mapped_range();
+        let timestamp = u64::from_le_bytes((*view).try_into().unwrap());
+        Self {
+            gpu_time: timestamp as f64 * queue.get_timestamp_period() as f64
+                / 1000.0
+                / 1000.0
+                / 1000.0,
+            system_time,
+        }
+    }
+
+    /// Converts a GPU time in seconds to system time.
+    ///
+    /// Returns `None` if the result can't be represented as [`SystemTime`].
+    pub fn to_system_time(&self, gpu_time: f64) -> Option<SystemTime> {
+        let offset = gpu_time - self.gpu_time;
+        if offset >= 0.0 {
+            self.system_time
+                .checked_add(Duration::try_from_secs_f64(offset).ok()?)
+        } else {
+            self.system_time
+                .checked_sub(Duration::try_from_secs_f64(-offset).ok()?)
+        }
+    }
+}
+
+/// Submits a timestamp query and returns a mappable buffer the timestamp is copied into.
+pub(crate) fn submit_calibration_timestamp(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> wgpu::Buffer {
+    let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+        label: Some("wgpu-profiler gpu -> cpu sync query_set"),
+        ty: wgpu::QueryType::Timestamp,
+        count: 1,
+    });
+
+    let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("wgpu-profiler gpu -> cpu resolve buffer"),
+        size: wgpu::QUERY_SIZE as _,
+        usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    let map_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("wgpu-profiler gpu -> cpu map buffer"),
+        size: wgpu::QUERY_SIZE as _,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut timestamp_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("wgpu-profiler gpu -> cpu query timestamp"),
+    });
+    timestamp_encoder.write_timestamp(&query_set, 0);
+    timestamp_encoder.resolve_query_set(&query_set, 0..1, &resolve_buffer, 0);
+    // Workaround for https://github.com/gfx-rs/wgpu/issues/6406
+    // TODO when that bug is fixed, merge these encoders together again
+    let mut copy_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("wgpu-profiler gpu -> cpu copy timestamp"),
+    });
+    copy_encoder.copy_buffer_to_buffer(&resolve_buffer, 0, &map_buffer, 0, wgpu::QUERY_SIZE as _);
+    queue.submit([timestamp_encoder.finish(), copy_encoder.finish()]);
+
+    map_buffer
+}
@@ -0,0 +1,89 @@
+use std::ops::Range;
+
+use crate::GpuTimerQueryResult;
+
+/// Width of the timing bar shown next to each scope, in points.
+const TIME_BAR_WIDTH: f32 = 100.0;
+
+/// Shows profiler results as a collapsible tree in an [`egui::Ui`].
+///
+/// Every scope shows its duration along with a bar visualizing where in the frame it ran,
+/// relative to the earliest start and latest end of all passed results.
+/// Scopes without timing information are shown without duration & bar.
+pub fn profiler_ui(ui: &mut egui::Ui, results: &[GpuTimerQueryResult]) {
+    let mut frame_range: Option<Range<f64>> = None;
+    GpuTimerQueryResult::walk_all(results, &mut |result, _, _| {
+        if let Some(time) = &result.time {
+            frame_range = Some(match frame_range.take() {
+                Some(range) => range.start.min(time.start)..range.end.max(time.end),
+                None => time.clone(),
+            });
+        }
+    });
+
+    for (index, result) in results.iter().enumerate() {
+        scope_ui(ui, result, frame_range.as_ref(), index);
+    }
+}
+
+fn scope_ui(
+    ui: &mut egui::Ui,
+    result: &GpuTimerQueryResult,
+    frame_range: Option<&Range<f64>>,
+    index: usize,
+) {
+    let header = |ui: &mut egui::Ui| {
+        time_bar_ui(ui, result.time.as_ref(), frame_range);
+        if let Some(duration) = result.duration_ms() {
+            ui.label(format!("{}: {:.3} ms", result.label, duration));
+        } else {
+            ui.label(result.label.as_str());
+        }
+    };
+
+    if result.nested_queries.is_empty() {
+        ui.horizontal(header);
+        return;
+    }
+
+    let id = ui.make_persistent_id((&result.label, index));
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+        .show_header(ui, header)
+        .body(|ui| {
+            for (index, nested) in result.nested_queries.iter().enumerate() {
+                scope_ui(ui, nested, frame_range, index);
+            }
+        });
+}
+
+fn time_bar_ui(ui: &mut egui::Ui, time: Option<&Range<f64>>, frame_range: Option<&Range<f64>>) {
+    let height = ui.text_style_height(&egui::TextStyle::Body);
+    let (rect, _) =
+        ui.allocate_exact_size(egui::vec2(TIME_BAR_WIDTH, height), egui::Sense::hover());
+
+    let background_color = ui.visuals().extreme_bg_color;
+    let bar_color = ui.visuals().selection.bg_fill;
+    let painter = ui.painter();
+    painter.rect_filled(rect, 0.0, background_color);
+
+    let (Some(time), Some(frame_range)) = (time, frame_range) else {
+        return;
+    };
+    let frame_duration = frame_range.end - frame_range.start;
+    if frame_duration <= 0.0 {
+        painter.rect_filled(rect, 0.0, bar_color);
+        return;
+    }
+    let to_x = |t: f64| {
+        let fraction = ((t - frame_range.start) / frame_duration).clamp(0.0, 1.0) as f32;
+        rect.left() + fraction * rect.width()
+    };
+    // Make sure that very short scopes are still visible.
+    let start_x = to_x(time.start);
+    let end_x = to_x(time.end).max(start_x + 1.0);
+    painter.rect_filled(
+        egui::Rect::from_x_y_ranges(start_x..=end_x, rect.y_range()),
+        0.0,
+        bar_color,
+    );
+}
@@ -12,6 +12,10 @@ pub enum CreationError {
     #[cfg(feature = "tracy")]
     #[error("Failed to create Tracy GPU context: {0}")]
     TracyGpuContextCreationError(#[from] tracy_client::GpuContextCreationError),
+
+    #[cfg(feature = "tracy")]
+    #[error("Failed to read back the timestamp for calibrating the Tracy GPU context: {0}")]
+    TracyCalibrationReadbackFailed(wgpu::BufferAsyncError),
 }
 
 #[cfg(feature = "tracy")]
@@ -33,6 +37,9 @@ impl PartialEq for CreationError {
                     )
                 ),
             },
+            CreationError::TracyCalibrationReadbackFailed(_) => {
+                matches!(other, CreationError::TracyCalibrationReadbackFailed(_))
+            }
         }
     }
 }
@@ -44,13 +51,54 @@ impl Eq for CreationError {}
 pub enum SettingsError {
     #[error("GpuProfilerSettings::max_num_pending_frames must be at least 1.")]
     InvalidMaxNumPendingFrames,
+
+    #[error("GpuProfilerSettings::min_pool_capacity must be even, greater than 0 and at most wgpu::QUERY_SET_MAX_QUERIES.")]
+    InvalidMinPoolCapacity,
+
+    #[error("GpuProfilerSettings::pool_size_decay must be between 0.0 and 1.0.")]
+    InvalidPoolSizeDecay,
+
+    #[error("GpuProfilerSettings::additional_resolve_buffer_usages must not contain map usages and GpuProfilerSettings::additional_read_buffer_usages must not contain MAP_WRITE.")]
+    InvalidBufferUsages,
+
+    #[error("GpuProfilerSettings::sample_every_n_frames must be greater than 0.")]
+    InvalidSampleEveryNFrames,
+}
+
+/// A scope that was still open when ending a frame, see [`EndFrameError::UnclosedQueries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnclosedScopeInfo {
+    /// Label the scope was opened with.
+    pub label: String,
+
+    /// Source location the scope was opened at, if known.
+    ///
+    /// Scopes opened via methods that track their caller report the location of the user's call site.
+    pub location: Option<&'static std::panic::Location<'static>>,
+}
+
+impl std::fmt::Display for UnclosedScopeInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{:?} opened at {location}", self.label),
+            None => write!(f, "{:?}", self.label),
+        }
+    }
+}
+
+fn format_unclosed_scopes(scopes: &[UnclosedScopeInfo]) -> String {
+    scopes
+        .iter()
+        .map(|scope| format!("\n{scope} is still open."))
+        .collect()
 }
 
 /// Errors that can occur during [`crate::GpuProfiler::end_frame`].
 #[derive(thiserror::Error, Debug, PartialEq, Eq)]
 pub enum EndFrameError {
-    #[error("All profiling queries need to be closed before ending a frame. There were still {0} open queries.")]
-    UnclosedQueries(u32),
+    /// Contains the number of open queries and, in debug builds, information about each of them.
+    #[error("All profiling queries need to be closed before ending a frame. There were still {0} open queries.{}", format_unclosed_scopes(.1))]
+    UnclosedQueries(u32, Vec<UnclosedScopeInfo>),
 
     #[error(
         "Not all queries were resolved before ending a frame.\n
@@ -58,4 +106,25 @@ Call `GpuProfiler::resolve_queries` after all profiling queries have been closed
 There were still {0} queries unresolved."
     )]
     UnresolvedQueries(u32),
+
+    #[error(
+        "The last query resolve of the frame was not submitted via `GpuProfiler::submit` before ending the frame.\n
+Submit the command buffer containing the resolve before calling `GpuProfiler::end_frame`.\n
+The frame was discarded."
+    )]
+    ResolveNotSubmitted,
+
+    #[error(
+        "The last query resolve of the frame was not submitted last.\n
+There were {0} submissions after the one containing the resolve, which may contain queries whose timings are then invalid.\n
+Call `GpuProfiler::resolve_queries` on a command buffer that is submitted after all others of the frame.\n
+The frame was discarded."
+    )]
+    SubmissionsAfterResolve(u32),
+
+    #[error(
+        "There were {0} scopes in the frame, exceeding GpuProfilerSettings::max_num_scopes_per_frame.\n
+The frame was discarded."
+    )]
+    TooManyScopes(u32),
 }
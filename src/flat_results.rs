@@ -0,0 +1,157 @@
+use std::{collections::HashMap, ops::Range, thread::ThreadId};
+
+use crate::{profiler::GpuTimerQueryTreeHandle, ClockCalibration, GpuTimerQueryResult};
+
+/// The result of a gpu timer scope, stored in a flat list of results, see [`GpuTimerQueryResults`].
+///
+/// Same as [`GpuTimerQueryResult`], except that nested results are referenced by index.
+#[derive(Debug, Clone)]
+pub struct GpuTimerQueryResultNode {
+    /// Label that was specified when opening the scope.
+    pub label: String,
+
+    /// The process id of the process that opened this scope.
+    pub pid: u32,
+
+    /// The thread id of the thread that opened this scope.
+    pub tid: ThreadId,
+
+    /// The backend that produced the timings of this scope, see [`GpuTimerQueryResult::backend`].
+    pub backend: Option<wgpu::Backend>,
+
+    /// Calibration for converting times to wall clock time, see [`GpuTimerQueryResult::clock_calibration`].
+    pub clock_calibration: Option<ClockCalibration>,
+
+    /// Time range of this scope in seconds, see [`GpuTimerQueryResult::time`].
+    pub time: Option<Range<f64>>,
+
+    /// Number of work units attached to this scope, see [`GpuTimerQueryResult::unit_count`].
+    pub unit_count: Option<u32>,
+
+    /// Category of this scope, see [`GpuTimerQueryResult::category`].
+    pub category: Option<&'static str>,
+
+    /// Number of timed regions merged into this result, see [`GpuTimerQueryResult::num_samples`].
+    pub num_samples: Option<u32>,
+
+    /// Additional backend specific metrics of this scope, see [`GpuTimerQueryResult::extra_metrics`].
+    pub extra_metrics: HashMap<String, f64>,
+
+    /// Indices of the scopes that were opened while this scope was open.
+    ///
+    /// Use [`GpuTimerQueryResults::nested_queries`] to access them.
+    pub nested_queries: Range<usize>,
+
+    /// Handle of the query this result originates from, used while building the list.
+    #[cfg_attr(feature = "disabled", allow(dead_code))]
+    pub(crate) handle: GpuTimerQueryTreeHandle,
+}
+
+/// Parent-child relation of a result, see [`GpuTimerQueryResults::edges`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultEdge<'a> {
+    /// Index of the parent result in [`GpuTimerQueryResults::nodes`], `None` for roots.
+    pub parent: Option<usize>,
+
+    /// Index of the result in [`GpuTimerQueryResults::nodes`].
+    pub child: usize,
+
+    /// Time range of the result in seconds, see [`GpuTimerQueryResult::time`].
+    pub time: Option<Range<f64>>,
+
+    /// Label of the result.
+    pub label: &'a str,
+}
+
+/// Results of a profiler frame, stored in a single vector.
+/// Returned as [`FinishedFrame::results`](crate::FinishedFrame::results) by [`GpuProfiler::process_finished_frame_flat`].
+/// Returned by [`GpuProfiler::process_finished_frame_flat`].
+/// Results are stored in breadth-first order, with all nested results of a scope stored contiguously.
+/// Unlike a tree of [`GpuTimerQueryResult`], this doesn't need an allocation for every result with nested results.
+///
+/// [`GpuProfiler::process_finished_frame_flat`]: crate::GpuProfiler::process_finished_frame_flat
+#[derive(Debug, Clone, Default)]
+pub struct GpuTimerQueryResults {
+    pub(crate) nodes: Vec<GpuTimerQueryResultNode>,
+    pub(crate) num_roots: usize,
+}
+
+impl GpuTimerQueryResults {
+    /// Results of all scopes that weren't nested in any other scope.
+    pub fn roots(&self) -> &[GpuTimerQueryResultNode] {
+        &self.nodes[..self.num_roots]
+    }
+
+    /// Results of all scopes that were opened while the passed scope was open.
+    ///
+    /// `node` has to be a result of this list.
+    pub fn nested_queries(&self, node: &GpuTimerQueryResultNode) -> &[GpuTimerQueryResultNode] {
+        &self.nodes[node.nested_queries.clone()]
+    }
+
+    /// All results in breadth-first order.
+    pub fn nodes(&self) -> &[GpuTimerQueryResultNode] {
+        &self.nodes
+    }
+
+    /// Total number of results, including nested results.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if there are no results.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Parent-child relations of all results as a list of edges, for visualizations that do their own layout.
+    ///
+    /// Results are identified by their index in [`Self::nodes`].
+    /// Every result has exactly one edge, roots have no parent.
+    /// Edges are in breadth-first order, i.e. a result's edge always comes after the edge of its parent.
+    pub fn edges(&self) -> Vec<ResultEdge<'_>> {
+        let mut edges = (0..self.num_roots)
+            .map(|child| ResultEdge {
+                parent: None,
+                child,
+                time: self.nodes[child].time.clone(),
+                label: &self.nodes[child].label,
+            })
+            .collect::<Vec<_>>();
+        for (parent, node) in self.nodes.iter().enumerate() {
+            edges.extend(node.nested_queries.clone().map(|child| ResultEdge {
+                parent: Some(parent),
+                child,
+                time: self.nodes[child].time.clone(),
+                label: &self.nodes[child].label,
+            }));
+        }
+        edges
+    }
+
+    /// Converts to a tree of [`GpuTimerQueryResult`], as returned by [`GpuProfiler::process_finished_frame`].
+    ///
+    /// [`GpuProfiler::process_finished_frame`]: crate::GpuProfiler::process_finished_frame
+    pub fn to_nested(&self) -> Vec<GpuTimerQueryResult> {
+        self.to_nested_recursive(self.roots())
+    }
+
+    fn to_nested_recursive(&self, nodes: &[GpuTimerQueryResultNode]) -> Vec<GpuTimerQueryResult> {
+        nodes
+            .iter()
+            .map(|node| GpuTimerQueryResult {
+                label: node.label.clone(),
+                pid: node.pid,
+                tid: node.tid,
+                backend: node.backend,
+                clock_calibration: node.clock_calibration,
+                time: node.time.clone(),
+                unit_count: node.unit_count,
+                category: node.category,
+                num_samples: node.num_samples,
+                extra_metrics: node.extra_metrics.clone(),
+                nested_queries: self.to_nested_recursive(self.nested_queries(node)),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,51 @@
+use std::io::Write;
+
+use crate::GpuTimerQueryResult;
+
+/// Writes results in the "folded stacks" format consumed by flamegraph tools like [inferno](https://github.com/jonhoo/inferno).
+///
+/// Emits one line per leaf scope, listing the labels of all enclosing scopes separated by `;`,
+/// followed by the duration of the leaf scope in whole microseconds:
+/// `frame;shadows;cascade0 412`.
+/// Scopes without timing information are skipped, as are all scopes nested in them.
+/// Since `;` and line breaks are part of the format, they are replaced by `_` in labels.
+pub fn write_folded_stacks<W: Write>(
+    mut writer: W,
+    results: &[GpuTimerQueryResult],
+) -> std::io::Result<()> {
+    let mut stack = String::new();
+    for result in results {
+        write_folded_stacks_recursive(&mut writer, &mut stack, result)?;
+    }
+    Ok(())
+}
+
+fn write_folded_stacks_recursive(
+    writer: &mut impl Write,
+    stack: &mut String,
+    result: &GpuTimerQueryResult,
+) -> std::io::Result<()> {
+    let Some(duration_us) = result.duration_us() else {
+        return Ok(());
+    };
+
+    let stack_len = stack.len();
+    if !stack.is_empty() {
+        stack.push(';');
+    }
+    stack.extend(result.label.chars().map(|c| match c {
+        ';' | '\n' | '\r' => '_',
+        c => c,
+    }));
+
+    if result.nested_queries.is_empty() {
+        writeln!(writer, "{} {}", stack, duration_us.round() as u64)?;
+    } else {
+        for nested in &result.nested_queries {
+            write_folded_stacks_recursive(writer, stack, nested)?;
+        }
+    }
+
+    stack.truncate(stack_len);
+    Ok(())
+}
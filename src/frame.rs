@@ -0,0 +1,136 @@
+//! Types describing the lifecycle of a profiler frame.
+
+use crate::{EndFrameError, GpuProfiler, GpuTimerQueryResult, ResolvedRange, SubmitMarker};
+
+/// Information stored with a frame when ending it, see [`GpuProfiler::end_frame_with`].
+///
+/// Everything stored here is returned along with the frame's results in a [`FinishedFrame`].
+#[derive(Debug)]
+pub struct FrameInfo<UserData = ()> {
+    /// Submission index of the command buffer containing the frame's last resolve.
+    ///
+    /// This allows [`GpuProfiler::process_finished_frame_blocking`] to wait for exactly this submission
+    /// instead of everything submitted to the device.
+    pub submission_index: Option<wgpu::SubmissionIndex>,
+
+    /// Label of the frame, e.g. "loading" or "steady state".
+    ///
+    /// Can be written to chrome traces with [`ChromeTraceOptions::frame_label`](crate::chrometrace::ChromeTraceOptions::frame_label).
+    pub label: Option<String>,
+
+    /// Arbitrary data, e.g. the camera position or quality level under which the frame was captured.
+    ///
+    /// The type is chosen when creating the profiler, see [`GpuProfiler::new_with_user_data`].
+    /// The data is dropped if the frame is dropped.
+    pub user_data: Option<UserData>,
+}
+
+impl<UserData> Default for FrameInfo<UserData> {
+    fn default() -> Self {
+        Self {
+            submission_index: None,
+            label: None,
+            user_data: None,
+        }
+    }
+}
+
+/// A processed frame, returned by [`GpuProfiler::process_finished_frame`] and its variants.
+///
+/// Bundles the frame's results with everything else that was recorded for it,
+/// since results usually arrive several frames after the frame was ended.
+#[derive(Debug, Clone)]
+pub struct FinishedFrame<UserData = (), Results = Vec<GpuTimerQueryResult>> {
+    /// Index of the frame, i.e. the number of frames that were ended with [`GpuProfiler::end_frame`] before it.
+    ///
+    /// Since frames may be dropped, indices of consecutive frames are not necessarily consecutive,
+    /// see [`GpuProfiler::take_dropped_frame_indices`] for telling dropped frames apart.
+    pub index: u64,
+
+    /// Label the frame was ended with, see [`FrameInfo::label`].
+    pub label: Option<String>,
+
+    /// Data the frame was ended with, see [`FrameInfo::user_data`].
+    pub user_data: Option<UserData>,
+
+    /// Submit markers recorded with [`GpuProfiler::note_submit`] during the frame.
+    pub submit_markers: Vec<SubmitMarker>,
+
+    /// Timer query results of the frame.
+    pub results: Results,
+}
+
+/// Guard for a single profiler frame, created with [`GpuProfiler::begin_frame`].
+///
+/// Encodes the order of operations of a frame:
+/// open scopes via the guard (it dereferences to the [`GpuProfiler`]), call [`GpuProfilerFrame::resolve`]
+/// on the last encoder of the frame, submit, then finish the frame with [`GpuProfilerFrame::end`].
+///
+/// If the guard is dropped without calling [`GpuProfilerFrame::end`], the frame is ended on drop
+/// and any [`EndFrameError`] is discarded.
+/// The low level methods [`GpuProfiler::resolve_queries`] & [`GpuProfiler::end_frame`] remain available for advanced use.
+#[must_use]
+pub struct GpuProfilerFrame<'a, UserData = ()> {
+    profiler: &'a GpuProfiler<UserData>,
+    resolved: bool,
+    ended: bool,
+}
+
+impl<'a, U> GpuProfilerFrame<'a, U> {
+    #[inline]
+    pub(crate) fn new(profiler: &'a GpuProfiler<U>) -> Self {
+        Self {
+            profiler,
+            resolved: false,
+            ended: false,
+        }
+    }
+
+    /// Records the resolve of all queries of this frame into `encoder`, see [`GpuProfiler::resolve_queries`].
+    ///
+    /// Should be called once, on the last encoder submitted in this frame.
+    /// Resolving again only records commands for queries that were opened since the last resolve.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) -> Vec<ResolvedRange> {
+        self.resolved = true;
+        self.profiler.resolve_queries(encoder)
+    }
+
+    /// Whether [`GpuProfilerFrame::resolve`] was called on this frame.
+    #[inline]
+    pub fn is_resolved(&self) -> bool {
+        self.resolved
+    }
+
+    /// Ends the frame, see [`GpuProfiler::end_frame`].
+    ///
+    /// Needs to be called **after** submitting the encoder passed to [`GpuProfilerFrame::resolve`].
+    pub fn end(self) -> Result<(), EndFrameError> {
+        self.end_with(FrameInfo::default())
+    }
+
+    /// Ends the frame, storing additional information with it, see [`GpuProfiler::end_frame_with`].
+    ///
+    /// Needs to be called **after** submitting the encoder passed to [`GpuProfilerFrame::resolve`].
+    pub fn end_with(mut self, info: FrameInfo<U>) -> Result<(), EndFrameError> {
+        self.ended = true;
+        self.profiler.end_frame_with(info)
+    }
+}
+
+impl<'a, U> std::ops::Deref for GpuProfilerFrame<'a, U> {
+    type Target = GpuProfiler<U>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.profiler
+    }
+}
+
+impl<'a, U> Drop for GpuProfilerFrame<'a, U> {
+    fn drop(&mut self) {
+        if !self.ended {
+            // Errors can't be reported from drop, use `end` to handle them.
+            let _ = self.profiler.end_frame();
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::ops::Range;
+
+use crate::{
+    profiler_query::{gpu_busy_time_of, union_length},
+    GpuTimerQueryResult, GpuTimerQueryResultNode, GpuTimerQueryResults,
+};
+
+/// Compact summary of a processed profiler frame, retained by [`GpuProfiler::recent_history`].
+///
+/// Meant as data source for frame time graphs, without keeping the full result tree around.
+///
+/// [`GpuProfiler::recent_history`]: crate::GpuProfiler::recent_history
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuFrameSummary {
+    /// Index of the frame, i.e. the number of frames ended before it.
+    pub frame_index: u64,
+
+    /// Time in seconds the GPU was busy with any scope of the frame, see [`GpuTimerQueryResult::gpu_busy_time`].
+    pub gpu_time: f64,
+
+    /// Label & duration in seconds of every timed scope that isn't nested in any other scope.
+    pub top_level_scopes: Vec<(String, f64)>,
+}
+
+#[cfg_attr(feature = "disabled", allow(dead_code))]
+impl GpuFrameSummary {
+    pub(crate) fn from_results(frame_index: u64, results: &[GpuTimerQueryResult]) -> Self {
+        Self {
+            frame_index,
+            gpu_time: gpu_busy_time_of(results),
+            top_level_scopes: results
+                .iter()
+                .filter_map(|result| Some((result.label.clone(), result.duration_secs()?)))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn from_flat_results(frame_index: u64, results: &GpuTimerQueryResults) -> Self {
+        let mut ranges = Vec::new();
+        collect_timed_ranges_flat(results, results.roots(), &mut ranges);
+        Self {
+            frame_index,
+            gpu_time: union_length(ranges),
+            top_level_scopes: results
+                .roots()
+                .iter()
+                .filter_map(|node| {
+                    let time = node.time.as_ref()?;
+                    Some((node.label.clone(), time.end - time.start))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "disabled", allow(dead_code))]
+fn collect_timed_ranges_flat(
+    results: &GpuTimerQueryResults,
+    nodes: &[GpuTimerQueryResultNode],
+    ranges: &mut Vec<Range<f64>>,
+) {
+    for node in nodes {
+        if let Some(time) = &node.time {
+            ranges.push(time.clone());
+        } else {
+            collect_timed_ranges_flat(results, results.nested_queries(node), ranges);
+        }
+    }
+}
@@ -34,13 +34,13 @@ use wgpu_profiler::*;
 #        label: None,
 #        layout: None,
 #        module: &cs_module,
-#        entry_point: "main",
+#        entry_point: Some("main"),
 #        compilation_options: wgpu::PipelineCompilationOptions::default(),
 #        cache: None,
 #    });
 // ...
 
-let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
 
 // ...
 
@@ -74,12 +74,12 @@ profiler.resolve_queries(&mut encoder);
 profiler.end_frame().unwrap();
 
 // Retrieving the oldest available frame and writing it out to a chrome trace file.
-if let Some(profiling_data) = profiler.process_finished_frame(queue.get_timestamp_period()) {
+if let Some(frame) = profiler.process_finished_frame(queue.get_timestamp_period()) {
     # let button_pressed = false;
     // You usually want to write to disk only under some condition, e.g. press of a key.
     if button_pressed {
         wgpu_profiler::chrometrace::write_chrometrace(
-            std::path::Path::new("mytrace.json"), &profiling_data);
+            std::path::Path::new("mytrace.json"), &frame.results);
     }
 }
 ```
@@ -100,19 +100,61 @@ On [`GpuProfiler::end_frame`], we memorize the total size of all `QueryPool`s in
 `QueryPool` from finished frames are re-used, unless they are deemed too small.
 */
 
+mod aggregator;
 pub mod chrometrace;
+mod clock_calibration;
+#[cfg(feature = "egui")]
+mod egui_ui;
 mod errors;
+mod flat_results;
+pub mod folded_stacks;
+mod frame;
+mod frame_summary;
+#[cfg(feature = "opentelemetry")]
+pub mod otel;
+#[cfg(feature = "pprof")]
+pub mod pprof;
+#[cfg(not(feature = "disabled"))]
+mod profiler;
+#[cfg(feature = "disabled")]
+#[path = "profiler_disabled.rs"]
 mod profiler;
 mod profiler_command_recorder;
 mod profiler_query;
 mod profiler_settings;
+mod profiler_statistics;
+#[cfg(feature = "profiling")]
+mod profiling_scope;
 mod scope;
-#[cfg(feature = "tracy")]
+pub mod summary;
+#[cfg(feature = "android")]
+pub mod systrace;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+#[cfg(feature = "tracing")]
+pub mod tracing_spans;
+#[cfg(all(feature = "tracy", not(feature = "disabled")))]
 mod tracy;
 
-pub use errors::{CreationError, EndFrameError, SettingsError};
-pub use profiler::GpuProfiler;
+pub use aggregator::{GpuTimerQueryAggregator, ScopeTimingStatistics, LABEL_PATH_SEPARATOR};
+pub use clock_calibration::ClockCalibration;
+#[cfg(feature = "egui")]
+pub use egui_ui::profiler_ui;
+pub use errors::{CreationError, EndFrameError, SettingsError, UnclosedScopeInfo};
+pub use flat_results::{GpuTimerQueryResultNode, GpuTimerQueryResults, ResultEdge};
+pub use frame::{FinishedFrame, FrameInfo, GpuProfilerFrame};
+pub use frame_summary::GpuFrameSummary;
+pub use profiler::{GpuProfiler, ReservedScopes, ResolvedRange};
 pub use profiler_command_recorder::ProfilerCommandRecorder;
-pub use profiler_query::{GpuProfilerQuery, GpuTimerQueryResult};
-pub use profiler_settings::GpuProfilerSettings;
-pub use scope::{ManualOwningScope, OwningScope, Scope};
+pub use profiler_query::{
+    AccumulatingScopeToken, GpuProfilerQuery, GpuTimerQueryResult, OverlapWarning, PassId,
+    RawTimestampWrites, ScopeLabel, SubmitMarker,
+};
+pub use profiler_settings::{GpuProfilerSettings, LabelTransform};
+pub use profiler_statistics::{GpuProfilerStatistics, PendingFrameStatus};
+pub use scope::{DebugScope, ManualOwningScope, OwningScope, Scope, ScopeBatch};
+
+// Used by `profiling_scope!`.
+#[cfg(feature = "profiling")]
+#[doc(hidden)]
+pub use profiling;
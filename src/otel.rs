@@ -0,0 +1,70 @@
+//! Export of results as [OpenTelemetry](https://opentelemetry.io) spans.
+//!
+//! Available with the `opentelemetry` feature.
+
+use std::time::SystemTime;
+
+use opentelemetry::{
+    trace::{SpanBuilder, TraceContextExt as _, Tracer},
+    Context, KeyValue,
+};
+
+use crate::GpuTimerQueryResult;
+
+/// Exports results as OpenTelemetry spans with `tracer`, preserving the parent-child structure of the scopes.
+///
+/// Unlike regular spans that measure their own lifetime, every span is created with explicit start & end timestamps,
+/// derived from the recorded GPU times via [`GpuTimerQueryResult::clock_calibration`].
+/// Top level scopes become children of the currently active span, if any.
+///
+/// Scopes without timing information or without clock calibration can't be placed in time and aren't exported,
+/// their nested scopes become children of the closest exported enclosing scope instead.
+/// Categories and unit counts are exported as the span attributes `wgpu_profiler.category` & `wgpu_profiler.unit_count`.
+pub fn export_otel<T>(results: &[GpuTimerQueryResult], tracer: &T)
+where
+    T: Tracer,
+    T::Span: Send + Sync + 'static,
+{
+    export_otel_recursive(results, tracer, &Context::current());
+}
+
+fn export_otel_recursive<T>(results: &[GpuTimerQueryResult], tracer: &T, parent: &Context)
+where
+    T: Tracer,
+    T::Span: Send + Sync + 'static,
+{
+    for result in results {
+        let Some((start, end)) = system_time_range(result) else {
+            export_otel_recursive(&result.nested_queries, tracer, parent);
+            continue;
+        };
+
+        let mut attributes = Vec::new();
+        if let Some(category) = result.category {
+            attributes.push(KeyValue::new("wgpu_profiler.category", category));
+        }
+        if let Some(unit_count) = result.unit_count {
+            attributes.push(KeyValue::new(
+                "wgpu_profiler.unit_count",
+                i64::from(unit_count),
+            ));
+        }
+
+        let span = SpanBuilder::from_name(result.label.clone())
+            .with_start_time(start)
+            .with_attributes(attributes)
+            .start_with_context(tracer, parent);
+        let context = parent.with_span(span);
+        export_otel_recursive(&result.nested_queries, tracer, &context);
+        context.span().end_with_timestamp(end);
+    }
+}
+
+fn system_time_range(result: &GpuTimerQueryResult) -> Option<(SystemTime, SystemTime)> {
+    let time = result.time.as_ref()?;
+    let clock_calibration = result.clock_calibration?;
+    Some((
+        clock_calibration.to_system_time(time.start)?,
+        clock_calibration.to_system_time(time.end)?,
+    ))
+}
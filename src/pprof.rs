@@ -0,0 +1,149 @@
+//! Export of aggregated timings in the [pprof](https://github.com/google/pprof) `profile.proto` format.
+//!
+//! The protobuf encoding is done by hand, since only a small subset of the format is needed.
+
+use std::{collections::HashMap, fs::File, io::Write, path::Path};
+
+use crate::{GpuTimerQueryAggregator, LABEL_PATH_SEPARATOR};
+
+/// Writes the timings accumulated by `aggregator` as an uncompressed pprof `profile.proto`,
+/// which can be opened with `go tool pprof` or uploaded to pprof compatible backends.
+///
+/// Every recorded label path becomes a stack, with one frame per label.
+/// Each sample has two values: the GPU time in nanoseconds spent in the scope itself,
+/// i.e. its total time minus the total time of its nested scopes, and the number of timed occurrences.
+/// Labels containing [`LABEL_PATH_SEPARATOR`] are split into several frames.
+pub fn write_pprof(target: &Path, aggregator: &GpuTimerQueryAggregator) -> std::io::Result<()> {
+    let mut file = File::create(target)?;
+    file.write_all(&encode_profile(aggregator))
+}
+
+// Field numbers of the messages in `profile.proto`.
+const PROFILE_SAMPLE_TYPE: u32 = 1;
+const PROFILE_SAMPLE: u32 = 2;
+const PROFILE_LOCATION: u32 = 4;
+const PROFILE_FUNCTION: u32 = 5;
+const PROFILE_STRING_TABLE: u32 = 6;
+const VALUE_TYPE_TYPE: u32 = 1;
+const VALUE_TYPE_UNIT: u32 = 2;
+const SAMPLE_LOCATION_ID: u32 = 1;
+const SAMPLE_VALUE: u32 = 2;
+const LOCATION_ID: u32 = 1;
+const LOCATION_LINE: u32 = 4;
+const LINE_FUNCTION_ID: u32 = 1;
+const FUNCTION_ID: u32 = 1;
+const FUNCTION_NAME: u32 = 2;
+
+fn encode_profile(aggregator: &GpuTimerQueryAggregator) -> Vec<u8> {
+    // Time spent in a scope itself is its total minus the totals of its direct children.
+    let mut self_time_by_label_path = aggregator
+        .iter()
+        .map(|(label_path, statistics)| (label_path, statistics.total))
+        .collect::<HashMap<_, _>>();
+    for (label_path, statistics) in aggregator.iter() {
+        if let Some((parent_path, _)) = label_path.rsplit_once(LABEL_PATH_SEPARATOR) {
+            if let Some(parent_self_time) = self_time_by_label_path.get_mut(parent_path) {
+                *parent_self_time -= statistics.total;
+            }
+        }
+    }
+
+    // Index 0 of the string table has to be the empty string.
+    let mut strings = StringTable::default();
+    strings.index("");
+
+    let mut profile = Vec::new();
+    for (type_, unit) in [("gpu_time", "nanoseconds"), ("samples", "count")] {
+        let mut value_type = Vec::new();
+        write_varint_field(&mut value_type, VALUE_TYPE_TYPE, strings.index(type_));
+        write_varint_field(&mut value_type, VALUE_TYPE_UNIT, strings.index(unit));
+        write_bytes_field(&mut profile, PROFILE_SAMPLE_TYPE, &value_type);
+    }
+
+    // One function & location per distinct label, ids have to be non-zero.
+    let mut location_id_by_label = HashMap::<&str, u64>::new();
+    for (label_path, statistics) in aggregator.iter() {
+        let mut location_ids = Vec::new();
+        for label in label_path.split(LABEL_PATH_SEPARATOR) {
+            let next_id = location_id_by_label.len() as u64 + 1;
+            let id = *location_id_by_label.entry(label).or_insert_with(|| {
+                let mut function = Vec::new();
+                write_varint_field(&mut function, FUNCTION_ID, next_id);
+                write_varint_field(&mut function, FUNCTION_NAME, strings.index(label));
+                write_bytes_field(&mut profile, PROFILE_FUNCTION, &function);
+
+                let mut line = Vec::new();
+                write_varint_field(&mut line, LINE_FUNCTION_ID, next_id);
+                let mut location = Vec::new();
+                write_varint_field(&mut location, LOCATION_ID, next_id);
+                write_bytes_field(&mut location, LOCATION_LINE, &line);
+                write_bytes_field(&mut profile, PROFILE_LOCATION, &location);
+
+                next_id
+            });
+            location_ids.push(id);
+        }
+        // Stacks are stored leaf first.
+        location_ids.reverse();
+
+        let self_time_ns = (self_time_by_label_path[label_path].max(0.0) * 1e9).round() as i64;
+        let mut sample = Vec::new();
+        write_packed_field(&mut sample, SAMPLE_LOCATION_ID, location_ids);
+        write_packed_field(
+            &mut sample,
+            SAMPLE_VALUE,
+            [self_time_ns as u64, statistics.num_samples],
+        );
+        write_bytes_field(&mut profile, PROFILE_SAMPLE, &sample);
+    }
+
+    for string in strings.strings {
+        write_bytes_field(&mut profile, PROFILE_STRING_TABLE, string.as_bytes());
+    }
+
+    profile
+}
+
+#[derive(Default)]
+struct StringTable<'a> {
+    strings: Vec<&'a str>,
+    index_by_string: HashMap<&'a str, u64>,
+}
+
+impl<'a> StringTable<'a> {
+    fn index(&mut self, string: &'a str) -> u64 {
+        *self.index_by_string.entry(string).or_insert_with(|| {
+            self.strings.push(string);
+            self.strings.len() as u64 - 1
+        })
+    }
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn write_varint_field(buffer: &mut Vec<u8>, field: u32, value: u64) {
+    // Wire type 0: varint
+    write_varint(buffer, u64::from(field) << 3);
+    write_varint(buffer, value);
+}
+
+fn write_bytes_field(buffer: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    // Wire type 2: length delimited
+    write_varint(buffer, (u64::from(field) << 3) | 2);
+    write_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+fn write_packed_field(buffer: &mut Vec<u8>, field: u32, values: impl IntoIterator<Item = u64>) {
+    let mut packed = Vec::new();
+    for value in values {
+        write_varint(&mut packed, value);
+    }
+    write_bytes_field(buffer, field, &packed);
+}
@@ -1,16 +1,25 @@
 use std::{
-    collections::HashMap,
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+    ops::Range,
     sync::{
-        atomic::{AtomicU32, Ordering},
-        Arc,
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, OnceLock, Weak,
     },
+    time::Duration,
 };
 
 use parking_lot::{Mutex, RwLock};
 
 use crate::{
-    CreationError, EndFrameError, GpuProfilerQuery, GpuProfilerSettings, GpuTimerQueryResult,
-    ManualOwningScope, OwningScope, ProfilerCommandRecorder, Scope, SettingsError,
+    flat_results::{GpuTimerQueryResultNode, GpuTimerQueryResults},
+    profiler_query::{LabelResolver, PassId, ScopeLabel},
+    AccumulatingScopeToken, ClockCalibration, CreationError, DebugScope, EndFrameError,
+    FinishedFrame, FrameInfo, GpuFrameSummary, GpuProfilerFrame, GpuProfilerQuery,
+    GpuProfilerSettings, GpuProfilerStatistics, GpuTimerQueryResult, LabelTransform,
+    ManualOwningScope, OwningScope, PendingFrameStatus, ProfilerCommandRecorder, Scope,
+    SettingsError, SubmitMarker, UnclosedScopeInfo,
 };
 
 /// Profiler instance.
@@ -22,19 +31,99 @@ use crate::{
 ///
 /// After the first call that passes [`wgpu::Device`], the same device must be used with all subsequent
 /// calls to [`GpuProfiler`] and all passed references to wgpu objects must originate from that device.
-pub struct GpuProfiler {
+/// In debug builds, passing a device with different features or limits than before panics with a descriptive message.
+/// (wgpu doesn't expose device identities, so different devices with identical features & limits go unnoticed.)
+///
+/// `UserData` is the type of data that can be stored with every frame, see [`GpuProfiler::new_with_user_data`].
+pub struct GpuProfiler<UserData = ()> {
+    /// Unique id of this profiler, used to tell apart queries of different profilers on the same thread.
+    id: u32,
+
+    /// Process id, queried once on creation since it can't change.
+    process_id: u32,
+
+    /// Thread the profiler was created on, used for all queries if [`GpuProfilerSettings::capture_thread_info`] is disabled.
+    creation_thread_id: std::thread::ThreadId,
+
     unused_pools: Vec<QueryPool>,
 
     active_frame: ActiveFrame,
-    pending_frames: Vec<PendingFrame>,
+    pending_frames: Mutex<Vec<PendingFrame<UserData>>>,
 
     num_open_queries: AtomicU32,
+
+    /// Scopes that are currently open, only tracked in debug builds for reporting unclosed scopes.
+    open_scopes: Mutex<Vec<(GpuTimerQueryTreeHandle, UnclosedScopeInfo)>>,
     next_query_handle: AtomicU32,
 
-    size_for_new_query_pools: u32,
+    num_failed_query_pool_creations: AtomicU32,
+
+    /// Number of query pools, i.e. query sets, that are currently alive, see [`GpuProfilerSettings::max_num_query_sets`].
+    num_live_query_sets: Arc<AtomicU32>,
+    num_invalid_timestamps: AtomicU32,
+    num_unwritten_timestamps: AtomicU32,
+    num_merged_reversed_timestamps: AtomicU32,
+
+    /// Total CPU time in nanoseconds spent in the respective methods, if [`GpuProfilerSettings::measure_cpu_overhead`] is enabled.
+    cpu_time_resolve_queries: AtomicU64,
+    cpu_time_end_frame: AtomicU64,
+    cpu_time_process_finished_frame: AtomicU64,
+    num_discarded_referenced_pools: AtomicU32,
+    num_timed_out_frames: AtomicU32,
+    num_dropped_queries_last_frame: AtomicU32,
+
+    size_for_new_query_pools: AtomicU32,
+
+    /// Highest number of queries used in a single frame so far.
+    peak_queries_per_frame: AtomicU32,
+
+    /// Number of queries used in each of the last [`NUM_FRAMES_FOR_POOL_SIZE_HISTORY`] frames.
+    recent_frame_query_counts: Mutex<VecDeque<u32>>,
+
+    /// Number of submissions via [`GpuProfiler::submit`] in the active frame.
+    num_submissions_in_frame: AtomicU32,
+
+    /// Value of `num_submissions_in_frame` at the time of the last resolve in the active frame, if any.
+    last_resolve_submission: Mutex<Option<u32>>,
+
+    /// Number of frames ended so far, used for labeling query pools.
+    frame_index: AtomicU64,
 
     settings: GpuProfilerSettings,
 
+    backend: Option<wgpu::Backend>,
+
+    adapter_info: Option<wgpu::AdapterInfo>,
+
+    clock_calibration: Option<ClockCalibration>,
+
+    /// Indices of frames that were dropped since the last call to [`GpuProfiler::take_dropped_frame_indices`].
+    ///
+    /// Holds at most [`MAX_NUM_DROPPED_FRAME_INDICES`] entries, oldest are forgotten first.
+    dropped_frame_indices: Mutex<VecDeque<u64>>,
+
+    /// Label path hashes of scopes in the last processed frame that exceeded [`GpuProfilerSettings::debug_group_threshold`].
+    slow_scope_label_paths: RwLock<HashSet<u64>>,
+
+    capture: Mutex<Option<FrameCapture>>,
+
+    /// Summaries of the last [`GpuProfilerSettings::history_len`] processed frames, oldest first.
+    history: Mutex<VecDeque<GpuFrameSummary>>,
+
+    label_resolver: Option<Box<LabelResolver>>,
+
+    /// Strings of numeric labels that were needed while opening scopes, resolved once per id.
+    resolved_labels: RwLock<HashMap<u32, String>>,
+
+    /// Names of passes registered with [`GpuProfiler::register_passes`], indexed by [`PassId`].
+    registered_passes: Vec<String>,
+
+    /// Only scopes with this encoder tag are timed if set, see [`GpuProfiler::set_active_encoder_tag`].
+    active_encoder_tag: Option<u32>,
+
+    /// Features & limits of the device passed on first use, used to detect accidental use of a different device in debug builds.
+    device_fingerprint: OnceLock<(wgpu::Features, wgpu::Limits)>,
+
     #[cfg(feature = "tracy")]
     tracy_context: Option<tracy_client::GpuContext>,
 }
@@ -50,49 +139,203 @@ impl GpuProfiler {
     #[deprecated(since = "0.9.0", note = "Use ALL_WGPU_TIMER_FEATURES instead")]
     pub const REQUIRED_WGPU_FEATURES: wgpu::Features = GpuProfiler::ALL_WGPU_TIMER_FEATURES;
 
+    /// Channel of all scopes that don't specify a channel, see [`GpuProfilerSettings::enabled_channels`].
+    pub const DEFAULT_CHANNEL: u32 = 1;
+
     /// Creates a new Profiler object.
     ///
     /// There is nothing preventing the use of several independent profiler objects.
     pub fn new(settings: GpuProfilerSettings) -> Result<Self, CreationError> {
+        Self::new_with_user_data(settings)
+    }
+
+    /// Creates a new profiler and connects to a running Tracy client.
+    ///
+    /// Creates a new Tracy GPU context, which involves a submit to calibrate GPU timestamps.
+    /// Use [`GpuProfiler::with_tracy_context`] to use an existing context instead.
+    #[cfg(feature = "tracy")]
+    pub fn new_with_tracy_client(
+        settings: GpuProfilerSettings,
+        backend: wgpu::Backend,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Self, CreationError> {
+        let context = crate::tracy::create_tracy_gpu_client(backend, device, queue)?;
+        Ok(Self::with_tracy_context(settings, context)?.with_backend(backend))
+    }
+
+    /// Like [`GpuProfiler::new_with_tracy_client`], but doesn't block on the device while calibrating GPU timestamps.
+    ///
+    /// Instead, awaits the readback of the calibration timestamp, yielding to the executor in the meantime.
+    /// Note that on native backends, the readback only completes once the device is polled,
+    /// so something else needs to call [`wgpu::Device::poll`] while awaiting, e.g. a dedicated polling thread.
+    #[cfg(feature = "tracy")]
+    pub async fn new_with_tracy_client_async(
+        settings: GpuProfilerSettings,
+        backend: wgpu::Backend,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Self, CreationError> {
+        let context = crate::tracy::create_tracy_gpu_client_async(backend, device, queue).await?;
+        Ok(Self::with_tracy_context(settings, context)?.with_backend(backend))
+    }
+
+    /// Creates a new profiler that reports to an existing Tracy GPU context.
+    ///
+    /// Allows sharing a single Tracy GPU track between several profilers
+    /// or with other parts of the application.
+    /// Unlike [`GpuProfiler::new_with_tracy_client`], this doesn't know the backend,
+    /// use [`GpuProfiler::with_backend`] to set it.
+    #[cfg(feature = "tracy")]
+    pub fn with_tracy_context(
+        settings: GpuProfilerSettings,
+        context: tracy_client::GpuContext,
+    ) -> Result<Self, CreationError> {
+        let mut profiler = Self::new(settings)?;
+        profiler.tracy_context = Some(context);
+        Ok(profiler)
+    }
+}
+
+impl<U> GpuProfiler<U> {
+    /// Like [`GpuProfiler::new`], but creates a profiler that stores user data of type `U` with every frame.
+    ///
+    /// The data is passed in [`FrameInfo::user_data`] when ending a frame
+    /// and returned in [`FinishedFrame::user_data`] along with the frame's results.
+    pub fn new_with_user_data(settings: GpuProfilerSettings) -> Result<Self, CreationError> {
         settings.validate()?;
 
         let (closed_scope_sender, closed_scope_receiver) = std::sync::mpsc::channel();
 
-        Ok(GpuProfiler {
+        Ok(Self {
+            id: NEXT_PROFILER_ID.fetch_add(1, Ordering::Relaxed),
+            process_id: if cfg!(target_arch = "wasm32") {
+                0
+            } else {
+                std::process::id()
+            },
+            creation_thread_id: std::thread::current().id(),
+
             unused_pools: Vec::new(),
 
-            pending_frames: Vec::with_capacity(settings.max_num_pending_frames),
+            pending_frames: Mutex::new(Vec::with_capacity(settings.max_num_pending_frames)),
             active_frame: ActiveFrame {
                 query_pools: RwLock::new(PendingFramePools::default()),
                 closed_query_sender: closed_scope_sender,
                 closed_query_receiver: Mutex::new(closed_scope_receiver),
+                num_closed_queries: AtomicU32::new(0),
+                num_ending_frames: AtomicU32::new(0),
+                submit_markers: Mutex::new(Vec::new()),
             },
 
             num_open_queries: AtomicU32::new(0),
+            open_scopes: Mutex::new(Vec::new()),
             next_query_handle: AtomicU32::new(0),
 
-            size_for_new_query_pools: QueryPool::MIN_CAPACITY,
+            num_failed_query_pool_creations: AtomicU32::new(0),
+            num_live_query_sets: Arc::new(AtomicU32::new(0)),
+            num_invalid_timestamps: AtomicU32::new(0),
+            num_unwritten_timestamps: AtomicU32::new(0),
+            num_merged_reversed_timestamps: AtomicU32::new(0),
+            cpu_time_resolve_queries: AtomicU64::new(0),
+            cpu_time_end_frame: AtomicU64::new(0),
+            cpu_time_process_finished_frame: AtomicU64::new(0),
+            num_discarded_referenced_pools: AtomicU32::new(0),
+            num_timed_out_frames: AtomicU32::new(0),
+            num_dropped_queries_last_frame: AtomicU32::new(0),
+
+            size_for_new_query_pools: AtomicU32::new(settings.min_pool_capacity),
+            peak_queries_per_frame: AtomicU32::new(0),
+            recent_frame_query_counts: Mutex::new(VecDeque::with_capacity(
+                NUM_FRAMES_FOR_POOL_SIZE_HISTORY,
+            )),
+            num_submissions_in_frame: AtomicU32::new(0),
+            last_resolve_submission: Mutex::new(None),
+
+            frame_index: AtomicU64::new(0),
 
             settings,
 
+            backend: None,
+            clock_calibration: None,
+            adapter_info: None,
+            dropped_frame_indices: Mutex::new(VecDeque::new()),
+            slow_scope_label_paths: RwLock::new(HashSet::new()),
+
+            capture: Mutex::new(None),
+            history: Mutex::new(VecDeque::new()),
+
+            label_resolver: None,
+            resolved_labels: RwLock::new(HashMap::new()),
+            registered_passes: Vec::new(),
+            active_encoder_tag: None,
+
+            device_fingerprint: OnceLock::new(),
+
             #[cfg(feature = "tracy")]
             tracy_context: None,
         })
     }
 
-    /// Creates a new profiler and connects to a running Tracy client.
-    #[cfg(feature = "tracy")]
-    pub fn new_with_tracy_client(
-        settings: GpuProfilerSettings,
-        backend: wgpu::Backend,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-    ) -> Result<Self, CreationError> {
-        let mut profiler = Self::new(settings)?;
-        profiler.tracy_context = Some(crate::tracy::create_tracy_gpu_client(
-            backend, device, queue,
-        )?);
-        Ok(profiler)
+    /// Sets the backend that all timings of this profiler originate from.
+    ///
+    /// Timestamp semantics may vary between backends, so results carry the backend along in
+    /// [`GpuTimerQueryResult::backend`]. Pass the result of `wgpu::Adapter::get_info().backend`.
+    #[must_use]
+    pub fn with_backend(mut self, backend: wgpu::Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// The backend set via [`GpuProfiler::with_backend`] or `GpuProfiler::new_with_tracy_client`, if any.
+    pub fn backend(&self) -> Option<wgpu::Backend> {
+        self.backend
+    }
+
+    /// Resolution in seconds that timestamps are known to be quantized to, based on the backend set via [`GpuProfiler::with_backend`].
+    ///
+    /// Browsers quantize timestamps of [`wgpu::Backend::BrowserWebGpu`] to 100µs or coarser as a mitigation against timing attacks,
+    /// so shorter scopes show up with zero duration or a multiple of the resolution.
+    /// Use this to e.g. warn users that short scopes are unreliable.
+    ///
+    /// Returns `None` if the backend isn't known or doesn't quantize timestamps beyond their usual precision.
+    pub fn timestamp_resolution_hint(&self) -> Option<f64> {
+        match self.backend? {
+            wgpu::Backend::BrowserWebGpu => Some(BROWSER_WEBGPU_TIMESTAMP_RESOLUTION),
+            _ => None,
+        }
+    }
+
+    /// Sets the calibration used for converting result times to wall clock time.
+    ///
+    /// Results processed afterwards carry the calibration along, see [`GpuTimerQueryResult::absolute_time`].
+    /// Use [`ClockCalibration::measure`] to create one. Since GPU and system clock drift apart,
+    /// long running applications should call this periodically with a fresh calibration.
+    pub fn set_clock_calibration(&mut self, clock_calibration: ClockCalibration) {
+        self.clock_calibration = Some(clock_calibration);
+    }
+
+    /// The calibration set via [`GpuProfiler::set_clock_calibration`], if any.
+    pub fn clock_calibration(&self) -> Option<ClockCalibration> {
+        self.clock_calibration
+    }
+
+    /// Sets information about the adapter that all timings of this profiler originate from.
+    ///
+    /// Also sets the backend, see [`GpuProfiler::with_backend`].
+    /// Pass the result of `wgpu::Adapter::get_info()`.
+    /// This makes traces comparable across machines,
+    /// see [`ChromeTraceOptions::adapter_info`](crate::chrometrace::ChromeTraceOptions::adapter_info).
+    #[must_use]
+    pub fn with_adapter_info(mut self, adapter_info: wgpu::AdapterInfo) -> Self {
+        self.backend = Some(adapter_info.backend);
+        self.adapter_info = Some(adapter_info);
+        self
+    }
+
+    /// The adapter information set via [`GpuProfiler::with_adapter_info`], if any.
+    pub fn adapter_info(&self) -> Option<&wgpu::AdapterInfo> {
+        self.adapter_info.as_ref()
     }
 
     /// Changes the settings of an existing profiler.
@@ -102,16 +345,155 @@ impl GpuProfiler {
     /// but unused query sets and buffers will be deallocated during [`Self::process_finished_frame`].
     /// Similarly, any opened debugging scope will still be closed if debug groups are disabled by setting
     /// [`GpuProfilerSettings::enable_debug_groups`] to false.
+    ///
+    /// Timer queries may also be disabled or enabled in the middle of a frame, even while queries are open:
+    /// * queries opened before the change keep their timer queries, including their end timestamp, and are resolved & reported as usual,
+    /// * queries opened after disabling don't get timer queries and are reported without timing information,
+    /// * [`Self::resolve_queries`] and [`Self::end_frame`] handle such mixed frames without error.
     pub fn change_settings(&mut self, settings: GpuProfilerSettings) -> Result<(), SettingsError> {
         settings.validate()?;
         if !settings.enable_timer_queries {
             self.unused_pools.clear();
         }
+        if settings.cpu_readback != self.settings.cpu_readback
+            || settings.additional_resolve_buffer_usages
+                != self.settings.additional_resolve_buffer_usages
+            || settings.additional_read_buffer_usages != self.settings.additional_read_buffer_usages
+        {
+            // Cached pools have the wrong set of buffers now.
+            self.active_frame.query_pools.get_mut().unused_pools.clear();
+        }
+        let size_for_new_query_pools = self.size_for_new_query_pools.get_mut();
+        *size_for_new_query_pools = (*size_for_new_query_pools).max(settings.min_pool_capacity);
         self.settings = settings;
 
         Ok(())
     }
 
+    /// Registers a resolver that maps numeric scope labels ([`ScopeLabel::Id`]) to strings.
+    ///
+    /// The resolver is applied when processing finished frames.
+    /// If the string is needed while opening a scope, i.e. for a debug group or a Tracy zone,
+    /// the resolver is called only once per id and its result is cached for the lifetime of the resolver.
+    /// The resolver is therefore expected to always map an id to the same string.
+    /// Without a resolver, numeric labels are formatted as plain numbers.
+    pub fn set_label_resolver(&mut self, resolver: impl Fn(u32) -> String + Send + Sync + 'static) {
+        self.label_resolver = Some(Box::new(resolver));
+        self.resolved_labels.get_mut().clear();
+    }
+
+    /// Registers a fixed list of named passes up front, returning a stable [`PassId`] for each of them.
+    ///
+    /// Scopes can then be opened with a [`PassId`] each frame without any string work.
+    /// Results carry the registered name as their label.
+    ///
+    /// Pass ids are opened as [`ScopeLabel::Pass`], which has an id space separate from numeric labels ([`ScopeLabel::Id`]).
+    /// Registering passes therefore never changes how numeric labels are resolved by
+    /// the resolver registered with [`GpuProfiler::set_label_resolver`].
+    pub fn register_passes(
+        &mut self,
+        names: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Vec<PassId> {
+        names
+            .into_iter()
+            .map(|name| {
+                let id = PassId(self.registered_passes.len() as u32);
+                self.registered_passes.push(name.as_ref().to_owned());
+                id
+            })
+            .collect()
+    }
+
+    /// Returns the name a pass was registered with, if it was registered with this profiler.
+    pub fn pass_name(&self, pass: PassId) -> Option<&str> {
+        self.registered_passes
+            .get(pass.0 as usize)
+            .map(String::as_str)
+    }
+
+    /// Limits profiling to scopes opened with the given encoder tag, see [`GpuProfiler::tagged_scope`].
+    ///
+    /// While a tag is active, all scopes without that tag behave as if [`GpuProfilerSettings::enable_timer_queries`] was false,
+    /// i.e. they still push debug groups but aren't timed.
+    /// This allows profiling a single subsystem's encoders with a profiler that is shared across the entire application.
+    /// `None` (default) times all scopes.
+    pub fn set_active_encoder_tag(&mut self, tag: Option<u32>) {
+        self.active_encoder_tag = tag;
+    }
+
+    /// Encoder tag set with [`GpuProfiler::set_active_encoder_tag`].
+    pub fn active_encoder_tag(&self) -> Option<u32> {
+        self.active_encoder_tag
+    }
+
+    /// Returns statistics about the profiler's internal state.
+    pub fn statistics(&self) -> GpuProfilerStatistics {
+        GpuProfilerStatistics {
+            num_failed_query_pool_creations: self
+                .num_failed_query_pool_creations
+                .load(Ordering::Relaxed),
+            num_invalid_timestamps: self.num_invalid_timestamps.load(Ordering::Relaxed),
+            num_unwritten_timestamps: self.num_unwritten_timestamps.load(Ordering::Relaxed),
+            num_merged_reversed_timestamps: self
+                .num_merged_reversed_timestamps
+                .load(Ordering::Relaxed),
+            num_discarded_referenced_pools: self
+                .num_discarded_referenced_pools
+                .load(Ordering::Relaxed),
+            num_timed_out_frames: self.num_timed_out_frames.load(Ordering::Relaxed),
+            num_dropped_queries_last_frame: self
+                .num_dropped_queries_last_frame
+                .load(Ordering::Relaxed),
+            cpu_time_resolve_queries: Duration::from_nanos(
+                self.cpu_time_resolve_queries.load(Ordering::Relaxed),
+            ),
+            cpu_time_end_frame: Duration::from_nanos(
+                self.cpu_time_end_frame.load(Ordering::Relaxed),
+            ),
+            cpu_time_process_finished_frame: Duration::from_nanos(
+                self.cpu_time_process_finished_frame.load(Ordering::Relaxed),
+            ),
+        }
+    }
+
+    /// Number of scopes & queries that are currently open.
+    ///
+    /// Counts open queries across all threads and encoders, so this is only the nesting depth
+    /// if all scopes are opened on a single thread.
+    /// Checking that this is zero before calling [`GpuProfiler::end_frame`] gives an earlier hint than the
+    /// [`EndFrameError::UnclosedQueries`] error.
+    pub fn open_scope_depth(&self) -> u32 {
+        self.num_open_queries.load(Ordering::Acquire)
+    }
+
+    /// Highest number of timer queries used in a single frame over the lifetime of the profiler.
+    ///
+    /// Every scope with timer queries uses two queries.
+    /// Useful for tuning [`GpuProfilerSettings::min_pool_capacity`]:
+    /// after a representative session, setting it to this value avoids growing query pools in the first frames of future runs.
+    pub fn peak_queries_per_frame(&self) -> u32 {
+        self.peak_queries_per_frame.load(Ordering::Relaxed)
+    }
+
+    /// Status of all frames that haven't been processed yet, oldest first.
+    ///
+    /// Shows how far behind the GPU is, which helps tuning [`GpuProfilerSettings::max_num_pending_frames`].
+    pub fn pending_frame_status(&self) -> Vec<PendingFrameStatus> {
+        self.pending_frames
+            .lock()
+            .iter()
+            .map(|frame| PendingFrameStatus {
+                pools: frame.query_pools.len(),
+                mapped: frame.mapped_buffers.load(Ordering::Acquire) as usize,
+                num_scopes: frame
+                    .closed_query_by_parent_handle
+                    .values()
+                    .map(Vec::len)
+                    .sum(),
+            })
+            .collect()
+    }
+
     /// Starts a new auto-closing profiler scope.
     ///
     /// To nest scopes inside this scope, call [`Scope::scope`] on the returned scope.
@@ -130,11 +512,54 @@ impl GpuProfiler {
     #[inline]
     pub fn scope<'a, Recorder: ProfilerCommandRecorder>(
         &'a self,
-        label: impl Into<String>,
+        label: impl Into<ScopeLabel>,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Scope<'a, Recorder, U> {
+        self.scope_in_channel(label, GpuProfiler::DEFAULT_CHANNEL, encoder_or_pass, device)
+    }
+
+    /// Like [`GpuProfiler::scope`], but the scope is only timed if any of the bits of `channel`
+    /// is set in [`GpuProfilerSettings::enabled_channels`].
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn scope_in_channel<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        label: impl Into<ScopeLabel>,
+        channel: u32,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Scope<'a, Recorder, U> {
+        let scope = self.begin_query_in_channel(label, channel, encoder_or_pass, device);
+        Scope {
+            profiler: self,
+            recorder: encoder_or_pass,
+            scope: Some(scope),
+        }
+    }
+
+    /// Like [`GpuProfiler::scope`], but tags the scope with an encoder tag, see [`GpuProfiler::set_active_encoder_tag`].
+    ///
+    /// The tag is inherited by all scopes & passes nested within the returned scope via its methods,
+    /// e.g. [`Scope::scope`] or [`Scope::scoped_render_pass`], regardless of which encoder or pass they are opened on.
+    /// Queries opened with [`GpuProfiler::begin_query`] and nested via [`GpuProfilerQuery::with_parent`] don't inherit the tag,
+    /// since whether they are timed is decided before their parent is known.
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn tagged_scope<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        tag: u32,
+        label: impl Into<ScopeLabel>,
         encoder_or_pass: &'a mut Recorder,
         device: &wgpu::Device,
-    ) -> Scope<'a, Recorder> {
-        let scope = self.begin_query(label, encoder_or_pass, device);
+    ) -> Scope<'a, Recorder, U> {
+        let options = QueryOptions {
+            encoder_tag: Some(tag),
+            ..Default::default()
+        };
+        let scope = self.begin_encoder_or_pass_query(label, options, encoder_or_pass, device);
         Scope {
             profiler: self,
             recorder: encoder_or_pass,
@@ -160,10 +585,10 @@ impl GpuProfiler {
     #[inline]
     pub fn owning_scope<'a, Recorder: ProfilerCommandRecorder>(
         &'a self,
-        label: impl Into<String>,
+        label: impl Into<ScopeLabel>,
         mut encoder_or_pass: Recorder,
         device: &wgpu::Device,
-    ) -> OwningScope<'a, Recorder> {
+    ) -> OwningScope<'a, Recorder, U> {
         let scope = self.begin_query(label, &mut encoder_or_pass, device);
         OwningScope {
             profiler: self,
@@ -193,10 +618,10 @@ impl GpuProfiler {
     #[inline]
     pub fn manual_owning_scope<'a, Recorder: ProfilerCommandRecorder>(
         &'a self,
-        label: impl Into<String>,
+        label: impl Into<ScopeLabel>,
         mut encoder_or_pass: Recorder,
         device: &wgpu::Device,
-    ) -> ManualOwningScope<'a, Recorder> {
+    ) -> ManualOwningScope<'a, Recorder, U> {
         let scope = self.begin_query(label, &mut encoder_or_pass, device);
         ManualOwningScope {
             profiler: self,
@@ -205,6 +630,71 @@ impl GpuProfiler {
         }
     }
 
+    /// Starts a new scope that only emits a debug group on the passed encoder or rendering/compute pass.
+    ///
+    /// Unlike [`GpuProfiler::scope`], this never touches any query pools, regardless of the profiler settings,
+    /// and pushes the debug group even if [`GpuProfilerSettings::enable_debug_groups`] is false.
+    /// Useful for always-on labeling in tools like [RenderDoc](https://renderdoc.org/) with opt-in timing.
+    ///
+    /// The debug group is popped when the returned scope is dropped.
+    #[must_use]
+    #[inline]
+    pub fn debug_scope<'a, Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: &str,
+        encoder_or_pass: &'a mut Recorder,
+    ) -> DebugScope<'a, Recorder> {
+        encoder_or_pass.push_debug_group(label);
+        DebugScope {
+            recorder: encoder_or_pass,
+        }
+    }
+
+    /// Creates a token for a logical scope that is recorded in several disjoint regions within a frame.
+    ///
+    /// Every query started with [`GpuProfiler::begin_accumulating_query`] for this token is merged into a single
+    /// result entry whose duration is the sum of all regions.
+    /// The token can be reused for any number of regions and frames.
+    pub fn accumulating_scope(&self, label: impl Into<String>) -> AccumulatingScopeToken {
+        AccumulatingScopeToken {
+            label: label.into(),
+            handle: self.next_scope_tree_handle(),
+        }
+    }
+
+    /// Starts a new region of an accumulating scope on the given encoder or rendering/compute pass (if enabled).
+    ///
+    /// Behaves like [`GpuProfiler::begin_query`] and *must* be closed with [`GpuProfiler::end_query`] as well.
+    /// All regions of the same token should be given the same parent via [`GpuProfilerQuery::with_parent`],
+    /// otherwise they are only merged with regions under the same parent.
+    /// Queries nested in any of the regions show up as children of the merged result.
+    #[track_caller]
+    #[must_use]
+    pub fn begin_accumulating_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        token: &AccumulatingScopeToken,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let mut query = self.begin_query(token.label.clone(), encoder_or_pass, device);
+        if query.is_on_thread_stack {
+            replace_open_query_on_thread(self.id, query.handle, token.handle);
+        }
+        if cfg!(debug_assertions) {
+            if let Some((handle, _)) = self
+                .open_scopes
+                .lock()
+                .iter_mut()
+                .find(|(handle, _)| *handle == query.handle)
+            {
+                *handle = token.handle;
+            }
+        }
+        query.handle = token.handle;
+        query.is_accumulating = true;
+        query
+    }
+
     /// Starts a new profiler query on the given encoder or rendering/compute pass (if enabled).
     ///
     /// The returned query *must* be closed by calling [`GpuProfiler::end_query`] with the same encoder/pass,
@@ -222,13 +712,123 @@ impl GpuProfiler {
     #[must_use]
     pub fn begin_query<Recorder: ProfilerCommandRecorder>(
         &self,
-        label: impl Into<String>,
+        label: impl Into<ScopeLabel>,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.begin_query_in_channel(label, GpuProfiler::DEFAULT_CHANNEL, encoder_or_pass, device)
+    }
+
+    /// Like [`GpuProfiler::begin_query`], but the query is only timed if any of the bits of `channel`
+    /// is set in [`GpuProfilerSettings::enabled_channels`].
+    ///
+    /// Queries of disabled channels behave as if [`GpuProfilerSettings::enable_timer_queries`] was false,
+    /// i.e. they still push debug groups and *must* be closed with [`GpuProfiler::end_query`].
+    #[track_caller]
+    #[must_use]
+    pub fn begin_query_in_channel<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<ScopeLabel>,
+        channel: u32,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let options = QueryOptions {
+            channel,
+            ..Default::default()
+        };
+        self.begin_encoder_or_pass_query(label, options, encoder_or_pass, device)
+    }
+
+    /// Reserves timer queries for up to `num_scopes` scopes with a single atomic operation.
+    ///
+    /// Scopes opened with [`GpuProfiler::begin_reserved_query`] take their queries from the returned reservation,
+    /// which avoids contention on the shared query pool when many scopes are opened from several threads at once.
+    /// Fewer queries than requested are reserved if the current query pool is nearly exhausted,
+    /// scopes beyond the reservation fall back to reserving their queries individually.
+    ///
+    /// A reservation is only valid in the profiler frame it was made in, it is empty after [`GpuProfiler::end_frame`].
+    /// Reserved queries that are never used are not given back, they stay unwritten and don't show up in any results.
+    pub fn reserve_scopes(&self, num_scopes: u32, device: &wgpu::Device) -> ReservedScopes {
+        let mut reserved = ReservedScopes {
+            profiler_id: self.id,
+            frame_index: self.frame_index.load(Ordering::Relaxed),
+            pool: Weak::new(),
+            query_indices: 0..0,
+        };
+        if !self.settings.enable_timer_queries
+            || num_scopes == 0
+            || !device.features().contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            return reserved;
+        }
+
+        {
+            let query_pools = self.active_frame.query_pools.read();
+            if let Some(pool) = query_pools.used_pools.last() {
+                if let Some(query_indices) = Self::try_reserve_query_pairs(pool, num_scopes) {
+                    reserved.pool = Arc::downgrade(pool);
+                    reserved.query_indices = query_indices;
+                    return reserved;
+                }
+            }
+        }
+
+        // The last pool is exhausted, let the regular path add a new pool and grow the reservation from there.
+        if let Some(pair) = self.reserve_query_pair(device) {
+            let start = pair.start_query_idx;
+            let mut end = start + 2;
+            let requested_end = start
+                .saturating_add(num_scopes.saturating_mul(2))
+                .min(pair.pool.capacity);
+            // Only succeeds if nobody else reserved queries from the new pool in the meantime.
+            if requested_end > end
+                && pair
+                    .pool
+                    .num_used_queries
+                    .compare_exchange(end, requested_end, Ordering::Release, Ordering::Relaxed)
+                    .is_ok()
+            {
+                end = requested_end;
+            }
+            reserved.pool = Arc::downgrade(&pair.pool);
+            reserved.query_indices = start..end;
+        }
+        reserved
+    }
+
+    /// Like [`GpuProfiler::begin_query`], but takes the timer queries from a reservation made with [`GpuProfiler::reserve_scopes`].
+    ///
+    /// If the reservation is used up or stems from a previous frame, queries are reserved individually as usual.
+    /// The returned query *must* be closed by calling [`GpuProfiler::end_query`] with the same encoder/pass.
+    #[track_caller]
+    #[must_use]
+    pub fn begin_reserved_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        reserved: &mut ReservedScopes,
+        label: impl Into<ScopeLabel>,
+        encoder_or_pass: &mut Recorder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let options = QueryOptions {
+            reserved: Some(reserved),
+            ..Default::default()
+        };
+        self.begin_encoder_or_pass_query(label, options, encoder_or_pass, device)
+    }
+
+    #[track_caller]
+    pub(crate) fn begin_encoder_or_pass_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<ScopeLabel>,
+        options: QueryOptions<'_>,
         encoder_or_pass: &mut Recorder,
         device: &wgpu::Device,
     ) -> GpuProfilerQuery {
         let is_for_pass_timestamp_writes = false;
         let mut query = self.begin_query_internal(
             label.into(),
+            options,
             is_for_pass_timestamp_writes,
             encoder_or_pass,
             device,
@@ -239,13 +839,28 @@ impl GpuProfiler {
             timer_query.usage_state = QueryPairUsageState::OnlyStartWritten;
         };
 
-        if self.settings.enable_debug_groups {
-            encoder_or_pass.push_debug_group(&query.label);
+        if self.settings.enable_debug_groups && self.wants_debug_group(&query) {
+            self.with_label_str(&query, |label| encoder_or_pass.push_debug_group(label));
             query.has_debug_group = true;
         }
         query
     }
 
+    fn wants_debug_group(&self, query: &GpuProfilerQuery) -> bool {
+        if self.settings.debug_group_threshold.is_none() {
+            return true;
+        }
+        self.slow_scope_label_paths
+            .read()
+            .contains(&query.label_path_hash)
+    }
+
+    fn update_slow_scope_label_paths(&self, context: &ResultProcessingContext<'_>) {
+        if self.settings.debug_group_threshold.is_some() {
+            *self.slow_scope_label_paths.write() = context.slow_scope_label_paths.take();
+        }
+    }
+
     /// Starts a new profiler query to be used for render/compute pass timestamp writes.
     ///
     /// The returned query *must* be closed by calling [`GpuProfiler::end_query`], even if timer queries are disabled.
@@ -259,24 +874,122 @@ impl GpuProfiler {
     /// Unlike [`GpuProfiler::begin_query`] this will not create a debug scope,
     /// in order to not force passing of the same encoder/pass to [`GpuProfiler::end_query`].
     /// (this is needed to relax resource tracking requirements a bit, making it easier to implement the automatic scopes)
+    #[track_caller]
     pub fn begin_pass_query(
         &self,
-        label: impl Into<String>,
+        label: impl Into<ScopeLabel>,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.begin_pass_query_in_channel(label, GpuProfiler::DEFAULT_CHANNEL, encoder, device)
+    }
+
+    /// Like [`GpuProfiler::begin_pass_query`], but the query is only timed if any of the bits of `channel`
+    /// is set in [`GpuProfilerSettings::enabled_channels`].
+    #[track_caller]
+    pub fn begin_pass_query_in_channel(
+        &self,
+        label: impl Into<ScopeLabel>,
+        channel: u32,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let options = QueryOptions {
+            channel,
+            ..Default::default()
+        };
+        self.begin_pass_query_with_options(label, options, encoder, device)
+    }
+
+    #[track_caller]
+    pub(crate) fn begin_pass_query_with_options(
+        &self,
+        label: impl Into<ScopeLabel>,
+        options: QueryOptions<'_>,
         encoder: &mut wgpu::CommandEncoder,
         device: &wgpu::Device,
     ) -> GpuProfilerQuery {
         let is_for_pass_timestamp_writes = true;
-        let mut query =
-            self.begin_query_internal(label.into(), is_for_pass_timestamp_writes, encoder, device);
+        let mut query = self.begin_query_internal(
+            label.into(),
+            options,
+            is_for_pass_timestamp_writes,
+            encoder,
+            device,
+        );
         if let Some(timer_query) = &mut query.timer_query_pair {
             timer_query.usage_state = QueryPairUsageState::ReservedForPassTimestampWrites;
         }
         query
     }
 
-    /// Ends passed query.
+    /// Starts a new profiler query whose timestamps are written manually, e.g. around commands recorded by code you don't control.
     ///
-    /// If the passed query was opened with [`GpuProfiler::begin_query`], the passed encoder or pass must be the same
+    /// Call [`GpuProfilerQuery::raw_timestamp_writes`] to get the query set and indices,
+    /// then write the start & end timestamp yourself with [`wgpu::CommandEncoder::write_timestamp`].
+    /// Both timestamps have to be written before the queries of the frame are resolved, otherwise results are undefined.
+    /// The returned query *must* be closed by calling [`GpuProfiler::end_raw_query`], even if timer queries are disabled.
+    ///
+    /// `encoder` is the encoder the timestamps are going to be written to, no commands are recorded on it.
+    /// If the [`wgpu::Device`] does not support [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS`], no gpu timer will be reserved.
+    /// No debug group is pushed for raw queries.
+    #[track_caller]
+    pub fn begin_raw_query(
+        &self,
+        label: impl Into<ScopeLabel>,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        let is_for_pass_timestamp_writes = false;
+        let mut query = self.begin_query_internal(
+            label.into(),
+            QueryOptions::default(),
+            is_for_pass_timestamp_writes,
+            encoder,
+            device,
+        );
+        if let Some(timer_query) = &mut query.timer_query_pair {
+            timer_query.usage_state = QueryPairUsageState::ReservedForRawTimestampWrites;
+        }
+        query
+    }
+
+    /// Ends a query started with [`GpuProfiler::begin_raw_query`].
+    ///
+    /// Doesn't record any commands, the timestamps are expected to have been written already.
+    pub fn end_raw_query(&self, mut query: GpuProfilerQuery) {
+        if let Some(timer_query) = &mut query.timer_query_pair {
+            debug_assert!(
+                timer_query.usage_state == QueryPairUsageState::ReservedForRawTimestampWrites,
+                "Query wasn't started with GpuProfiler::begin_raw_query"
+            );
+            timer_query.usage_state = QueryPairUsageState::BothStartAndEndWritten;
+        }
+        debug_assert!(!query.has_debug_group);
+        self.close_query(query);
+    }
+
+    /// Ends passed query, replacing the label it was opened with.
+    ///
+    /// Useful for labeling the same region differently depending on parameters only known once the commands were recorded,
+    /// e.g. for A/B comparisons or parameter sweeps.
+    /// The new label is only used for the results of the query,
+    /// debug groups and tracy zones keep the label the query was opened with.
+    ///
+    /// Same requirements as for [`GpuProfiler::end_query`] apply.
+    pub fn end_query_labeled<Recorder: ProfilerCommandRecorder>(
+        &self,
+        encoder_or_pass: &mut Recorder,
+        mut query: GpuProfilerQuery,
+        label: impl Into<ScopeLabel>,
+    ) {
+        (query.label, query.label_id, query.pass_id) = label.into().into_parts();
+        self.end_query(encoder_or_pass, query);
+    }
+
+    /// Ends passed query.
+    ///
+    /// If the passed query was opened with [`GpuProfiler::begin_query`], the passed encoder or pass must be the same
     /// as when the query was opened.
     pub fn end_query<Recorder: ProfilerCommandRecorder>(
         &self,
@@ -290,6 +1003,11 @@ impl GpuProfiler {
                 }
                 QueryPairUsageState::ReservedForPassTimestampWrites => {
                     // No need to do a timestamp write, this is handled by wgpu.
+                    timer_query.usage_state = QueryPairUsageState::BothStartAndEndWritten;
+                }
+                QueryPairUsageState::ReservedForRawTimestampWrites => {
+                    // No need to do a timestamp write, this is handled by the user.
+                    timer_query.usage_state = QueryPairUsageState::BothStartAndEndWritten;
                 }
                 QueryPairUsageState::OnlyStartWritten => {
                     encoder_or_pass.write_timestamp(
@@ -304,20 +1022,78 @@ impl GpuProfiler {
             }
         }
 
+        if query.has_debug_group {
+            encoder_or_pass.pop_debug_group();
+        }
+
+        self.close_query(query);
+    }
+
+    /// Closes a query started with [`GpuProfiler::begin_pass_query`] whose pass ended up not being recorded.
+    ///
+    /// The query is excluded from the results, along with any queries nested in it.
+    /// Its reserved timer queries are left unused, so they can't report garbage timings.
+    /// Use this instead of [`GpuProfiler::end_query`] if a pass is skipped conditionally after opening its query.
+    pub fn cancel_pass_query(&self, mut query: GpuProfilerQuery) {
+        debug_assert!(
+            query.timer_query_pair.as_ref().is_none_or(|timer_query| {
+                timer_query.usage_state == QueryPairUsageState::ReservedForPassTimestampWrites
+            }),
+            "Query wasn't started with GpuProfiler::begin_pass_query"
+        );
+        debug_assert!(!query.has_debug_group);
+
+        // The reserved queries are still resolved with all others, but never read.
+        // The query still needs to be processed, since queries may have been nested in it already.
+        query.timer_query_pair = None;
+        query.is_cancelled = true;
+        self.close_query(query);
+    }
+
+    /// Ends the tracy zone of a query and removes it from the thread's open queries.
+    fn release_query(&self, query: &mut GpuProfilerQuery) {
         #[cfg(feature = "tracy")]
         if let Some(ref mut tracy_scope) = query.tracy_scope {
             tracy_scope.end_zone();
         }
 
-        if query.has_debug_group {
-            encoder_or_pass.pop_debug_group();
+        if query.is_on_thread_stack {
+            remove_open_query_on_thread(self.id, query.handle);
+        }
+
+        if cfg!(debug_assertions) {
+            let mut open_scopes = self.open_scopes.lock();
+            if let Some(index) = open_scopes
+                .iter()
+                .position(|(handle, _)| *handle == query.handle)
+            {
+                open_scopes.swap_remove(index);
+            }
         }
+    }
 
-        let send_result = self.active_frame.closed_query_sender.send(query);
+    /// Hands a query whose commands were all recorded over to processing at the end of the frame.
+    fn close_query(&self, mut query: GpuProfilerQuery) {
+        self.release_query(&mut query);
+
+        let num_closed_queries = self
+            .active_frame
+            .num_closed_queries
+            .fetch_add(1, Ordering::Relaxed)
+            .saturating_add(1);
+        // Queries past the limit are dropped right away instead of piling up until the end of the frame,
+        // the frame is going to be discarded anyways.
+        if self
+            .settings
+            .max_num_scopes_per_frame
+            .is_none_or(|max_num_scopes| num_closed_queries <= max_num_scopes)
+        {
+            let send_result = self.active_frame.closed_query_sender.send(query);
 
-        // The only way we can fail sending the query is if the receiver has been dropped.
-        // Since it sits on `active_frame` as well, there's no way for this to happen!
-        debug_assert!(send_result.is_ok());
+            // The only way we can fail sending the query is if the receiver has been dropped.
+            // Since it sits on `active_frame` as well, there's no way for this to happen!
+            debug_assert!(send_result.is_ok());
+        }
 
         // Count queries even if we haven't processed this one, makes experiences more consistent
         // if there's a lack of support for some queries.
@@ -334,53 +1110,101 @@ impl GpuProfiler {
     ///
     /// It is advised to call this only once at the end of a profiling frame, but it is safe to do so several times.
     ///
+    /// Records a query set resolve and a buffer copy for every query pool with unresolved queries.
+    /// Since every pool owns its query set & buffers, these commands can't be merged across pools.
+    /// The number of pools per frame is kept low though: pools grow geometrically within a frame
+    /// and subsequent frames start out with a single pool big enough for all queries of previous frames.
+    /// If a workload still ends up with many pools, e.g. because the first frames open lots of queries from many threads,
+    /// consider raising [`GpuProfilerSettings::min_pool_capacity`].
     ///
-    /// Implementation note:
-    /// This method could be made `&self`, taking the internal lock on the query pools.
-    /// However, the intended use is to call this once at the end of a frame, so we instead
-    /// encourage this explicit sync point and avoid the lock.
-    pub fn resolve_queries(&mut self, encoder: &mut wgpu::CommandEncoder) {
-        let query_pools = self.active_frame.query_pools.get_mut();
+    /// Takes the internal lock on the query pools, so this can be called on a shared profiler,
+    /// but must not race with scopes being opened in the same frame.
+    ///
+    /// Returns the buffer regions written by the commands, one entry per query pool with newly resolved queries.
+    /// This is only needed for advanced use cases like manual synchronization of buffer accesses.
+    pub fn resolve_queries(&self, encoder: &mut wgpu::CommandEncoder) -> Vec<ResolvedRange> {
+        self.measure_cpu_time(&self.cpu_time_resolve_queries, || {
+            self.resolve_queries_internal(encoder, None)
+        })
+    }
 
-        for query_pool in query_pools.used_pools.iter_mut() {
-            // We sync with the last update of num_used_query (which has Release semantics)
-            // mostly to be on the safe side - it happened inside a lock which gives it release semantics anyways
-            // but the concern is that if we don't acquire here, we may miss on other side prior effects of the query begin.
-            let num_used_queries = query_pool.num_used_queries.load(Ordering::Acquire);
-            let num_resolved_queries = query_pool.num_resolved_queries.load(Ordering::Acquire);
+    /// Like [`GpuProfiler::resolve_queries`], but additionally copies all newly resolved raw timestamps into `destination`.
+    ///
+    /// This allows consuming timings on the GPU (e.g. binding `destination` in a shader) without a CPU round-trip.
+    /// Regular results are still produced by [`GpuProfiler::process_finished_frame`].
+    ///
+    /// Ordering guarantees:
+    /// * every timestamp is a little endian `u64` in device ticks,
+    ///   multiply with [`wgpu::Queue::get_timestamp_period`] to get nanoseconds.
+    /// * a query's start & end timestamps are stored consecutively at the indices returned by
+    ///   [`GpuProfilerQuery::frame_timestamp_indices`], i.e. at byte offset `index * wgpu::QUERY_SIZE`.
+    /// * indices are only valid for the profiler frame they were reserved in and get reused in later frames.
+    /// * values are only written once the command buffer containing the resolve has executed;
+    ///   entries that don't belong to a query, or belong to a query not resolved yet, are left untouched.
+    ///
+    /// `destination` requires [`wgpu::BufferUsages::COPY_DST`] and has to be large enough to hold
+    /// all timestamps of the frame, see [`GpuProfiler::num_frame_timestamps`].
+    pub fn resolve_queries_into_buffer(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        destination: &wgpu::Buffer,
+    ) -> Vec<ResolvedRange> {
+        self.measure_cpu_time(&self.cpu_time_resolve_queries, || {
+            self.resolve_queries_internal(encoder, Some(destination))
+        })
+    }
 
-            if num_resolved_queries == num_used_queries {
-                continue;
-            }
+    /// Submits command buffers to the queue, keeping track of the submission order.
+    ///
+    /// Used for validating that query resolves are submitted last, see [`GpuProfilerSettings::validate_submission_order`].
+    /// All command buffers of a profiler frame that contain queries or resolves need to be submitted this way for this to work.
+    pub fn submit<I: IntoIterator<Item = wgpu::CommandBuffer>>(
+        &self,
+        queue: &wgpu::Queue,
+        command_buffers: I,
+    ) -> wgpu::SubmissionIndex {
+        self.num_submissions_in_frame
+            .fetch_add(1, Ordering::Relaxed);
+        queue.submit(command_buffers)
+    }
 
-            debug_assert!(query_pool.capacity >= num_used_queries);
-            debug_assert!(num_resolved_queries < num_used_queries);
+    /// Records a CPU-side marker for a queue submission in the active profiler frame.
+    ///
+    /// Call this right before or after [`wgpu::Queue::submit`] to see submit boundaries relative to GPU scopes,
+    /// e.g. with [`ChromeTraceOptions::submit_markers`](crate::chrometrace::ChromeTraceOptions::submit_markers).
+    /// Markers of a frame are returned in [`FinishedFrame::submit_markers`] once the frame was processed.
+    ///
+    /// Does nothing on wasm32, since there is no way to query the time there.
+    pub fn note_submit(&self, label: impl Into<String>) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        self.active_frame.submit_markers.lock().push(SubmitMarker {
+            label: label.into(),
+            time: std::time::Instant::now(),
+        });
+    }
 
-            // Resolve into offset 0 of the resolve buffer - this way we don't have to worry about
-            // the offset restrictions on resolve buffers (`wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT`)
-            // and we copy it anyways.
-            encoder.resolve_query_set(
-                &query_pool.query_set,
-                num_resolved_queries..num_used_queries,
-                &query_pool.resolve_buffer,
-                0,
-            );
-            // Copy the newly resolved queries into the read buffer, making sure
-            // that we don't override any of the results that are already there.
-            let destination_offset = (num_resolved_queries * wgpu::QUERY_SIZE) as u64;
-            let copy_size = ((num_used_queries - num_resolved_queries) * wgpu::QUERY_SIZE) as u64;
-            encoder.copy_buffer_to_buffer(
-                &query_pool.resolve_buffer,
-                0,
-                &query_pool.read_buffer,
-                destination_offset,
-                copy_size,
-            );
+    /// Number of timestamps reserved in the active profiler frame so far.
+    ///
+    /// Useful for sizing the destination buffer of [`GpuProfiler::resolve_queries_into_buffer`].
+    pub fn num_frame_timestamps(&self) -> u32 {
+        self.active_frame
+            .query_pools
+            .read()
+            .used_pools
+            .last()
+            .map_or(0, |pool| {
+                pool.frame_query_offset + pool.num_used_queries.load(Ordering::Acquire)
+            })
+    }
 
-            query_pool
-                .num_resolved_queries
-                .store(num_used_queries, Ordering::Release);
-        }
+    /// Starts a frame guard that encodes the order of resolving queries and ending the frame.
+    ///
+    /// See [`GpuProfilerFrame`] for details.
+    /// Frames can just as well be driven manually via [`GpuProfiler::resolve_queries`] & [`GpuProfiler::end_frame`].
+    pub fn begin_frame(&self) -> GpuProfilerFrame<'_, U> {
+        GpuProfilerFrame::new(self)
     }
 
     /// Marks the end of a frame.
@@ -388,27 +1212,108 @@ impl GpuProfiler {
     /// Needs to be called **after** submitting any encoder used in the current profiler frame.
     ///
     /// Fails if there are still open queries or unresolved queries.
-    pub fn end_frame(&mut self) -> Result<(), EndFrameError> {
-        let num_open_queries = self.num_open_queries.load(Ordering::Acquire);
+    /// If [`GpuProfilerSettings::validate_submission_order`] is enabled,
+    /// also fails if the last resolve wasn't submitted in the last submission.
+    /// If more scopes than [`GpuProfilerSettings::max_num_scopes_per_frame`] were closed,
+    /// the frame is discarded and an error is returned.
+    ///
+    /// Takes internal locks, so this can be called on a profiler shared between threads (e.g. via [`Arc`]).
+    /// However, scopes must not be opened on other threads while the frame is ended:
+    /// if the frame sees such a scope as open, it fails with [`EndFrameError::UnclosedQueries`],
+    /// otherwise the scope is opened without timer queries and is excluded from the results along with all scopes nested in it.
+    pub fn end_frame(&self) -> Result<(), EndFrameError> {
+        self.end_frame_with(FrameInfo::default())
+    }
+
+    /// Like [`GpuProfiler::end_frame`], but additionally stores information with the frame, see [`FrameInfo`].
+    ///
+    /// Results usually arrive several frames later, the information is returned along with them in a [`FinishedFrame`].
+    pub fn end_frame_with(&self, info: FrameInfo<U>) -> Result<(), EndFrameError> {
+        self.measure_cpu_time(&self.cpu_time_end_frame, || self.end_frame_internal(info))
+    }
+
+    fn end_frame_internal(&self, info: FrameInfo<U>) -> Result<(), EndFrameError> {
+        // Queries opened from here on are discarded, see `Self::begin_query_internal`.
+        self.active_frame
+            .num_ending_frames
+            .fetch_add(1, Ordering::SeqCst);
+        let result = self.end_active_frame(info);
+        self.active_frame
+            .num_ending_frames
+            .fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+
+    fn end_active_frame(&self, info: FrameInfo<U>) -> Result<(), EndFrameError> {
+        let num_open_queries = self.num_open_queries.load(Ordering::SeqCst);
         if num_open_queries != 0 {
-            return Err(EndFrameError::UnclosedQueries(num_open_queries));
+            let open_scopes = self
+                .open_scopes
+                .lock()
+                .iter()
+                .map(|(_, info)| info.clone())
+                .collect();
+            return Err(EndFrameError::UnclosedQueries(
+                num_open_queries,
+                open_scopes,
+            ));
         }
 
-        let query_pools = self.active_frame.query_pools.get_mut();
+        // Frames with invalid submission order are discarded below, once their queries & pools were gathered.
+        let num_submissions_in_frame = self.num_submissions_in_frame.swap(0, Ordering::Relaxed);
+        let submission_order_error = match (
+            self.settings.validate_submission_order,
+            self.last_resolve_submission.lock().take(),
+        ) {
+            (true, Some(last_resolve_submission)) => {
+                match num_submissions_in_frame - last_resolve_submission {
+                    0 => Some(EndFrameError::ResolveNotSubmitted),
+                    1 => None,
+                    num_submissions_after_resolve => Some(EndFrameError::SubmissionsAfterResolve(
+                        num_submissions_after_resolve - 1,
+                    )),
+                }
+            }
+            _ => None,
+        };
+
+        // Ending frames is serialized by the lock on the pending frames.
+        let mut pending_frames = self.pending_frames.lock();
 
+        let used_pools = {
+            let mut query_pools = self.active_frame.query_pools.write();
+            query_pools.pool_creation_failed = false;
+            self.num_dropped_queries_last_frame.store(
+                std::mem::take(&mut query_pools.num_dropped_queries),
+                Ordering::Relaxed,
+            );
+            std::mem::take(&mut query_pools.used_pools)
+        };
+
+        let frame_index = self.frame_index.load(Ordering::Relaxed);
         let mut new_pending_frame = PendingFrame {
-            query_pools: std::mem::take(&mut query_pools.used_pools),
+            frame_index,
+            query_pools: used_pools,
             closed_query_by_parent_handle: HashMap::new(),
             mapped_buffers: Arc::new(AtomicU32::new(0)),
+            submit_markers: std::mem::take(&mut *self.active_frame.submit_markers.lock()),
+            submission_index: info.submission_index,
+            label: info.label,
+            user_data: info.user_data,
+            age: 0,
         };
 
-        for query in self.active_frame.closed_query_receiver.get_mut().try_iter() {
+        for query in self.active_frame.closed_query_receiver.lock().try_iter() {
             new_pending_frame
                 .closed_query_by_parent_handle
                 .entry(query.parent_handle)
                 .or_default()
                 .push(query);
         }
+        let num_scopes = self
+            .active_frame
+            .num_closed_queries
+            .swap(0, Ordering::Relaxed);
 
         // All loads of pool.num_used_queries are Relaxed since we assume,
         // that we already acquired the state during `resolve_queries` and no further otherwise unobserved
@@ -426,38 +1331,101 @@ impl GpuProfiler {
             return Err(EndFrameError::UnresolvedQueries(num_unresolved_queries));
         }
 
+        self.recycle_timed_out_frames(&mut pending_frames);
+
+        let discard_error = if self
+            .settings
+            .max_num_scopes_per_frame
+            .is_some_and(|max_num_scopes| num_scopes > max_num_scopes)
+        {
+            Some(EndFrameError::TooManyScopes(num_scopes))
+        } else {
+            submission_order_error
+        };
+        if let Some(error) = discard_error {
+            // Drop queries first since they still have references to the query pools that we want to reuse.
+            drop(new_pending_frame.closed_query_by_parent_handle);
+            self.reset_and_cache_unused_query_pools(new_pending_frame.query_pools);
+            self.note_dropped_frame(frame_index);
+            self.frame_index.store(frame_index + 1, Ordering::Relaxed);
+            return Err(error);
+        }
+
+        // Non-sampled frames have nothing to report, so they must neither evict pending frames nor shrink query pools.
+        if !self.is_sampled_frame(frame_index) {
+            // Drop queries first since they still have references to the query pools that we want to reuse.
+            drop(new_pending_frame.closed_query_by_parent_handle);
+            self.reset_and_cache_unused_query_pools(new_pending_frame.query_pools);
+            self.frame_index.store(frame_index + 1, Ordering::Relaxed);
+            return Ok(());
+        }
+
         // Next time we create a new query pool, we want it to be at least as big to hold all queries of this frame.
-        self.size_for_new_query_pools = self
-            .size_for_new_query_pools
-            .max(
-                new_pending_frame
-                    .query_pools
-                    .iter()
-                    .map(|pool| pool.num_used_queries.load(Ordering::Relaxed))
-                    .sum(),
-            )
-            .min(QUERY_SET_MAX_QUERIES);
+        let num_queries_in_frame: u32 = new_pending_frame
+            .query_pools
+            .iter()
+            .map(|pool| pool.num_used_queries.load(Ordering::Relaxed))
+            .sum();
+        self.size_for_new_query_pools.fetch_max(
+            num_queries_in_frame.min(QUERY_SET_MAX_QUERIES),
+            Ordering::Relaxed,
+        );
+        self.peak_queries_per_frame
+            .fetch_max(num_queries_in_frame, Ordering::Relaxed);
+        {
+            let mut recent_frame_query_counts = self.recent_frame_query_counts.lock();
+            if recent_frame_query_counts.len() == NUM_FRAMES_FOR_POOL_SIZE_HISTORY {
+                recent_frame_query_counts.pop_front();
+            }
+            recent_frame_query_counts.push_back(num_queries_in_frame);
+
+            if self.settings.pool_size_decay > 0.0 {
+                self.decay_size_for_new_query_pools(&recent_frame_query_counts);
+            }
+        }
+
+        // Without CPU readback there's nothing to wait for, so we can give back the query pools right away.
+        // Subsequent submissions reusing the pools are ordered after the ones of this frame on the queue.
+        // Pools created before readback was enabled mid-frame lack a read buffer, so we have to skip the frame as well.
+        let has_cpu_readback = self.settings.cpu_readback
+            && new_pending_frame
+                .query_pools
+                .iter()
+                .all(|pool| pool.read_buffer.is_some());
+        if !has_cpu_readback {
+            // Drop queries first since they still have references to the query pools that we want to reuse.
+            drop(new_pending_frame.closed_query_by_parent_handle);
+            self.reset_and_cache_unused_query_pools(new_pending_frame.query_pools);
+            self.frame_index.store(frame_index + 1, Ordering::Relaxed);
+            return Ok(());
+        }
 
         // Make sure we don't overflow.
-        if self.pending_frames.len() == self.settings.max_num_pending_frames {
+        // While capturing, we rather let the number of pending frames grow than losing any frame.
+        // Once the capture is over, we may have to drop several frames to get back under the limit.
+        while pending_frames.len() >= self.settings.max_num_pending_frames && !self.is_capturing() {
             // Drop previous (!) frame.
             // Dropping the oldest frame could get us into an endless cycle where we're never able to complete
             // any pending frames as the ones closest to completion would be evicted.
-            if let Some(dropped_frame) = self.pending_frames.pop() {
+            if let Some(dropped_frame) = pending_frames.pop() {
                 // Drop queries first since they still have references to the query pools that we want to reuse.
                 drop(dropped_frame.closed_query_by_parent_handle);
 
                 // Mark the frame as dropped. We'll give back the query pools once the mapping is done.
                 // Any previously issued map_async call that haven't finished yet, will invoke their callback with mapping abort.
                 self.reset_and_cache_unused_query_pools(dropped_frame.query_pools);
+                self.note_dropped_frame(dropped_frame.frame_index);
             }
         }
 
         // Map all buffers.
         for pool in new_pending_frame.query_pools.iter_mut() {
             let mapped_buffers = new_pending_frame.mapped_buffers.clone();
-            pool.read_buffer
-                .slice(0..(pool.num_used_queries.load(Ordering::Relaxed) * wgpu::QUERY_SIZE) as u64)
+            let Some(read_buffer) = &pool.read_buffer else {
+                unreachable!("Frames without CPU readback are never pending");
+            };
+            read_buffer
+                .slice(0..query_byte_offset(pool.num_used_queries.load(Ordering::Relaxed)))
                 .map_async(wgpu::MapMode::Read, move |mapping_result| {
                     // Mapping should not fail unless it was cancelled due to the frame being dropped.
                     match mapping_result {
@@ -475,57 +1443,368 @@ impl GpuProfiler {
         }
 
         // Enqueue
-        self.pending_frames.push(new_pending_frame);
-        assert!(self.pending_frames.len() <= self.settings.max_num_pending_frames);
+        pending_frames.push(new_pending_frame);
+        self.frame_index.store(frame_index + 1, Ordering::Relaxed);
+        assert!(
+            pending_frames.len() <= self.settings.max_num_pending_frames || self.is_capturing()
+        );
 
         Ok(())
     }
 
+    /// Returns true if any pending frame was ended more than `max_age_frames` frames ago and still hasn't been processed.
+    ///
+    /// Frames can get stuck if their buffers never finish mapping, e.g. after a device loss
+    /// or if the device isn't polled anymore.
+    /// Use [`GpuProfiler::clear_pending_frames`] to get rid of them.
+    pub fn has_stuck_frames(&self, max_age_frames: u64) -> bool {
+        let frame_index = self.frame_index.load(Ordering::Relaxed);
+        self.pending_frames
+            .lock()
+            .iter()
+            .any(|frame| frame_index - frame.frame_index > max_age_frames)
+    }
+
+    /// Drops all pending frames, discarding their results.
+    ///
+    /// Useful to recover from stuck frames, see [`GpuProfiler::has_stuck_frames`].
+    pub fn clear_pending_frames(&self) {
+        let mut pending_frames = self.pending_frames.lock();
+        for frame in std::mem::take(&mut *pending_frames) {
+            // Drop queries first since they still have references to the query pools that we want to reuse.
+            drop(frame.closed_query_by_parent_handle);
+            self.reset_and_cache_unused_query_pools(frame.query_pools);
+        }
+    }
+
+    /// Shrinks the size of newly created query pools to what was needed in recent frames
+    /// and drops all cached query pools that are bigger than that.
+    ///
+    /// The size of new query pools only ever grows otherwise,
+    /// so this is useful to reclaim memory after a one-time spike in the number of queries, e.g. on a loading screen.
+    /// Considers the last 64 frames.
+    /// Pools that are still in use by pending frames are not affected.
+    pub fn shrink_to_fit(&mut self) {
+        let max_capacity = self
+            .recent_frame_query_counts
+            .get_mut()
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .clamp(self.settings.min_pool_capacity, QUERY_SET_MAX_QUERIES);
+        *self.size_for_new_query_pools.get_mut() = max_capacity;
+
+        self.unused_pools
+            .retain(|pool| pool.capacity <= max_capacity);
+        self.active_frame
+            .query_pools
+            .get_mut()
+            .unused_pools
+            .retain(|pool| pool.capacity <= max_capacity);
+    }
+
+    /// Starts capturing the results of the next `num_frames` frames processed by [`GpuProfiler::process_finished_frame`].
+    ///
+    /// Captured frames are retrieved with [`GpuProfiler::take_capture`].
+    /// While a capture is in progress, [`GpuProfiler::end_frame`] doesn't drop any frames,
+    /// exceeding [`GpuProfilerSettings::max_num_pending_frames`] if necessary.
+    ///
+    /// Starting a new capture discards any previously captured frames that haven't been taken yet.
+    pub fn capture_frames(&mut self, num_frames: usize) {
+        *self.capture.get_mut() = Some(FrameCapture {
+            num_remaining_frames: num_frames,
+            frames: Vec::with_capacity(num_frames),
+        });
+    }
+
+    /// Returns true if a capture started by [`GpuProfiler::capture_frames`] still waits for more frames.
+    pub fn is_capturing(&self) -> bool {
+        self.capture
+            .lock()
+            .as_ref()
+            .is_some_and(|capture| capture.num_remaining_frames > 0)
+    }
+
+    /// Indices of all frames that were dropped since the last call, in ascending order.
+    ///
+    /// Dropped frames are never returned by [`GpuProfiler::process_finished_frame`],
+    /// which leaves gaps in the frame indices of [`GpuProfiler::process_all_finished_frames`].
+    /// Frames are dropped if they exceed [`GpuProfilerSettings::max_num_pending_frames`],
+    /// [`GpuProfilerSettings::max_num_scopes_per_frame`] or [`GpuProfilerSettings::map_timeout_frames`],
+    /// or if their submission order is invalid while [`GpuProfilerSettings::validate_submission_order`] is set.
+    /// Frames that are skipped by design, i.e. frames not sampled due to [`GpuProfilerSettings::sample_every_n_frames`]
+    /// or frames without [`GpuProfilerSettings::cpu_readback`], aren't reported.
+    ///
+    /// Only the indices of the last 1024 dropped frames are kept if this isn't called regularly.
+    pub fn take_dropped_frame_indices(&self) -> Vec<u64> {
+        let mut dropped_frame_indices =
+            Vec::from(std::mem::take(&mut *self.dropped_frame_indices.lock()));
+        dropped_frame_indices.sort_unstable();
+        dropped_frame_indices
+    }
+
+    fn note_dropped_frame(&self, frame_index: u64) {
+        let mut dropped_frame_indices = self.dropped_frame_indices.lock();
+        if dropped_frame_indices.len() == MAX_NUM_DROPPED_FRAME_INDICES {
+            dropped_frame_indices.pop_front();
+        }
+        dropped_frame_indices.push_back(frame_index);
+    }
+
+    /// Summaries of the most recently processed frames, oldest first.
+    ///
+    /// Retains up to [`GpuProfilerSettings::history_len`] frames processed with
+    /// [`GpuProfiler::process_finished_frame`] or [`GpuProfiler::process_finished_frame_flat`].
+    pub fn recent_history(&self) -> Vec<GpuFrameSummary> {
+        self.history.lock().iter().cloned().collect()
+    }
+
+    /// Adds the CPU time spent in `f` to `total_nanos` if [`GpuProfilerSettings::measure_cpu_overhead`] is enabled.
+    fn measure_cpu_time<T>(&self, total_nanos: &AtomicU64, f: impl FnOnce() -> T) -> T {
+        // There is no way to query the time on wasm32.
+        if !self.settings.measure_cpu_overhead || cfg!(target_arch = "wasm32") {
+            return f();
+        }
+        let start = std::time::Instant::now();
+        let result = f();
+        total_nanos.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        result
+    }
+
+    fn record_history(&self, summary: impl FnOnce() -> GpuFrameSummary) {
+        let history_len = self.settings.history_len;
+        let mut history = self.history.lock();
+        while history.len() >= history_len.max(1) {
+            history.pop_front();
+        }
+        if history_len > 0 {
+            history.push_back(summary());
+        }
+    }
+
+    /// Takes all frames captured since the last call to [`GpuProfiler::capture_frames`], ending the capture.
+    ///
+    /// If the capture isn't complete yet, this returns the frames captured so far.
+    pub fn take_capture(&mut self) -> Vec<Vec<GpuTimerQueryResult>> {
+        self.capture
+            .get_mut()
+            .take()
+            .map(|capture| capture.frames)
+            .unwrap_or_default()
+    }
+
     /// Checks if all timer queries for the oldest pending finished frame are done and returns that snapshot if any.
     ///
+    /// Frames without any scopes are finished as soon as all frames before them are, yielding an empty list of results.
+    ///
     /// `timestamp_period`:
     ///    The timestamp period of the device. Pass the result of [`wgpu::Queue::get_timestamp_period()`].
     ///    Note that some implementations (Chrome as of writing) may converge to a timestamp period while the application is running,
     ///    so caching this value is usually not recommended.
-    pub fn process_finished_frame(
-        &mut self,
-        timestamp_period: f32,
-    ) -> Option<Vec<GpuTimerQueryResult>> {
-        let frame = self.pending_frames.first_mut()?;
+    pub fn process_finished_frame(&self, timestamp_period: f32) -> Option<FinishedFrame<U>> {
+        self.measure_cpu_time(&self.cpu_time_process_finished_frame, || {
+            self.process_finished_frame_internal(timestamp_period)
+        })
+    }
 
-        // We only process if all mappings succeed.
-        if frame
-            .mapped_buffers
-            .load(std::sync::atomic::Ordering::Acquire)
-            != frame.query_pools.len() as u32
-        {
-            return None;
+    /// Processes all pending frames that are finished, oldest first.
+    ///
+    /// Like calling [`GpuProfiler::process_finished_frame`] until it returns `None`,
+    /// which is convenient after waiting for the device with [`wgpu::Maintain::Wait`], finishing several frames at once.
+    /// Since frames may be dropped, indices of consecutive frames are not necessarily consecutive,
+    /// see [`FinishedFrame::index`].
+    pub fn process_all_finished_frames(&self, timestamp_period: f32) -> Vec<FinishedFrame<U>> {
+        std::iter::from_fn(|| self.process_finished_frame(timestamp_period)).collect()
+    }
+
+    fn process_finished_frame_internal(&self, timestamp_period: f32) -> Option<FinishedFrame<U>> {
+        let PendingFrame {
+            frame_index,
+            query_pools,
+            mut closed_query_by_parent_handle,
+            mapped_buffers: _,
+            submit_markers,
+            submission_index: _,
+            label,
+            user_data,
+            age: _,
+        } = self.take_finished_frame()?;
+
+        let context = self.result_processing_context(timestamp_period);
+        let mut results = Self::process_timings_recursive(
+            &context,
+            &mut closed_query_by_parent_handle,
+            ROOT_QUERY_HANDLE,
+            0,
+        );
+        self.num_invalid_timestamps
+            .fetch_add(context.num_invalid_timestamps.get(), Ordering::Relaxed);
+        self.num_unwritten_timestamps
+            .fetch_add(context.num_unwritten_timestamps.get(), Ordering::Relaxed);
+        self.num_merged_reversed_timestamps.fetch_add(
+            context.num_merged_reversed_timestamps.get(),
+            Ordering::Relaxed,
+        );
+        if self.settings.sort_results_by_time {
+            sort_results_by_time_recursive(&mut results);
+        }
+
+        self.recycle_processed_frame(query_pools, closed_query_by_parent_handle);
+
+        self.record_history(|| GpuFrameSummary::from_results(frame_index, &results));
+        self.update_slow_scope_label_paths(&context);
+
+        if let Some(capture) = self.capture.lock().as_mut() {
+            if capture.num_remaining_frames > 0 {
+                capture.frames.push(results.clone());
+                capture.num_remaining_frames -= 1;
+            }
+        }
+
+        Some(FinishedFrame {
+            index: frame_index,
+            label,
+            user_data,
+            submit_markers,
+            results,
+        })
+    }
+
+    /// Like [`GpuProfiler::process_finished_frame`], but first blocks until the oldest pending frame is done on the GPU.
+    ///
+    /// Only waits for the submission passed in [`FrameInfo::submission_index`],
+    /// rather than for all work on the device like polling with [`wgpu::Maintain::Wait`] would.
+    /// If the oldest pending frame was ended without submission index, this doesn't wait at all.
+    pub fn process_finished_frame_blocking(
+        &self,
+        device: &wgpu::Device,
+        timestamp_period: f32,
+    ) -> Option<FinishedFrame<U>> {
+        let submission_index = self
+            .pending_frames
+            .lock()
+            .first()
+            .and_then(|frame| frame.submission_index.clone());
+        if let Some(submission_index) = submission_index {
+            device.poll(wgpu::Maintain::WaitForSubmissionIndex(submission_index));
         }
+        self.process_finished_frame(timestamp_period)
+    }
+
+    /// Like [`GpuProfiler::process_finished_frame`], but returns results as a flat list instead of a tree of nested vectors.
+    ///
+    /// [`GpuTimerQueryResults`] stores all results in a single vector, avoiding an allocation per result,
+    /// which is useful when processing results every frame.
+    /// Frames processed this way are not recorded by [`GpuProfiler::capture_frames`].
+    pub fn process_finished_frame_flat(
+        &self,
+        timestamp_period: f32,
+    ) -> Option<FinishedFrame<U, GpuTimerQueryResults>> {
+        self.measure_cpu_time(&self.cpu_time_process_finished_frame, || {
+            self.process_finished_frame_flat_internal(timestamp_period)
+        })
+    }
 
+    fn process_finished_frame_flat_internal(
+        &self,
+        timestamp_period: f32,
+    ) -> Option<FinishedFrame<U, GpuTimerQueryResults>> {
         let PendingFrame {
+            frame_index,
             query_pools,
             mut closed_query_by_parent_handle,
             mapped_buffers: _,
-        } = self.pending_frames.remove(0);
+            submit_markers,
+            submission_index: _,
+            label,
+            user_data,
+            age: _,
+        } = self.take_finished_frame()?;
+
+        let context = self.result_processing_context(timestamp_period);
+        let results = Self::process_timings_flat(
+            &context,
+            &mut closed_query_by_parent_handle,
+            self.settings.sort_results_by_time,
+        );
+        self.num_invalid_timestamps
+            .fetch_add(context.num_invalid_timestamps.get(), Ordering::Relaxed);
+        self.num_unwritten_timestamps
+            .fetch_add(context.num_unwritten_timestamps.get(), Ordering::Relaxed);
+        self.num_merged_reversed_timestamps.fetch_add(
+            context.num_merged_reversed_timestamps.get(),
+            Ordering::Relaxed,
+        );
 
-        let results = {
-            let timestamp_to_sec = timestamp_period as f64 / 1000.0 / 1000.0 / 1000.0;
+        self.recycle_processed_frame(query_pools, closed_query_by_parent_handle);
+        self.record_history(|| GpuFrameSummary::from_flat_results(frame_index, &results));
+        self.update_slow_scope_label_paths(&context);
 
-            Self::process_timings_recursive(
-                timestamp_to_sec,
-                &mut closed_query_by_parent_handle,
-                ROOT_QUERY_HANDLE,
-            )
-        };
+        Some(FinishedFrame {
+            index: frame_index,
+            label,
+            user_data,
+            submit_markers,
+            results,
+        })
+    }
+}
 
-        // Ensure that closed queries no longer hold references to the query pools.
-        // `process_timings_recursive` should have handled this already.
-        debug_assert!(closed_query_by_parent_handle.is_empty());
-        drop(closed_query_by_parent_handle); // But just in case, we make sure to drop it here even if above debug assertion fails.
+/// Buffer regions written by [`GpuProfiler::resolve_queries`] for a single query pool.
+///
+/// Byte ranges of the same entry have the same length, since they all hold the same newly resolved timestamps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRange {
+    /// Index of the query pool among all pools used in the active profiler frame, in order of creation.
+    pub pool_index: usize,
 
-        self.reset_and_cache_unused_query_pools(query_pools);
+    /// Region of the pool's internal resolve buffer that the queries were resolved into.
+    pub resolve_byte_range: Range<u64>,
+
+    /// Region of the pool's internal read buffer that the resolved queries were copied to.
+    ///
+    /// `None` if [`GpuProfilerSettings::cpu_readback`] was disabled when the pool was created.
+    pub read_byte_range: Option<Range<u64>>,
+
+    /// Region of the destination buffer passed to [`GpuProfiler::resolve_queries_into_buffer`], if any.
+    pub destination_byte_range: Option<Range<u64>>,
+}
 
-        Some(results)
+/// Timer queries reserved up front for several scopes, see [`GpuProfiler::reserve_scopes`].
+#[derive(Debug)]
+pub struct ReservedScopes {
+    profiler_id: u32,
+    frame_index: u64,
+
+    /// Weak, so that a reservation kept around past the end of its frame doesn't prevent the pool from being reused.
+    pool: Weak<QueryPool>,
+
+    /// Query indices in `pool` that weren't handed out yet.
+    query_indices: Range<u32>,
+}
+
+impl ReservedScopes {
+    /// Number of scopes that can still be opened with queries from this reservation.
+    pub fn remaining(&self) -> u32 {
+        self.query_indices.len() as u32 / 2
+    }
+
+    fn take_query_pair<U>(&mut self, profiler: &GpuProfiler<U>) -> Option<ReservedTimerQueryPair> {
+        if self.profiler_id != profiler.id
+            || self.frame_index != profiler.frame_index.load(Ordering::Relaxed)
+            || self.query_indices.is_empty()
+        {
+            return None;
+        }
+        let pool = self.pool.upgrade()?;
+        let start_query_idx = self.query_indices.start;
+        self.query_indices.start += 2;
+        Some(ReservedTimerQueryPair {
+            pool,
+            start_query_idx,
+            usage_state: QueryPairUsageState::Reserved,
+        })
     }
 }
 
@@ -535,6 +1814,99 @@ impl GpuProfiler {
 
 const QUERY_SET_MAX_QUERIES: u32 = wgpu::QUERY_SET_MAX_QUERIES;
 
+/// End timestamps that lie at most this many ticks before their start timestamp are treated as zero length scopes.
+const MAX_BACKWARDS_TIMESTAMP_TICKS: u64 = 1000;
+
+/// Timestamp quantization applied by browsers to WebGPU timestamps, in seconds.
+const BROWSER_WEBGPU_TIMESTAMP_RESOLUTION: f64 = 100.0 / 1000.0 / 1000.0;
+
+/// Number of frames considered by [`GpuProfiler::shrink_to_fit`].
+const NUM_FRAMES_FOR_POOL_SIZE_HISTORY: usize = 64;
+
+/// Number of dropped frames remembered for [`GpuProfiler::take_dropped_frame_indices`].
+const MAX_NUM_DROPPED_FRAME_INDICES: usize = 1024;
+
+static NEXT_PROFILER_ID: AtomicU32 = AtomicU32::new(0);
+
+thread_local! {
+    /// Queries opened on this thread that are still open, innermost last.
+    ///
+    /// Only used with [`GpuProfilerSettings::infer_parent_from_thread`].
+    /// Entries are tagged with the id of the profiler that opened them and carry the label path hash of the query.
+    static OPEN_QUERIES_ON_THREAD: RefCell<Vec<(u32, GpuTimerQueryTreeHandle, u64)>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Returns the innermost query of the given profiler that is open on this thread, as handle & label path hash.
+fn innermost_open_query_on_thread(profiler_id: u32) -> Option<(GpuTimerQueryTreeHandle, u64)> {
+    OPEN_QUERIES_ON_THREAD.with(|open_queries| {
+        open_queries
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(id, _, _)| *id == profiler_id)
+            .map(|(_, handle, label_path_hash)| (*handle, *label_path_hash))
+    })
+}
+
+/// Pushes a query to this thread's stack of open queries.
+fn push_open_query_on_thread(
+    profiler_id: u32,
+    handle: GpuTimerQueryTreeHandle,
+    label_path_hash: u64,
+) {
+    OPEN_QUERIES_ON_THREAD.with(|open_queries| {
+        open_queries
+            .borrow_mut()
+            .push((profiler_id, handle, label_path_hash));
+    });
+}
+
+/// Removes a query from this thread's stack of open queries.
+///
+/// Queries don't have to be closed in order, so this may remove an entry from the middle of the stack.
+fn remove_open_query_on_thread(profiler_id: u32, handle: GpuTimerQueryTreeHandle) {
+    OPEN_QUERIES_ON_THREAD.with(|open_queries| {
+        let mut open_queries = open_queries.borrow_mut();
+        if let Some(index) = open_queries
+            .iter()
+            .rposition(|entry| (entry.0, entry.1) == (profiler_id, handle))
+        {
+            open_queries.remove(index);
+        }
+    });
+}
+
+/// Changes the handle of an open query on this thread's stack.
+fn replace_open_query_on_thread(
+    profiler_id: u32,
+    old_handle: GpuTimerQueryTreeHandle,
+    new_handle: GpuTimerQueryTreeHandle,
+) {
+    OPEN_QUERIES_ON_THREAD.with(|open_queries| {
+        let mut open_queries = open_queries.borrow_mut();
+        if let Some(entry) = open_queries
+            .iter_mut()
+            .rev()
+            .find(|entry| (entry.0, entry.1) == (profiler_id, old_handle))
+        {
+            entry.1 = new_handle;
+        }
+    });
+}
+
+/// Hashes the label path of a query, i.e. the unresolved & untransformed labels of all its parents and its own.
+fn label_path_hash(
+    parent_label_path_hash: u64,
+    label: &str,
+    label_id: Option<u32>,
+    pass_id: Option<PassId>,
+) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (parent_label_path_hash, label, label_id, pass_id).hash(&mut hasher);
+    hasher.finish()
+}
+
 /// Returns true if a timestamp query is supported.
 fn timestamp_query_support<Recorder: ProfilerCommandRecorder>(
     is_for_pass_timestamp_writes: bool,
@@ -551,7 +1923,129 @@ fn timestamp_query_support<Recorder: ProfilerCommandRecorder>(
     features.contains(required_feature)
 }
 
-impl GpuProfiler {
+/// Byte offset of the query with the given index in a query buffer.
+///
+/// Widens before multiplying, so that large indices can't overflow.
+pub(crate) fn query_byte_offset(query_index: u32) -> u64 {
+    query_index as u64 * wgpu::QUERY_SIZE as u64
+}
+
+/// Resolves a numeric label or pass id to a string, `None` if there is neither.
+fn resolve_label(
+    registered_passes: &[String],
+    label_resolver: Option<&LabelResolver>,
+    label_id: Option<u32>,
+    pass_id: Option<PassId>,
+) -> Option<String> {
+    if let Some(pass) = pass_id {
+        // Passes registered with another profiler are reported by their index.
+        return Some(
+            registered_passes
+                .get(pass.0 as usize)
+                .cloned()
+                .unwrap_or_else(|| pass.0.to_string()),
+        );
+    }
+    label_id.map(|id| label_resolver.map_or_else(|| id.to_string(), |resolver| resolver(id)))
+}
+
+/// Orders by start timestamp, putting results without timing first.
+fn compare_by_start_time(a: &Option<Range<f64>>, b: &Option<Range<f64>>) -> std::cmp::Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.start.total_cmp(&b.start),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+/// Sorts every level of the result tree by start timestamp, putting results without timing first.
+fn sort_results_by_time_recursive(results: &mut [GpuTimerQueryResult]) {
+    results.sort_by(|a, b| compare_by_start_time(&a.time, &b.time));
+    for result in results {
+        sort_results_by_time_recursive(&mut result.nested_queries);
+    }
+}
+
+impl<U> GpuProfiler<U> {
+    fn resolve_queries_internal(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        destination: Option<&wgpu::Buffer>,
+    ) -> Vec<ResolvedRange> {
+        let mut query_pools = self.active_frame.query_pools.write();
+        let mut resolved_ranges = Vec::new();
+
+        for (pool_index, query_pool) in query_pools.used_pools.iter_mut().enumerate() {
+            // We sync with the last update of num_used_query (which has Release semantics)
+            // mostly to be on the safe side - it happened inside a lock which gives it release semantics anyways
+            // but the concern is that if we don't acquire here, we may miss on other side prior effects of the query begin.
+            let num_used_queries = query_pool.num_used_queries.load(Ordering::Acquire);
+            let num_resolved_queries = query_pool.num_resolved_queries.load(Ordering::Acquire);
+
+            if num_resolved_queries == num_used_queries {
+                continue;
+            }
+
+            debug_assert!(query_pool.capacity >= num_used_queries);
+            debug_assert!(num_resolved_queries < num_used_queries);
+
+            // Resolve into offset 0 of the resolve buffer - this way we don't have to worry about
+            // the offset restrictions on resolve buffers (`wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT`)
+            // and we copy it anyways.
+            encoder.resolve_query_set(
+                &query_pool.query_set,
+                num_resolved_queries..num_used_queries,
+                &query_pool.resolve_buffer,
+                0,
+            );
+            // Copy the newly resolved queries into the read buffer, making sure
+            // that we don't override any of the results that are already there.
+            let destination_offset = query_byte_offset(num_resolved_queries);
+            let copy_size = query_byte_offset(num_used_queries - num_resolved_queries);
+            let mut resolved_range = ResolvedRange {
+                pool_index,
+                resolve_byte_range: 0..copy_size,
+                read_byte_range: None,
+                destination_byte_range: None,
+            };
+            if let Some(read_buffer) = &query_pool.read_buffer {
+                resolved_range.read_byte_range =
+                    Some(destination_offset..(destination_offset + copy_size));
+                encoder.copy_buffer_to_buffer(
+                    &query_pool.resolve_buffer,
+                    0,
+                    read_buffer,
+                    destination_offset,
+                    copy_size,
+                );
+            }
+            if let Some(destination) = destination {
+                let frame_destination_offset = query_byte_offset(query_pool.frame_query_offset)
+                    + query_byte_offset(num_resolved_queries);
+                encoder.copy_buffer_to_buffer(
+                    &query_pool.resolve_buffer,
+                    0,
+                    destination,
+                    frame_destination_offset,
+                    copy_size,
+                );
+                resolved_range.destination_byte_range =
+                    Some(frame_destination_offset..(frame_destination_offset + copy_size));
+            }
+
+            query_pool
+                .num_resolved_queries
+                .store(num_used_queries, Ordering::Release);
+            resolved_ranges.push(resolved_range);
+        }
+
+        if !resolved_ranges.is_empty() {
+            *self.last_resolve_submission.lock() =
+                Some(self.num_submissions_in_frame.load(Ordering::Relaxed));
+        }
+
+        resolved_ranges
+    }
+
     fn next_scope_tree_handle(&self) -> GpuTimerQueryTreeHandle {
         // Relaxed is fine, we just want a number that nobody uses this frame already.
         let mut handle = self.next_query_handle.fetch_add(1, Ordering::Relaxed);
@@ -560,32 +2054,112 @@ impl GpuProfiler {
         while handle == ROOT_QUERY_HANDLE {
             handle = self.next_query_handle.fetch_add(1, Ordering::Relaxed);
         }
-
-        handle
+
+        handle
+    }
+
+    /// Moves the size for new query pools towards the average number of queries of recent frames,
+    /// see [`GpuProfilerSettings::pool_size_decay`].
+    ///
+    /// Only called while ending a frame, so there are no concurrent modifications other than growing.
+    fn decay_size_for_new_query_pools(&self, recent_frame_query_counts: &VecDeque<u32>) {
+        let average = recent_frame_query_counts
+            .iter()
+            .map(|&count| count as u64)
+            .sum::<u64>()
+            / recent_frame_query_counts.len().max(1) as u64;
+        // The current frame still has to fit into a single pool.
+        let target = (average as u32)
+            .max(recent_frame_query_counts.back().copied().unwrap_or(0))
+            .clamp(self.settings.min_pool_capacity, QUERY_SET_MAX_QUERIES);
+
+        let size = self.size_for_new_query_pools.load(Ordering::Relaxed);
+        if target < size {
+            let decrease = ((size - target) as f32 * self.settings.pool_size_decay).ceil() as u32;
+            let new_size = size - decrease.min(size - target);
+            // Pools hold pairs of queries, so keep the size even.
+            self.size_for_new_query_pools
+                .fetch_min((new_size + 1) & !1, Ordering::Relaxed);
+        }
     }
 
-    fn reset_and_cache_unused_query_pools(&mut self, mut discarded_pools: Vec<Arc<QueryPool>>) {
-        let capacity_threshold = self.size_for_new_query_pools / 2;
+    fn reset_and_cache_unused_query_pools(&self, mut discarded_pools: Vec<Arc<QueryPool>>) {
+        let size_for_new_query_pools = self.size_for_new_query_pools.load(Ordering::Relaxed);
+        let capacity_threshold = size_for_new_query_pools / 2;
+        // With decaying pool sizes, pools that got way too big are given up to reclaim memory.
+        let max_capacity = if self.settings.pool_size_decay > 0.0 {
+            size_for_new_query_pools.saturating_mul(2)
+        } else {
+            u32::MAX
+        };
+        let mut query_pools = self.active_frame.query_pools.write();
         for pool in discarded_pools.drain(..) {
             // If the pool is truly unused now, it's ref count should be 1!
             // If we use it anywhere else we have an implementation bug.
-            let mut pool = Arc::into_inner(pool).expect("Pool still in use");
+            // That's no reason to take down the application in release builds though,
+            // we rather give up on the pool, leaving it to whoever still holds a reference.
+            let Some(mut pool) = Arc::into_inner(pool) else {
+                debug_assert!(false, "Pool still in use");
+                self.num_discarded_referenced_pools
+                    .fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
             pool.reset();
 
             // If a pool was less than half of the size of the max frame, then we don't keep it.
             // This way we're going to need less pools in upcoming frames and thus have less overhead in the long run.
             // If timer queries were disabled, we also don't keep any pools.
-            if self.settings.enable_timer_queries && pool.capacity >= capacity_threshold {
-                self.active_frame
-                    .query_pools
-                    .get_mut()
-                    .unused_pools
-                    .push(pool);
+            // Pools that don't match the current readback & buffer usage settings are discarded as well.
+            if self.settings.enable_timer_queries
+                && pool.capacity >= capacity_threshold
+                && pool.capacity <= max_capacity
+                && pool.matches_settings(&self.settings)
+            {
+                query_pools.unused_pools.push(pool);
             }
         }
     }
 
+    /// Ages all pending frames by one frame and recycles frames whose buffers didn't get mapped in time,
+    /// see [`GpuProfilerSettings::map_timeout_frames`].
+    fn recycle_timed_out_frames(&self, pending_frames: &mut Vec<PendingFrame<U>>) {
+        for frame in pending_frames.iter_mut() {
+            frame.age = frame.age.saturating_add(1);
+        }
+        let Some(map_timeout_frames) = self.settings.map_timeout_frames else {
+            return;
+        };
+
+        let (timed_out_frames, remaining_frames) = std::mem::take(pending_frames)
+            .into_iter()
+            .partition::<Vec<_>, _>(|frame| {
+                frame.age > map_timeout_frames
+                    && frame.mapped_buffers.load(Ordering::Acquire)
+                        != frame.query_pools.len() as u32
+            });
+        *pending_frames = remaining_frames;
+
+        for frame in timed_out_frames {
+            self.num_timed_out_frames.fetch_add(1, Ordering::Relaxed);
+            self.note_dropped_frame(frame.frame_index);
+            // Drop queries first since they still have references to the query pools that we want to reuse.
+            drop(frame.closed_query_by_parent_handle);
+            self.reset_and_cache_unused_query_pools(frame.query_pools);
+        }
+    }
+
     fn try_reserve_query_pair(pool: &Arc<QueryPool>) -> Option<ReservedTimerQueryPair> {
+        Self::try_reserve_query_pairs(pool, 1).map(|queries| ReservedTimerQueryPair {
+            pool: pool.clone(),
+            start_query_idx: queries.start,
+            usage_state: QueryPairUsageState::Reserved,
+        })
+    }
+
+    // Reserves up to `max_pairs` consecutive query pairs (but at least one) with a single atomic operation.
+    //
+    // Returns the reserved range of query indices.
+    fn try_reserve_query_pairs(pool: &QueryPool, max_pairs: u32) -> Option<Range<u32>> {
         let mut num_used_queries = pool.num_used_queries.load(Ordering::Relaxed);
 
         loop {
@@ -593,10 +2167,12 @@ impl GpuProfiler {
                 // This pool is out of capacity, we failed the operation.
                 return None;
             }
+            let num_reserved_queries =
+                (pool.capacity - num_used_queries).min(max_pairs.saturating_mul(2));
 
             match pool.num_used_queries.compare_exchange_weak(
                 num_used_queries,
-                num_used_queries + 2,
+                num_used_queries + num_reserved_queries,
                 // Write to num_used_queries with release semantics to be on the safe side.
                 // (It doesn't look like there's other side effects that we need to publish.)
                 Ordering::Release,
@@ -606,12 +2182,8 @@ impl GpuProfiler {
                 Ordering::Relaxed,
             ) {
                 Ok(_) => {
-                    // We successfully acquired two queries!
-                    return Some(ReservedTimerQueryPair {
-                        pool: pool.clone(),
-                        start_query_idx: num_used_queries,
-                        usage_state: QueryPairUsageState::Reserved,
-                    });
+                    // We successfully acquired our queries!
+                    return Some(num_used_queries..num_used_queries + num_reserved_queries);
                 }
                 Err(updated) => {
                     // Someone else acquired queries in the meantime, try again.
@@ -623,7 +2195,9 @@ impl GpuProfiler {
 
     // Reserves two query objects.
     // Our query pools always have an even number of queries, so we know the next query is the next in the same pool.
-    fn reserve_query_pair(&self, device: &wgpu::Device) -> ReservedTimerQueryPair {
+    //
+    // Returns `None` if a new pool was needed but couldn't be created.
+    fn reserve_query_pair(&self, device: &wgpu::Device) -> Option<ReservedTimerQueryPair> {
         // First, try to allocate from current top pool.
         // Requires taking a read lock on the current query pool.
         {
@@ -633,7 +2207,7 @@ impl GpuProfiler {
                 .last()
                 .and_then(Self::try_reserve_query_pair)
             {
-                return pair;
+                return Some(pair);
             }
         }
         // If this didn't work, we may need to add a new pool.
@@ -648,152 +2222,664 @@ impl GpuProfiler {
                 .last()
                 .and_then(Self::try_reserve_query_pair)
             {
-                return pair;
+                return Some(pair);
+            }
+
+            // Don't try creating pools over and over again if it already failed this frame.
+            if query_pools.pool_creation_failed {
+                query_pools.num_dropped_queries += 1;
+                return None;
             }
 
             // Now we know for certain that the last pool is exhausted, so add a new one!
-            let new_pool = if let Some(reused_pool) = query_pools.unused_pools.pop() {
+            let frame_query_offset = query_pools
+                .used_pools
+                .iter()
+                .map(|pool| pool.capacity)
+                .sum::<u32>();
+            // Pools never exceed what is left of the per-frame query budget,
+            // so the used pools' capacities bound the number of queries in a frame.
+            let max_capacity = self.settings.max_queries_per_frame.map_or(
+                QUERY_SET_MAX_QUERIES,
+                |max_queries_per_frame| {
+                    max_queries_per_frame.saturating_sub(frame_query_offset) & !1
+                },
+            );
+            if max_capacity == 0 {
+                query_pools.num_dropped_queries += 1;
+                return None;
+            }
+            let reusable_pool_index = query_pools
+                .unused_pools
+                .iter()
+                .rposition(|pool| pool.capacity <= max_capacity);
+            let mut new_pool = if let Some(reusable_pool_index) = reusable_pool_index {
                 // First check if there's an unused pool we can take.
-                Arc::new(reused_pool)
+                query_pools.unused_pools.swap_remove(reusable_pool_index)
             } else {
                 // If we can't, create a new pool that is as big as all previous pools combined.
-                Arc::new(QueryPool::new(
-                    query_pools
-                        .used_pools
-                        .iter()
-                        .map(|pool| pool.capacity)
-                        .sum::<u32>()
-                        .max(self.size_for_new_query_pools)
-                        .min(QUERY_SET_MAX_QUERIES),
+                let num_live_query_sets = self.num_live_query_sets.load(Ordering::Relaxed);
+                if self
+                    .settings
+                    .max_num_query_sets
+                    .is_some_and(|max_num_query_sets| num_live_query_sets >= max_num_query_sets)
+                {
+                    // Degrade gracefully by continuing without timer queries.
+                    query_pools.pool_creation_failed = true;
+                    query_pools.num_dropped_queries += 1;
+                    if self
+                        .num_failed_query_pool_creations
+                        .fetch_add(1, Ordering::Relaxed)
+                        == 0
+                    {
+                        log::warn!(
+                            "GpuProfiler reached its budget of {num_live_query_sets} query sets, \
+                            scopes are recorded without timer queries until pools become available again"
+                        );
+                    }
+                    return None;
+                }
+                query_pools.num_created_pools += 1;
+                QueryPool::new(
+                    frame_query_offset
+                        .max(self.size_for_new_query_pools.load(Ordering::Relaxed))
+                        .min(QUERY_SET_MAX_QUERIES)
+                        .min(max_capacity),
+                    query_pools.num_created_pools,
+                    self.frame_index.load(Ordering::Relaxed),
+                    &self.settings,
+                    &self.num_live_query_sets,
                     device,
-                ))
+                )
             };
+            new_pool.frame_query_offset = frame_query_offset;
+            let new_pool = Arc::new(new_pool);
 
             let pair = Self::try_reserve_query_pair(&new_pool)
                 .expect("Freshly reserved pool doesn't have enough capacity");
             query_pools.used_pools.push(new_pool);
 
-            pair
+            Some(pair)
+        }
+    }
+
+    fn is_sampled_frame(&self, frame_index: u64) -> bool {
+        self.settings
+            .sample_every_n_frames
+            .is_none_or(|n| frame_index.is_multiple_of(u64::from(n)))
+    }
+
+    fn resolve_label(&self, label_id: Option<u32>, pass_id: Option<PassId>) -> Option<String> {
+        resolve_label(
+            &self.registered_passes,
+            self.label_resolver.as_deref(),
+            label_id,
+            pass_id,
+        )
+    }
+
+    /// Calls `f` with the label of a query that hasn't been closed yet.
+    ///
+    /// Numeric labels are resolved only once per id, so that opening scopes doesn't call the resolver over and over.
+    pub(crate) fn with_label_str<R>(
+        &self,
+        query: &GpuProfilerQuery,
+        f: impl FnOnce(&str) -> R,
+    ) -> R {
+        if !query.label.is_empty() {
+            return f(&query.label);
+        }
+        if let Some(name) = query.pass_id.and_then(|pass| self.pass_name(pass)) {
+            return f(name);
         }
+        if let (Some(id), None) = (query.label_id, query.pass_id) {
+            if let Some(label) = self.resolved_labels.read().get(&id) {
+                return f(label);
+            }
+            let mut resolved_labels = self.resolved_labels.write();
+            let label = resolved_labels
+                .entry(id)
+                .or_insert_with(|| self.resolve_label(Some(id), None).unwrap_or_default());
+            return f(label);
+        }
+        f(&self
+            .resolve_label(query.label_id, query.pass_id)
+            .unwrap_or_default())
     }
 
     #[track_caller]
     #[must_use]
     fn begin_query_internal<Recorder: ProfilerCommandRecorder>(
         &self,
-        label: String,
+        label: ScopeLabel,
+        options: QueryOptions<'_>,
         is_for_pass_timestamp_writes: bool,
         encoder_or_pass: &mut Recorder,
         device: &wgpu::Device,
     ) -> GpuProfilerQuery {
-        // Give opening/closing queries acquire/release semantics:
-        // This way, we won't get any nasty surprises when observing zero open queries.
-        self.num_open_queries.fetch_add(1, Ordering::Acquire);
+        let QueryOptions {
+            channel,
+            encoder_tag,
+            parent,
+            reserved,
+        } = options;
+        if cfg!(debug_assertions) {
+            let (features, limits) = self
+                .device_fingerprint
+                .get_or_init(|| (device.features(), device.limits()));
+            assert!(
+                *features == device.features() && *limits == device.limits(),
+                "GpuProfiler used with a different wgpu::Device than before"
+            );
+        }
 
-        let query = if self.settings.enable_timer_queries
+        // Sequentially consistent with the check in `Self::end_frame_internal`:
+        // either the frame being ended sees this query as open, or this query sees the frame being ended.
+        self.num_open_queries.fetch_add(1, Ordering::SeqCst);
+        let is_opened_during_end_frame =
+            self.active_frame.num_ending_frames.load(Ordering::SeqCst) != 0;
+
+        let is_timed = !is_opened_during_end_frame
+            && self.settings.enable_timer_queries
+            && channel & self.settings.enabled_channels != 0
+            && self
+                .active_encoder_tag
+                .is_none_or(|active_tag| encoder_tag == Some(active_tag))
+            && self.is_sampled_frame(self.frame_index.load(Ordering::Relaxed));
+        let query = if is_timed
             && timestamp_query_support(
                 is_for_pass_timestamp_writes,
                 encoder_or_pass,
                 device.features(),
             ) {
-            Some(self.reserve_query_pair(device))
+            reserved
+                .and_then(|reserved| reserved.take_query_pair(self))
+                .or_else(|| self.reserve_query_pair(device))
         } else {
             None
         };
 
-        let _tracy_scope = if self.settings.enable_timer_queries {
-            #[cfg(feature = "tracy")]
-            {
-                let location = std::panic::Location::caller();
-                self.tracy_context.as_ref().and_then(|c| {
-                    c.span_alloc(&label, "", location.file(), location.line())
-                        .ok()
-                })
-            }
-            #[cfg(not(feature = "tracy"))]
-            Option::<()>::None
+        // Numeric labels are only resolved once their string is needed, see `Self::with_label_str`.
+        let (label, label_id, pass_id) = label.into_parts();
+
+        let (pid, tid) = if self.settings.capture_thread_info {
+            (self.process_id, std::thread::current().id())
         } else {
-            None
+            (0, self.creation_thread_id)
         };
 
-        let pid = if cfg!(target_arch = "wasm32") {
-            0
+        let handle = self.next_scope_tree_handle();
+        let is_on_thread_stack = self.settings.infer_parent_from_thread;
+        let (parent_handle, parent_label_path_hash) = match parent {
+            Some(parent) => (parent.handle, parent.label_path_hash),
+            None if is_on_thread_stack => {
+                innermost_open_query_on_thread(self.id).unwrap_or((ROOT_QUERY_HANDLE, 0))
+            }
+            None => (ROOT_QUERY_HANDLE, 0),
+        };
+        // Label paths are only needed for matching against slow scopes.
+        let label_path_hash = if self.settings.debug_group_threshold.is_some() {
+            label_path_hash(parent_label_path_hash, &label, label_id, pass_id)
         } else {
-            std::process::id()
+            0
         };
+        if is_on_thread_stack {
+            push_open_query_on_thread(self.id, handle, label_path_hash);
+        }
 
-        GpuProfilerQuery {
+        let query = GpuProfilerQuery {
             label,
+            label_id,
+            pass_id,
             pid,
-            tid: std::thread::current().id(),
+            tid,
+            unit_count: None,
+            category: None,
             timer_query_pair: query,
-            handle: self.next_scope_tree_handle(),
-            parent_handle: ROOT_QUERY_HANDLE,
+            handle,
+            parent_handle,
             has_debug_group: false,
+            is_accumulating: false,
+            // Queries opened while a frame is ended can't be attributed to a frame, they are discarded like cancelled queries.
+            is_cancelled: is_opened_during_end_frame,
+            is_on_thread_stack,
+            label_path_hash,
+            encoder_tag,
             #[cfg(feature = "tracy")]
-            tracy_scope: _tracy_scope,
+            tracy_scope: None,
+        };
+
+        #[cfg(feature = "tracy")]
+        let location = std::panic::Location::caller();
+        #[cfg(feature = "tracy")]
+        let query = GpuProfilerQuery {
+            tracy_scope: self
+                .tracy_context
+                .as_ref()
+                .filter(|_| is_timed)
+                .and_then(|c| {
+                    self.with_label_str(&query, |label| {
+                        c.span_alloc(label, "", location.file(), location.line())
+                            .ok()
+                    })
+                }),
+            ..query
+        };
+
+        if cfg!(debug_assertions) {
+            self.open_scopes.lock().push((
+                handle,
+                UnclosedScopeInfo {
+                    label: self.with_label_str(&query, str::to_owned),
+                    location: Some(std::panic::Location::caller()),
+                },
+            ));
         }
+
+        query
     }
 
     fn process_timings_recursive(
-        timestamp_to_sec: f64,
+        context: &ResultProcessingContext<'_>,
         closed_scope_by_parent_handle: &mut HashMap<GpuTimerQueryTreeHandle, Vec<GpuProfilerQuery>>,
         parent_handle: GpuTimerQueryTreeHandle,
+        depth: u32,
     ) -> Vec<GpuTimerQueryResult> {
+        if depth >= context.max_scope_depth {
+            Self::discard_nested_queries(context, closed_scope_by_parent_handle, parent_handle);
+            return Vec::new();
+        }
         let Some(queries_with_same_parent) = closed_scope_by_parent_handle.remove(&parent_handle)
         else {
             return Vec::new();
         };
 
-        queries_with_same_parent
-            .into_iter()
-            .map(|mut scope| {
-                // Note that inactive queries may still have nested queries, it's therefore important we process all of them.
-                // In particular, this happens if only `wgpu::Features::TIMESTAMP_QUERY`` is enabled and `timestamp_writes`
-                // on passes are nested inside inactive encoder timer queries.
-                let time = scope.timer_query_pair.take().map(|query| {
-                    // Read timestamp from buffer.
-                    // By design timestamps for start/end are consecutive.
-                    let offset = (query.start_query_idx * wgpu::QUERY_SIZE) as u64;
-                    let buffer_slice = &query
-                        .pool
-                        .read_buffer
-                        .slice(offset..(offset + (wgpu::QUERY_SIZE * 2) as u64))
-                        .get_mapped_range();
-                    let start_raw = u64::from_le_bytes(
-                        buffer_slice[0..wgpu::QUERY_SIZE as usize]
-                            .try_into()
-                            .unwrap(),
-                    );
-                    let end_raw = u64::from_le_bytes(
-                        buffer_slice[wgpu::QUERY_SIZE as usize..(wgpu::QUERY_SIZE as usize) * 2]
-                            .try_into()
-                            .unwrap(),
-                    );
+        let mut results = Vec::with_capacity(queries_with_same_parent.len());
+        // All regions of an accumulating scope share the same handle, we merge them into a single result.
+        let mut accumulated_result_index_by_handle = HashMap::new();
 
-                    #[cfg(feature = "tracy")]
-                    if let Some(tracy_scope) = scope.tracy_scope.take() {
-                        tracy_scope.upload_timestamp(start_raw as i64, end_raw as i64);
-                    }
+        for mut scope in queries_with_same_parent {
+            if scope.is_cancelled {
+                Self::discard_nested_queries(context, closed_scope_by_parent_handle, scope.handle);
+                continue;
+            }
+            let time = Self::read_query_time(context, &mut scope);
+
+            if scope.is_accumulating {
+                if let Some(&index) = accumulated_result_index_by_handle.get(&scope.handle) {
+                    let result: &mut GpuTimerQueryResult = &mut results[index];
+                    result.num_samples = accumulate_num_samples(result.num_samples, time.as_ref());
+                    result.time = accumulate_time(result.time.take(), time);
+                    result.unit_count = accumulate_unit_count(result.unit_count, scope.unit_count);
+                    continue;
+                }
+                accumulated_result_index_by_handle.insert(scope.handle, results.len());
+            }
 
-                    (start_raw as f64 * timestamp_to_sec)..(end_raw as f64 * timestamp_to_sec)
-                });
+            let nested_queries = Self::process_timings_recursive(
+                context,
+                closed_scope_by_parent_handle,
+                scope.handle,
+                depth + 1,
+            );
 
-                let nested_queries = Self::process_timings_recursive(
-                    timestamp_to_sec,
+            results.push(GpuTimerQueryResult {
+                label: Self::take_query_label(context, &mut scope),
+                num_samples: scope.is_accumulating.then_some(u32::from(time.is_some())),
+                time,
+                nested_queries,
+                pid: scope.pid,
+                tid: scope.tid,
+                backend: context.backend,
+                clock_calibration: context.clock_calibration,
+                unit_count: scope.unit_count,
+                category: scope.category,
+                extra_metrics: HashMap::new(),
+            });
+        }
+
+        // Nested queries were processed already, so dropping results doesn't leave any queries behind.
+        results.retain(|result| !context.is_below_report_duration(result.time.as_ref()));
+
+        results
+    }
+
+    /// Like [`Self::process_timings_recursive`], but builds a flat list of results in breadth-first order.
+    fn process_timings_flat(
+        context: &ResultProcessingContext<'_>,
+        closed_scope_by_parent_handle: &mut HashMap<GpuTimerQueryTreeHandle, Vec<GpuProfilerQuery>>,
+        sort_by_time: bool,
+    ) -> GpuTimerQueryResults {
+        let mut nodes =
+            Vec::with_capacity(closed_scope_by_parent_handle.values().map(Vec::len).sum());
+        if context.max_scope_depth > 0 {
+            Self::push_flat_siblings(
+                context,
+                closed_scope_by_parent_handle,
+                ROOT_QUERY_HANDLE,
+                sort_by_time,
+                &mut nodes,
+            );
+        } else {
+            Self::discard_nested_queries(context, closed_scope_by_parent_handle, ROOT_QUERY_HANDLE);
+        }
+        let num_roots = nodes.len();
+
+        // All children of a node are pushed at once, making them a contiguous range.
+        let mut depths = vec![1; num_roots];
+        let mut index = 0;
+        while index < nodes.len() {
+            if depths[index] >= context.max_scope_depth {
+                Self::discard_nested_queries(
+                    context,
                     closed_scope_by_parent_handle,
-                    scope.handle,
+                    nodes[index].handle,
                 );
+                index += 1;
+                continue;
+            }
+            let first_nested_index = nodes.len();
+            Self::push_flat_siblings(
+                context,
+                closed_scope_by_parent_handle,
+                nodes[index].handle,
+                sort_by_time,
+                &mut nodes,
+            );
+            nodes[index].nested_queries = first_nested_index..nodes.len();
+            depths.resize(nodes.len(), depths[index] + 1);
+            index += 1;
+        }
+
+        GpuTimerQueryResults { nodes, num_roots }
+    }
+
+    fn push_flat_siblings(
+        context: &ResultProcessingContext<'_>,
+        closed_scope_by_parent_handle: &mut HashMap<GpuTimerQueryTreeHandle, Vec<GpuProfilerQuery>>,
+        parent_handle: GpuTimerQueryTreeHandle,
+        sort_by_time: bool,
+        nodes: &mut Vec<GpuTimerQueryResultNode>,
+    ) {
+        let Some(queries_with_same_parent) = closed_scope_by_parent_handle.remove(&parent_handle)
+        else {
+            return;
+        };
+
+        let first_sibling_index = nodes.len();
+        for mut scope in queries_with_same_parent {
+            if scope.is_cancelled {
+                Self::discard_nested_queries(context, closed_scope_by_parent_handle, scope.handle);
+                continue;
+            }
+            let time = Self::read_query_time(context, &mut scope);
+
+            // All regions of an accumulating scope share the same handle, we merge them into a single result.
+            // Searching the siblings is fine since accumulating scopes are expected to be rare.
+            if scope.is_accumulating {
+                if let Some(node) = nodes[first_sibling_index..]
+                    .iter_mut()
+                    .find(|node| node.handle == scope.handle)
+                {
+                    node.num_samples = accumulate_num_samples(node.num_samples, time.as_ref());
+                    node.time = accumulate_time(node.time.take(), time);
+                    node.unit_count = accumulate_unit_count(node.unit_count, scope.unit_count);
+                    continue;
+                }
+            }
+
+            nodes.push(GpuTimerQueryResultNode {
+                label: Self::take_query_label(context, &mut scope),
+                num_samples: scope.is_accumulating.then_some(u32::from(time.is_some())),
+                pid: scope.pid,
+                tid: scope.tid,
+                backend: context.backend,
+                clock_calibration: context.clock_calibration,
+                time,
+                unit_count: scope.unit_count,
+                category: scope.category,
+                extra_metrics: HashMap::new(),
+                nested_queries: 0..0,
+                handle: scope.handle,
+            });
+        }
+
+        if sort_by_time {
+            nodes[first_sibling_index..].sort_by(|a, b| compare_by_start_time(&a.time, &b.time));
+        }
 
-                GpuTimerQueryResult {
-                    label: std::mem::take(&mut scope.label),
-                    time,
-                    nested_queries,
-                    pid: scope.pid,
-                    tid: scope.tid,
+        if context.min_report_duration.is_some() {
+            for node in nodes.split_off(first_sibling_index) {
+                if context.is_below_report_duration(node.time.as_ref()) {
+                    Self::discard_nested_queries(
+                        context,
+                        closed_scope_by_parent_handle,
+                        node.handle,
+                    );
+                } else {
+                    nodes.push(node);
                 }
+            }
+        }
+    }
+
+    /// Removes all queries nested in the given query, without reporting them.
+    ///
+    /// Their timings are still read, so that tracy spans of discarded queries are finished.
+    /// Iterative rather than recursive, since this is also used for scopes that are nested too deeply.
+    fn discard_nested_queries(
+        context: &ResultProcessingContext<'_>,
+        closed_scope_by_parent_handle: &mut HashMap<GpuTimerQueryTreeHandle, Vec<GpuProfilerQuery>>,
+        parent_handle: GpuTimerQueryTreeHandle,
+    ) {
+        let mut parent_handles = vec![parent_handle];
+        while let Some(parent_handle) = parent_handles.pop() {
+            // All regions of an accumulating scope share the same handle, the second removal simply finds nothing.
+            let Some(queries) = closed_scope_by_parent_handle.remove(&parent_handle) else {
+                continue;
+            };
+            for mut query in queries {
+                Self::read_query_time(context, &mut query);
+                parent_handles.push(query.handle);
+            }
+        }
+    }
+
+    /// Removes the oldest pending frame if all its buffers are mapped.
+    fn take_finished_frame(&self) -> Option<PendingFrame<U>> {
+        let mut pending_frames = self.pending_frames.lock();
+        let frame = pending_frames.first()?;
+
+        // We only process if all mappings succeed.
+        // Frames without any query pools, e.g. because no scopes were opened, are finished right away,
+        // but still only after all frames ended before them.
+        if frame
+            .mapped_buffers
+            .load(std::sync::atomic::Ordering::Acquire)
+            != frame.query_pools.len() as u32
+        {
+            return None;
+        }
+
+        Some(pending_frames.remove(0))
+    }
+
+    fn result_processing_context(&self, timestamp_period: f32) -> ResultProcessingContext<'_> {
+        ResultProcessingContext {
+            clock_calibration: self.clock_calibration,
+            registered_passes: &self.registered_passes,
+            ..ResultProcessingContext::new(
+                timestamp_period,
+                self.backend,
+                self.label_resolver.as_deref(),
+                &self.settings,
+            )
+        }
+    }
+
+    fn recycle_processed_frame(
+        &self,
+        query_pools: Vec<Arc<QueryPool>>,
+        closed_query_by_parent_handle: HashMap<GpuTimerQueryTreeHandle, Vec<GpuProfilerQuery>>,
+    ) {
+        // Ensure that closed queries no longer hold references to the query pools.
+        // Processing the timings should have handled this already.
+        debug_assert!(closed_query_by_parent_handle.is_empty());
+        drop(closed_query_by_parent_handle); // But just in case, we make sure to drop it here even if above debug assertion fails.
+
+        self.reset_and_cache_unused_query_pools(query_pools);
+    }
+
+    /// Reads the time range of a closed query from its pool's read buffer, if it has a timer query.
+    fn read_query_time(
+        context: &ResultProcessingContext<'_>,
+        scope: &mut GpuProfilerQuery,
+    ) -> Option<Range<f64>> {
+        // Note that inactive queries may still have nested queries, it's therefore important we process all of them.
+        // In particular, this happens if only `wgpu::Features::TIMESTAMP_QUERY`` is enabled and `timestamp_writes`
+        // on passes are nested inside inactive encoder timer queries.
+        let time = scope.timer_query_pair.take().and_then(|query| {
+            // Timestamps of queries that weren't properly closed may be garbage, don't report them.
+            if query.usage_state != QueryPairUsageState::BothStartAndEndWritten {
+                debug_assert!(false, "Query wasn't closed properly before processing");
+                context.count_invalid_timestamps();
+                return None;
+            }
+
+            // Read timestamp from buffer.
+            // By design timestamps for start/end are consecutive.
+            let offset = query_byte_offset(query.start_query_idx);
+            let buffer_slice = &query
+                .pool
+                .read_buffer
+                .as_ref()
+                .expect("Frames without CPU readback are never processed")
+                .slice(offset..(offset + query_byte_offset(2)))
+                .get_mapped_range();
+            let start_raw = u64::from_le_bytes(
+                buffer_slice[0..wgpu::QUERY_SIZE as usize]
+                    .try_into()
+                    .unwrap(),
+            );
+            let end_raw = u64::from_le_bytes(
+                buffer_slice[wgpu::QUERY_SIZE as usize..(wgpu::QUERY_SIZE as usize) * 2]
+                    .try_into()
+                    .unwrap(),
+            );
+            let (start_raw, end_raw) = context.validate_timestamps(start_raw, end_raw)?;
+
+            #[cfg(feature = "tracy")]
+            if let Some(tracy_scope) = scope.tracy_scope.take() {
+                tracy_scope.upload_timestamp(start_raw as i64, end_raw as i64);
+            }
+
+            Some(context.timestamps_to_time(start_raw, end_raw))
+        });
+        context.note_scope_duration(scope.label_path_hash, time.as_ref());
+        time
+    }
+
+    /// Builds results from raw timestamps, with the same validation & conversion as for results of actual frames.
+    #[cfg(feature = "test-support")]
+    pub(crate) fn build_results_from_raw(
+        scopes: &[crate::test_support::RawScope],
+        timestamp_period: f32,
+        settings: &GpuProfilerSettings,
+    ) -> Vec<GpuTimerQueryResult> {
+        let context = ResultProcessingContext::new(timestamp_period, None, None, settings);
+        let mut results = Self::build_results_from_raw_recursive(&context, scopes);
+        if settings.sort_results_by_time {
+            sort_results_by_time_recursive(&mut results);
+        }
+        results
+    }
+
+    #[cfg(feature = "test-support")]
+    fn build_results_from_raw_recursive(
+        context: &ResultProcessingContext<'_>,
+        scopes: &[crate::test_support::RawScope],
+    ) -> Vec<GpuTimerQueryResult> {
+        let mut results = scopes
+            .iter()
+            .map(|scope| GpuTimerQueryResult {
+                label: match context.label_transform {
+                    Some(transform) => (transform.0)(&scope.label),
+                    None => scope.label.clone(),
+                },
+                pid: 0,
+                tid: std::thread::current().id(),
+                backend: context.backend,
+                clock_calibration: context.clock_calibration,
+                time: scope.timestamps.and_then(|(start_raw, end_raw)| {
+                    let (start_raw, end_raw) = context.validate_timestamps(start_raw, end_raw)?;
+                    Some(context.timestamps_to_time(start_raw, end_raw))
+                }),
+                unit_count: None,
+                category: scope.category,
+                num_samples: None,
+                extra_metrics: HashMap::new(),
+                nested_queries: Self::build_results_from_raw_recursive(
+                    context,
+                    &scope.nested_scopes,
+                ),
             })
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+        results.retain(|result| !context.is_below_report_duration(result.time.as_ref()));
+        results
+    }
+
+    /// Takes the label of a closed query, resolving numeric labels and applying the label transform if necessary.
+    fn take_query_label(
+        context: &ResultProcessingContext<'_>,
+        scope: &mut GpuProfilerQuery,
+    ) -> String {
+        let resolved_label = if scope.label.is_empty() {
+            resolve_label(
+                context.registered_passes,
+                context.label_resolver,
+                scope.label_id,
+                scope.pass_id,
+            )
+        } else {
+            None
+        };
+        let label = resolved_label.unwrap_or_else(|| std::mem::take(&mut scope.label));
+        match context.label_transform {
+            Some(transform) => (transform.0)(&label),
+            None => label,
+        }
+    }
+}
+
+/// Counts another region of an accumulating scope, if it has timing information.
+fn accumulate_num_samples(num_samples: Option<u32>, region: Option<&Range<f64>>) -> Option<u32> {
+    num_samples.map(|num_samples| num_samples + u32::from(region.is_some()))
+}
+
+/// Merges the time of another region into an accumulated time range.
+///
+/// The accumulated range starts with the earliest region and is as long as all regions combined.
+fn accumulate_time(
+    accumulated: Option<Range<f64>>,
+    region: Option<Range<f64>>,
+) -> Option<Range<f64>> {
+    match (accumulated, region) {
+        (Some(accumulated), Some(region)) => {
+            let start = accumulated.start.min(region.start);
+            Some(
+                start
+                    ..(start + (accumulated.end - accumulated.start) + (region.end - region.start)),
+            )
+        }
+        (accumulated, region) => accumulated.or(region),
+    }
+}
+
+/// Merges the unit count of another region into an accumulated unit count.
+fn accumulate_unit_count(accumulated: Option<u32>, region: Option<u32>) -> Option<u32> {
+    match (accumulated, region) {
+        (Some(accumulated), Some(region)) => Some(accumulated + region),
+        (accumulated, region) => accumulated.or(region),
     }
 }
 
@@ -805,6 +2891,9 @@ pub enum QueryPairUsageState {
     /// Don't do manual timestamp writes, wgpu is expected to do them for us.
     ReservedForPassTimestampWrites,
 
+    /// Don't do manual timestamp writes, the user is expected to do them, see [`GpuProfiler::begin_raw_query`].
+    ReservedForRawTimestampWrites,
+
     /// Start query has been used, end query is still available.
     OnlyStartWritten,
 
@@ -833,48 +2922,108 @@ pub struct QueryPool {
     pub query_set: wgpu::QuerySet,
 
     resolve_buffer: wgpu::Buffer,
-    read_buffer: wgpu::Buffer,
+    /// Buffer the resolved queries are copied to for reading them back on the CPU.
+    ///
+    /// `None` if the pool was created with [`GpuProfilerSettings::cpu_readback`] disabled.
+    read_buffer: Option<wgpu::Buffer>,
 
     capacity: u32,
     num_used_queries: AtomicU32,
     num_resolved_queries: AtomicU32,
+
+    /// Sum of the capacities of all pools that were used before this one in the same frame.
+    pub frame_query_offset: u32,
+
+    /// Counter of the profiler's live pools, see [`GpuProfilerSettings::max_num_query_sets`].
+    num_live_query_sets: Arc<AtomicU32>,
+}
+
+impl Drop for QueryPool {
+    fn drop(&mut self) {
+        self.num_live_query_sets.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl QueryPool {
-    const MIN_CAPACITY: u32 = 32;
+    fn resolve_buffer_usages(settings: &GpuProfilerSettings) -> wgpu::BufferUsages {
+        wgpu::BufferUsages::QUERY_RESOLVE
+            | wgpu::BufferUsages::COPY_SRC
+            | settings.additional_resolve_buffer_usages
+    }
+
+    fn read_buffer_usages(settings: &GpuProfilerSettings) -> wgpu::BufferUsages {
+        wgpu::BufferUsages::COPY_DST
+            | wgpu::BufferUsages::MAP_READ
+            | settings.additional_read_buffer_usages
+    }
+
+    /// Whether the pool's buffers match what a new pool would be created with.
+    fn matches_settings(&self, settings: &GpuProfilerSettings) -> bool {
+        self.resolve_buffer.usage() == Self::resolve_buffer_usages(settings)
+            && self.read_buffer.as_ref().map(|buffer| buffer.usage())
+                == settings
+                    .cpu_readback
+                    .then(|| Self::read_buffer_usages(settings))
+    }
+
+    /// Creates a new pool.
+    ///
+    /// Since pools are reused across frames and wgpu labels can't be changed after creation,
+    /// labels contain a unique pool number and the frame the pool was created in,
+    /// making it possible to correlate resources in graphics debuggers.
+    ///
+    /// The pool counts itself in `num_live_query_sets` until it is dropped.
+    fn new(
+        capacity: u32,
+        pool_number: u32,
+        frame_index: u64,
+        settings: &GpuProfilerSettings,
+        num_live_query_sets: &Arc<AtomicU32>,
+        device: &wgpu::Device,
+    ) -> Self {
+        num_live_query_sets.fetch_add(1, Ordering::Relaxed);
+        let label_suffix = format!("#{pool_number} (created in frame {frame_index})");
 
-    fn new(capacity: u32, device: &wgpu::Device) -> Self {
         QueryPool {
             query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
-                label: Some("GpuProfiler - Query Set"),
+                label: Some(&format!("GpuProfiler - Query Set {label_suffix}")),
                 ty: wgpu::QueryType::Timestamp,
                 count: capacity,
             }),
 
             resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("GpuProfiler - Query Resolve Buffer"),
-                size: (wgpu::QUERY_SIZE * capacity) as u64,
-                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                label: Some(&format!(
+                    "GpuProfiler - Query Resolve Buffer {label_suffix}"
+                )),
+                size: query_byte_offset(capacity),
+                usage: Self::resolve_buffer_usages(settings),
                 mapped_at_creation: false,
             }),
 
-            read_buffer: device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("GpuProfiler - Query Read Buffer"),
-                size: (wgpu::QUERY_SIZE * capacity) as u64,
-                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-                mapped_at_creation: false,
+            read_buffer: settings.cpu_readback.then(|| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(&format!("GpuProfiler - Query Read Buffer {label_suffix}")),
+                    size: query_byte_offset(capacity),
+                    usage: Self::read_buffer_usages(settings),
+                    mapped_at_creation: false,
+                })
             }),
 
             capacity,
             num_used_queries: AtomicU32::new(0),
             num_resolved_queries: AtomicU32::new(0),
+            frame_query_offset: 0,
+            num_live_query_sets: num_live_query_sets.clone(),
         }
     }
 
     fn reset(&mut self) {
         self.num_used_queries = AtomicU32::new(0);
         self.num_resolved_queries = AtomicU32::new(0);
-        self.read_buffer.unmap();
+        self.frame_query_offset = 0;
+        if let Some(read_buffer) = &self.read_buffer {
+            read_buffer.unmap();
+        }
     }
 }
 
@@ -886,6 +3035,15 @@ struct PendingFramePools {
 
     /// List of unused pools recycled from previous frames.
     unused_pools: Vec<QueryPool>,
+
+    /// Number of pools created over the lifetime of the profiler.
+    num_created_pools: u32,
+
+    /// Set if creating a new pool failed in the active frame.
+    pool_creation_failed: bool,
+
+    /// Number of queries in the active frame that didn't get timer queries since no pool could be created.
+    num_dropped_queries: u32,
 }
 
 /// Internal handle to building a tree of profiling queries.
@@ -899,19 +3057,208 @@ struct ActiveFrame {
 
     /// Closed queries get send to this channel.
     ///
-    /// Note that channel is still overkill for what we want here:
-    /// We're in a multi producer situation, *but* the single consumer is known to be only
-    /// active in a mut context, i.e. while we're consuming we know that we're not producing.
-    /// We have to wrap it in a Mutex because the channel is not Sync, but we actually never lock it
-    /// since we only ever access it in a `mut` context.
+    /// Multiple producers, the single consumer is [`GpuProfiler::end_frame`] which takes `&self`.
+    /// The receiver is not Sync, so it is wrapped in a Mutex which is locked while draining the channel.
+    /// Concurrent end frames are serialized by the lock on the pending frames already, so this lock is never contended.
     closed_query_sender: std::sync::mpsc::Sender<GpuProfilerQuery>,
     closed_query_receiver: Mutex<std::sync::mpsc::Receiver<GpuProfilerQuery>>,
+
+    /// Number of [`GpuProfiler::end_frame`] calls in progress, queries opened meanwhile are discarded.
+    num_ending_frames: AtomicU32,
+
+    /// Number of queries closed in this frame, including those dropped due to [`GpuProfilerSettings::max_num_scopes_per_frame`].
+    num_closed_queries: AtomicU32,
+
+    submit_markers: Mutex<Vec<SubmitMarker>>,
+}
+
+/// Options shared by all ways of opening a query.
+pub(crate) struct QueryOptions<'a> {
+    /// Channel bits of the query, see [`GpuProfilerSettings::enabled_channels`].
+    pub(crate) channel: u32,
+
+    /// Encoder tag of the query, see [`GpuProfiler::set_active_encoder_tag`].
+    pub(crate) encoder_tag: Option<u32>,
+
+    /// Parent of the query if it is known when opening it, takes precedence over a parent inferred from the thread.
+    pub(crate) parent: Option<&'a GpuProfilerQuery>,
+
+    /// Reservation to take the timer queries from before reserving them individually.
+    pub(crate) reserved: Option<&'a mut ReservedScopes>,
+}
+
+impl<'a> QueryOptions<'a> {
+    /// Options for a query nested in `parent`, inheriting its encoder tag.
+    pub(crate) fn nested_in(parent: Option<&'a GpuProfilerQuery>) -> Self {
+        Self {
+            encoder_tag: parent.and_then(|parent| parent.encoder_tag),
+            parent,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for QueryOptions<'_> {
+    fn default() -> Self {
+        Self {
+            channel: GpuProfiler::DEFAULT_CHANNEL,
+            encoder_tag: None,
+            parent: None,
+            reserved: None,
+        }
+    }
+}
+
+/// Per-frame state needed for turning closed queries into results.
+struct ResultProcessingContext<'a> {
+    timestamp_to_sec: f64,
+    backend: Option<wgpu::Backend>,
+    clock_calibration: Option<ClockCalibration>,
+    label_resolver: Option<&'a LabelResolver>,
+    registered_passes: &'a [String],
+    label_transform: Option<&'a LabelTransform>,
+    min_report_duration: Option<f64>,
+    merge_reversed_timestamps: bool,
+    max_scope_depth: u32,
+    debug_group_threshold: Option<f64>,
+
+    /// Label path hashes of scopes that took at least [`GpuProfilerSettings::debug_group_threshold`].
+    slow_scope_label_paths: RefCell<HashSet<u64>>,
+
+    /// Number of queries whose end timestamp lies before their start timestamp.
+    num_invalid_timestamps: std::cell::Cell<u32>,
+
+    /// Number of queries whose start or end timestamp was never written.
+    num_unwritten_timestamps: std::cell::Cell<u32>,
+
+    /// Number of queries whose reversed timestamps were merged into a single range.
+    num_merged_reversed_timestamps: std::cell::Cell<u32>,
+}
+
+impl<'a> ResultProcessingContext<'a> {
+    fn new(
+        timestamp_period: f32,
+        backend: Option<wgpu::Backend>,
+        label_resolver: Option<&'a LabelResolver>,
+        settings: &'a GpuProfilerSettings,
+    ) -> Self {
+        Self {
+            timestamp_to_sec: timestamp_period as f64 / 1000.0 / 1000.0 / 1000.0,
+            backend,
+            clock_calibration: None,
+            label_resolver,
+            registered_passes: &[],
+            label_transform: settings.label_transform.as_ref(),
+            min_report_duration: settings.min_report_duration,
+            merge_reversed_timestamps: settings.merge_reversed_timestamps,
+            max_scope_depth: settings.max_scope_depth,
+            debug_group_threshold: settings.debug_group_threshold,
+            slow_scope_label_paths: RefCell::new(HashSet::new()),
+            num_invalid_timestamps: std::cell::Cell::new(0),
+            num_unwritten_timestamps: std::cell::Cell::new(0),
+            num_merged_reversed_timestamps: std::cell::Cell::new(0),
+        }
+    }
+
+    /// Returns the start & end timestamp to use for a scope, or `None` if the timestamps are invalid.
+    ///
+    /// The timestamp counter may wrap around or get reset, e.g. in long running sessions.
+    /// Tiny backwards steps are tolerated as zero length scopes, anything else is counted as invalid
+    /// unless [`GpuProfilerSettings::merge_reversed_timestamps`] is set.
+    ///
+    /// Queries that were reserved but never written, e.g. for a pass that was culled by the driver,
+    /// are resolved to zero. Such timestamps are counted as unwritten instead of being reported as garbage times.
+    fn validate_timestamps(&self, start_raw: u64, end_raw: u64) -> Option<(u64, u64)> {
+        if start_raw == 0 || end_raw == 0 {
+            self.num_unwritten_timestamps
+                .set(self.num_unwritten_timestamps.get() + 1);
+            None
+        } else if end_raw >= start_raw {
+            Some((start_raw, end_raw))
+        } else if start_raw - end_raw <= MAX_BACKWARDS_TIMESTAMP_TICKS {
+            Some((start_raw, start_raw))
+        } else if self.merge_reversed_timestamps {
+            self.num_merged_reversed_timestamps
+                .set(self.num_merged_reversed_timestamps.get() + 1);
+            Some((end_raw, start_raw))
+        } else {
+            self.count_invalid_timestamps();
+            None
+        }
+    }
+
+    fn count_invalid_timestamps(&self) {
+        self.num_invalid_timestamps
+            .set(self.num_invalid_timestamps.get() + 1);
+    }
+
+    /// Converts raw timestamps to a time range in seconds.
+    fn timestamps_to_time(&self, start_raw: u64, end_raw: u64) -> Range<f64> {
+        (start_raw as f64 * self.timestamp_to_sec)..(end_raw as f64 * self.timestamp_to_sec)
+    }
+
+    /// Remembers the label path of a scope if it exceeded [`GpuProfilerSettings::debug_group_threshold`].
+    fn note_scope_duration(&self, label_path_hash: u64, time: Option<&Range<f64>>) {
+        if let (Some(threshold), Some(time)) = (self.debug_group_threshold, time) {
+            if time.end - time.start >= threshold {
+                self.slow_scope_label_paths
+                    .borrow_mut()
+                    .insert(label_path_hash);
+            }
+        }
+    }
+
+    /// Returns true if a result with the given time range should be dropped due to [`GpuProfilerSettings::min_report_duration`].
+    fn is_below_report_duration(&self, time: Option<&Range<f64>>) -> bool {
+        match (self.min_report_duration, time) {
+            (Some(min_duration), Some(time)) => time.end - time.start < min_duration,
+            _ => false,
+        }
+    }
 }
 
-struct PendingFrame {
+struct FrameCapture {
+    num_remaining_frames: usize,
+    frames: Vec<Vec<GpuTimerQueryResult>>,
+}
+
+struct PendingFrame<UserData> {
+    /// Index of the frame, i.e. the number of frames ended before it.
+    frame_index: u64,
+
     query_pools: Vec<Arc<QueryPool>>,
     closed_query_by_parent_handle: HashMap<GpuTimerQueryTreeHandle, Vec<GpuProfilerQuery>>,
 
     /// Keeps track of the number of buffers in the query pool that have been mapped successfully.
     mapped_buffers: std::sync::Arc<std::sync::atomic::AtomicU32>,
+
+    submit_markers: Vec<SubmitMarker>,
+
+    /// Submission index of the last resolve of the frame, see [`FrameInfo::submission_index`].
+    submission_index: Option<wgpu::SubmissionIndex>,
+
+    /// See [`FrameInfo::label`].
+    label: Option<String>,
+
+    /// See [`FrameInfo::user_data`].
+    user_data: Option<UserData>,
+
+    /// Number of frames ended since this frame, see [`GpuProfilerSettings::map_timeout_frames`].
+    age: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::query_byte_offset;
+
+    #[test]
+    fn query_byte_offsets_dont_overflow() {
+        assert_eq!(query_byte_offset(0), 0);
+        assert_eq!(query_byte_offset(3), 3 * wgpu::QUERY_SIZE as u64);
+        // Would overflow in 32 bit.
+        assert_eq!(
+            query_byte_offset(u32::MAX),
+            u32::MAX as u64 * wgpu::QUERY_SIZE as u64
+        );
+    }
 }
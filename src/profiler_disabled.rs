@@ -0,0 +1,613 @@
+//! Stand-in for the profiler if profiling is compiled out via the `disabled` feature.
+//!
+//! Mirrors the public interface of the profiler so that code using it compiles unchanged,
+//! but never creates any query sets or buffers, records any commands or produces any results.
+//! See `profiler.rs` for the documentation of the individual methods.
+
+use std::{marker::PhantomData, ops::Range};
+
+use parking_lot::Mutex;
+
+use crate::{
+    flat_results::GpuTimerQueryResults,
+    profiler_query::{LabelResolver, PassId, ScopeLabel},
+    AccumulatingScopeToken, ClockCalibration, CreationError, DebugScope, EndFrameError,
+    FinishedFrame, FrameInfo, GpuFrameSummary, GpuProfilerFrame, GpuProfilerQuery,
+    GpuProfilerSettings, GpuProfilerStatistics, GpuTimerQueryResult, ManualOwningScope,
+    OwningScope, PendingFrameStatus, ProfilerCommandRecorder, Scope, SettingsError,
+};
+
+/// Profiler instance, compiled out via the `disabled` feature.
+///
+/// Has the same interface as the regular profiler, but all methods do nothing.
+/// Scopes & queries can still be opened and closed, frames can still be ended,
+/// but no results are ever returned.
+pub struct GpuProfiler<UserData = ()> {
+    /// Process id, queried once on creation since it can't change.
+    process_id: u32,
+
+    backend: Option<wgpu::Backend>,
+    clock_calibration: Option<ClockCalibration>,
+    adapter_info: Option<wgpu::AdapterInfo>,
+
+    /// Only used for labeling passes opened via the scope types, see [`GpuProfiler::with_label_str`].
+    label_resolver: Option<Box<LabelResolver>>,
+    registered_passes: Vec<String>,
+    active_encoder_tag: Option<u32>,
+
+    /// User data is dropped right away, this only keeps the same auto traits as the regular profiler.
+    user_data: PhantomData<Mutex<UserData>>,
+}
+
+// Public interface
+impl GpuProfiler {
+    /// Combination of all timer query features [`GpuProfiler`] can leverage.
+    pub const ALL_WGPU_TIMER_FEATURES: wgpu::Features = wgpu::Features::TIMESTAMP_QUERY
+        .union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS)
+        .union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES);
+
+    /// Combination of all timer query features [`GpuProfiler`] can leverage.
+    #[deprecated(since = "0.9.0", note = "Use ALL_WGPU_TIMER_FEATURES instead")]
+    pub const REQUIRED_WGPU_FEATURES: wgpu::Features = GpuProfiler::ALL_WGPU_TIMER_FEATURES;
+
+    /// Channel of all scopes that don't specify a channel, see [`GpuProfilerSettings::enabled_channels`].
+    pub const DEFAULT_CHANNEL: u32 = 1;
+
+    pub fn new(settings: GpuProfilerSettings) -> Result<Self, CreationError> {
+        Self::new_with_user_data(settings)
+    }
+
+    /// Doesn't connect to Tracy, since there are no timings to report.
+    #[cfg(feature = "tracy")]
+    pub fn new_with_tracy_client(
+        settings: GpuProfilerSettings,
+        backend: wgpu::Backend,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> Result<Self, CreationError> {
+        Ok(Self::new(settings)?.with_backend(backend))
+    }
+
+    /// Doesn't connect to Tracy, since there are no timings to report.
+    #[cfg(feature = "tracy")]
+    pub async fn new_with_tracy_client_async(
+        settings: GpuProfilerSettings,
+        backend: wgpu::Backend,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> Result<Self, CreationError> {
+        Ok(Self::new(settings)?.with_backend(backend))
+    }
+
+    #[cfg(feature = "tracy")]
+    pub fn with_tracy_context(
+        settings: GpuProfilerSettings,
+        _context: tracy_client::GpuContext,
+    ) -> Result<Self, CreationError> {
+        Self::new(settings)
+    }
+}
+
+impl<U> GpuProfiler<U> {
+    /// Still validates the settings, so that invalid settings aren't only noticed once profiling is compiled in.
+    pub fn new_with_user_data(settings: GpuProfilerSettings) -> Result<Self, CreationError> {
+        settings.validate()?;
+
+        Ok(Self {
+            process_id: if cfg!(target_arch = "wasm32") {
+                0
+            } else {
+                std::process::id()
+            },
+            backend: None,
+            clock_calibration: None,
+            adapter_info: None,
+            label_resolver: None,
+            registered_passes: Vec::new(),
+            active_encoder_tag: None,
+            user_data: PhantomData,
+        })
+    }
+
+    #[must_use]
+    pub fn with_backend(mut self, backend: wgpu::Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    pub fn backend(&self) -> Option<wgpu::Backend> {
+        self.backend
+    }
+
+    pub fn timestamp_resolution_hint(&self) -> Option<f64> {
+        None
+    }
+
+    pub fn set_clock_calibration(&mut self, clock_calibration: ClockCalibration) {
+        self.clock_calibration = Some(clock_calibration);
+    }
+
+    pub fn clock_calibration(&self) -> Option<ClockCalibration> {
+        self.clock_calibration
+    }
+
+    #[must_use]
+    pub fn with_adapter_info(mut self, adapter_info: wgpu::AdapterInfo) -> Self {
+        self.backend = Some(adapter_info.backend);
+        self.adapter_info = Some(adapter_info);
+        self
+    }
+
+    pub fn adapter_info(&self) -> Option<&wgpu::AdapterInfo> {
+        self.adapter_info.as_ref()
+    }
+
+    pub fn change_settings(&mut self, settings: GpuProfilerSettings) -> Result<(), SettingsError> {
+        settings.validate()
+    }
+
+    pub fn set_label_resolver(&mut self, resolver: impl Fn(u32) -> String + Send + Sync + 'static) {
+        self.label_resolver = Some(Box::new(resolver));
+    }
+
+    pub fn register_passes(
+        &mut self,
+        names: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Vec<PassId> {
+        names
+            .into_iter()
+            .map(|name| {
+                let id = PassId(self.registered_passes.len() as u32);
+                self.registered_passes.push(name.as_ref().to_owned());
+                id
+            })
+            .collect()
+    }
+
+    pub fn pass_name(&self, pass: PassId) -> Option<&str> {
+        self.registered_passes
+            .get(pass.0 as usize)
+            .map(String::as_str)
+    }
+
+    pub fn set_active_encoder_tag(&mut self, tag: Option<u32>) {
+        self.active_encoder_tag = tag;
+    }
+
+    pub fn active_encoder_tag(&self) -> Option<u32> {
+        self.active_encoder_tag
+    }
+
+    pub fn statistics(&self) -> GpuProfilerStatistics {
+        GpuProfilerStatistics::default()
+    }
+
+    pub fn open_scope_depth(&self) -> u32 {
+        0
+    }
+
+    pub fn peak_queries_per_frame(&self) -> u32 {
+        0
+    }
+
+    pub fn pending_frame_status(&self) -> Vec<PendingFrameStatus> {
+        Vec::new()
+    }
+
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn scope<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        label: impl Into<ScopeLabel>,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Scope<'a, Recorder, U> {
+        self.scope_in_channel(label, GpuProfiler::DEFAULT_CHANNEL, encoder_or_pass, device)
+    }
+
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn scope_in_channel<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        label: impl Into<ScopeLabel>,
+        channel: u32,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Scope<'a, Recorder, U> {
+        let scope = self.begin_query_in_channel(label, channel, encoder_or_pass, device);
+        Scope {
+            profiler: self,
+            recorder: encoder_or_pass,
+            scope: Some(scope),
+        }
+    }
+
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn tagged_scope<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        _tag: u32,
+        label: impl Into<ScopeLabel>,
+        encoder_or_pass: &'a mut Recorder,
+        device: &wgpu::Device,
+    ) -> Scope<'a, Recorder, U> {
+        self.scope(label, encoder_or_pass, device)
+    }
+
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn owning_scope<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        label: impl Into<ScopeLabel>,
+        mut encoder_or_pass: Recorder,
+        device: &wgpu::Device,
+    ) -> OwningScope<'a, Recorder, U> {
+        let scope = self.begin_query(label, &mut encoder_or_pass, device);
+        OwningScope {
+            profiler: self,
+            recorder: encoder_or_pass,
+            scope: Some(scope),
+        }
+    }
+
+    #[must_use]
+    #[track_caller]
+    #[inline]
+    pub fn manual_owning_scope<'a, Recorder: ProfilerCommandRecorder>(
+        &'a self,
+        label: impl Into<ScopeLabel>,
+        mut encoder_or_pass: Recorder,
+        device: &wgpu::Device,
+    ) -> ManualOwningScope<'a, Recorder, U> {
+        let scope = self.begin_query(label, &mut encoder_or_pass, device);
+        ManualOwningScope {
+            profiler: self,
+            recorder: encoder_or_pass,
+            scope: Some(scope),
+        }
+    }
+
+    /// Still pushes the debug group, since debug scopes are labeling rather than profiling.
+    #[must_use]
+    #[inline]
+    pub fn debug_scope<'a, Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: &str,
+        encoder_or_pass: &'a mut Recorder,
+    ) -> DebugScope<'a, Recorder> {
+        encoder_or_pass.push_debug_group(label);
+        DebugScope {
+            recorder: encoder_or_pass,
+        }
+    }
+
+    pub fn accumulating_scope(&self, label: impl Into<String>) -> AccumulatingScopeToken {
+        AccumulatingScopeToken {
+            label: label.into(),
+            handle: 0,
+        }
+    }
+
+    #[track_caller]
+    #[must_use]
+    pub fn begin_accumulating_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        token: &AccumulatingScopeToken,
+        _encoder_or_pass: &mut Recorder,
+        _device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.untimed_query(token.label.clone())
+    }
+
+    #[track_caller]
+    #[must_use]
+    pub fn begin_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<ScopeLabel>,
+        _encoder_or_pass: &mut Recorder,
+        _device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.untimed_query(label)
+    }
+
+    #[track_caller]
+    #[must_use]
+    pub fn begin_query_in_channel<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<ScopeLabel>,
+        _channel: u32,
+        _encoder_or_pass: &mut Recorder,
+        _device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.untimed_query(label)
+    }
+
+    pub fn reserve_scopes(&self, _num_scopes: u32, _device: &wgpu::Device) -> ReservedScopes {
+        ReservedScopes { _private: () }
+    }
+
+    #[track_caller]
+    #[must_use]
+    pub fn begin_reserved_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        _reserved: &mut ReservedScopes,
+        label: impl Into<ScopeLabel>,
+        _encoder_or_pass: &mut Recorder,
+        _device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.untimed_query(label)
+    }
+
+    #[track_caller]
+    pub(crate) fn begin_encoder_or_pass_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        label: impl Into<ScopeLabel>,
+        _options: QueryOptions<'_>,
+        _encoder_or_pass: &mut Recorder,
+        _device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.untimed_query(label)
+    }
+
+    #[track_caller]
+    pub fn begin_pass_query(
+        &self,
+        label: impl Into<ScopeLabel>,
+        _encoder: &mut wgpu::CommandEncoder,
+        _device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.untimed_query(label)
+    }
+
+    #[track_caller]
+    pub fn begin_pass_query_in_channel(
+        &self,
+        label: impl Into<ScopeLabel>,
+        _channel: u32,
+        _encoder: &mut wgpu::CommandEncoder,
+        _device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.untimed_query(label)
+    }
+
+    #[track_caller]
+    pub(crate) fn begin_pass_query_with_options(
+        &self,
+        label: impl Into<ScopeLabel>,
+        _options: QueryOptions<'_>,
+        _encoder: &mut wgpu::CommandEncoder,
+        _device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.untimed_query(label)
+    }
+
+    #[track_caller]
+    pub fn begin_raw_query(
+        &self,
+        label: impl Into<ScopeLabel>,
+        _encoder: &mut wgpu::CommandEncoder,
+        _device: &wgpu::Device,
+    ) -> GpuProfilerQuery {
+        self.untimed_query(label)
+    }
+
+    pub fn end_raw_query(&self, _query: GpuProfilerQuery) {}
+
+    pub fn end_query_labeled<Recorder: ProfilerCommandRecorder>(
+        &self,
+        _encoder_or_pass: &mut Recorder,
+        _query: GpuProfilerQuery,
+        _label: impl Into<ScopeLabel>,
+    ) {
+    }
+
+    pub fn end_query<Recorder: ProfilerCommandRecorder>(
+        &self,
+        _encoder_or_pass: &mut Recorder,
+        _query: GpuProfilerQuery,
+    ) {
+    }
+
+    pub fn cancel_pass_query(&self, _query: GpuProfilerQuery) {}
+
+    pub fn resolve_queries(&self, _encoder: &mut wgpu::CommandEncoder) -> Vec<ResolvedRange> {
+        Vec::new()
+    }
+
+    pub fn resolve_queries_into_buffer(
+        &self,
+        _encoder: &mut wgpu::CommandEncoder,
+        _destination: &wgpu::Buffer,
+    ) -> Vec<ResolvedRange> {
+        Vec::new()
+    }
+
+    /// Still submits the command buffers, only the tracking of the submission order is compiled out.
+    pub fn submit<I: IntoIterator<Item = wgpu::CommandBuffer>>(
+        &self,
+        queue: &wgpu::Queue,
+        command_buffers: I,
+    ) -> wgpu::SubmissionIndex {
+        queue.submit(command_buffers)
+    }
+
+    pub fn note_submit(&self, _label: impl Into<String>) {}
+
+    pub fn num_frame_timestamps(&self) -> u32 {
+        0
+    }
+
+    pub fn begin_frame(&self) -> GpuProfilerFrame<'_, U> {
+        GpuProfilerFrame::new(self)
+    }
+
+    pub fn end_frame(&self) -> Result<(), EndFrameError> {
+        self.end_frame_with(FrameInfo::default())
+    }
+
+    pub fn end_frame_with(&self, _info: FrameInfo<U>) -> Result<(), EndFrameError> {
+        Ok(())
+    }
+
+    pub fn has_stuck_frames(&self, _max_age_frames: u64) -> bool {
+        false
+    }
+
+    pub fn clear_pending_frames(&self) {}
+
+    pub fn shrink_to_fit(&mut self) {}
+
+    pub fn capture_frames(&mut self, _num_frames: usize) {}
+
+    /// Always false, since a capture would never receive any frames.
+    pub fn is_capturing(&self) -> bool {
+        false
+    }
+
+    pub fn take_dropped_frame_indices(&self) -> Vec<u64> {
+        Vec::new()
+    }
+
+    pub fn recent_history(&self) -> Vec<GpuFrameSummary> {
+        Vec::new()
+    }
+
+    pub fn take_capture(&mut self) -> Vec<Vec<GpuTimerQueryResult>> {
+        Vec::new()
+    }
+
+    pub fn process_finished_frame(&self, _timestamp_period: f32) -> Option<FinishedFrame<U>> {
+        None
+    }
+
+    pub fn process_all_finished_frames(&self, _timestamp_period: f32) -> Vec<FinishedFrame<U>> {
+        Vec::new()
+    }
+
+    pub fn process_finished_frame_blocking(
+        &self,
+        _device: &wgpu::Device,
+        _timestamp_period: f32,
+    ) -> Option<FinishedFrame<U>> {
+        None
+    }
+
+    pub fn process_finished_frame_flat(
+        &self,
+        _timestamp_period: f32,
+    ) -> Option<FinishedFrame<U, GpuTimerQueryResults>> {
+        None
+    }
+}
+
+/// Buffer regions written by [`GpuProfiler::resolve_queries`] for a single query pool.
+///
+/// Never produced, since no queries are resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRange {
+    pub pool_index: usize,
+    pub resolve_byte_range: Range<u64>,
+    pub read_byte_range: Option<Range<u64>>,
+    pub destination_byte_range: Option<Range<u64>>,
+}
+
+/// Timer queries reserved up front for several scopes, always empty.
+#[derive(Debug)]
+pub struct ReservedScopes {
+    _private: (),
+}
+
+impl ReservedScopes {
+    pub fn remaining(&self) -> u32 {
+        0
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Internals
+// --------------------------------------------------------------------------------
+
+impl<U> GpuProfiler<U> {
+    /// Creates a query that keeps its label but never has any timer queries or debug groups.
+    fn untimed_query(&self, label: impl Into<ScopeLabel>) -> GpuProfilerQuery {
+        let (label, label_id, pass_id) = label.into().into_parts();
+        GpuProfilerQuery {
+            label,
+            label_id,
+            pass_id,
+            pid: self.process_id,
+            tid: std::thread::current().id(),
+            unit_count: None,
+            category: None,
+        }
+    }
+
+    /// Calls `f` with the label of a query, used as the label of passes opened via the scope types.
+    pub(crate) fn with_label_str<R>(
+        &self,
+        query: &GpuProfilerQuery,
+        f: impl FnOnce(&str) -> R,
+    ) -> R {
+        if !query.label.is_empty() {
+            return f(&query.label);
+        }
+        if let Some(name) = query.pass_id.and_then(|pass| self.pass_name(pass)) {
+            return f(name);
+        }
+        match query.label_id {
+            Some(id) => match &self.label_resolver {
+                Some(resolver) => f(&resolver(id)),
+                None => f(&id.to_string()),
+            },
+            None => f(""),
+        }
+    }
+
+    #[cfg(feature = "test-support")]
+    pub(crate) fn build_results_from_raw(
+        _scopes: &[crate::test_support::RawScope],
+        _timestamp_period: f32,
+        _settings: &GpuProfilerSettings,
+    ) -> Vec<GpuTimerQueryResult> {
+        Vec::new()
+    }
+}
+
+/// Internal handle to building a tree of profiling queries.
+pub type GpuTimerQueryTreeHandle = u32;
+
+/// Options shared by all ways of opening a query.
+///
+/// Same fields as for the regular profiler, so that the scope types can fill them in,
+/// but they are ignored since no queries are recorded.
+#[allow(dead_code)]
+pub(crate) struct QueryOptions<'a> {
+    pub(crate) channel: u32,
+    pub(crate) encoder_tag: Option<u32>,
+    pub(crate) parent: Option<&'a GpuProfilerQuery>,
+    pub(crate) reserved: Option<&'a mut ReservedScopes>,
+}
+
+impl<'a> QueryOptions<'a> {
+    pub(crate) fn nested_in(parent: Option<&'a GpuProfilerQuery>) -> Self {
+        Self {
+            parent,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for QueryOptions<'_> {
+    fn default() -> Self {
+        Self {
+            channel: GpuProfiler::DEFAULT_CHANNEL,
+            encoder_tag: None,
+            parent: None,
+            reserved: None,
+        }
+    }
+}
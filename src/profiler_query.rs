@@ -1,8 +1,8 @@
-use std::{ops::Range, thread::ThreadId};
+use std::{collections::HashMap, ops::Range, thread::ThreadId, time::SystemTime};
 
-use crate::profiler::{
-    GpuTimerQueryTreeHandle, QueryPairUsageState, ReservedTimerQueryPair, ROOT_QUERY_HANDLE,
-};
+#[cfg(not(feature = "disabled"))]
+use crate::profiler::{QueryPairUsageState, ReservedTimerQueryPair, ROOT_QUERY_HANDLE};
+use crate::{profiler::GpuTimerQueryTreeHandle, ClockCalibration};
 
 /// The result of a gpu timer scope.
 #[derive(Debug, Clone)]
@@ -11,21 +11,557 @@ pub struct GpuTimerQueryResult {
     pub label: String,
 
     /// The process id of the process that opened this scope.
+    ///
+    /// 0 if [`GpuProfilerSettings::capture_thread_info`] was disabled.
+    ///
+    /// [`GpuProfilerSettings::capture_thread_info`]: crate::GpuProfilerSettings::capture_thread_info
     pub pid: u32,
 
     /// The thread id of the thread that opened this scope.
+    ///
+    /// The thread the profiler was created on if [`GpuProfilerSettings::capture_thread_info`] was disabled.
+    ///
+    /// [`GpuProfilerSettings::capture_thread_info`]: crate::GpuProfilerSettings::capture_thread_info
     pub tid: ThreadId,
 
+    /// The backend that produced the timings of this scope.
+    ///
+    /// Only known if the profiler was told about it, see [`GpuProfiler::with_backend`].
+    ///
+    /// [`GpuProfiler::with_backend`]: crate::GpuProfiler::with_backend
+    pub backend: Option<wgpu::Backend>,
+
+    /// Calibration for converting [`GpuTimerQueryResult::time`] to wall clock time, see [`GpuTimerQueryResult::absolute_time`].
+    ///
+    /// Only known if the profiler was given one, see [`GpuProfiler::set_clock_calibration`].
+    ///
+    /// [`GpuProfiler::set_clock_calibration`]: crate::GpuProfiler::set_clock_calibration
+    pub clock_calibration: Option<ClockCalibration>,
+
     /// Time range of this scope in seconds.
     ///
     /// Meaning of absolute value is not defined.
     /// If timestamp writing was disabled for this scope, this is None.
     pub time: Option<Range<f64>>,
 
+    /// Number of work units (e.g. dispatches or draws) attached to this scope via [`GpuProfilerQuery::unit_count`].
+    ///
+    /// Accumulating scopes report the sum of all their regions.
+    pub unit_count: Option<u32>,
+
+    /// Category attached to this scope via [`GpuProfilerQuery::category`].
+    ///
+    /// See [`GpuTimerQueryAggregator::totals_by_category`](crate::GpuTimerQueryAggregator::totals_by_category).
+    pub category: Option<&'static str>,
+
+    /// Number of timed regions that were merged into this result, `None` unless this is an accumulating scope.
+    ///
+    /// See [`GpuProfiler::accumulating_scope`](crate::GpuProfiler::accumulating_scope)
+    /// and [`GpuTimerQueryResult::average_duration_secs`].
+    pub num_samples: Option<u32>,
+
+    /// Additional backend specific metrics of this scope, keyed by metric name.
+    ///
+    /// A forward compatible place for counters beyond timestamps that only some backends support.
+    /// The profiler doesn't record any such counters yet, so this is empty unless populated by hand,
+    /// e.g. via [`GpuTimerQueryResult::with_extra_metric`].
+    /// Written as event arguments by [`chrometrace::write_chrometrace`](crate::chrometrace::write_chrometrace).
+    pub extra_metrics: HashMap<String, f64>,
+
     /// Scopes that were opened while this scope was open.
     pub nested_queries: Vec<GpuTimerQueryResult>,
 }
 
+impl GpuTimerQueryResult {
+    /// Creates a result without timing information or nested results, e.g. for testing code that consumes results.
+    ///
+    /// Process & thread id are those of the calling thread.
+    /// Use the `with_*` methods to fill in the remaining fields.
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            pid: std::process::id(),
+            tid: std::thread::current().id(),
+            backend: None,
+            clock_calibration: None,
+            time: None,
+            unit_count: None,
+            category: None,
+            num_samples: None,
+            extra_metrics: HashMap::new(),
+            nested_queries: Vec::new(),
+        }
+    }
+
+    /// Sets the time range of this result in seconds, see [`GpuTimerQueryResult::time`].
+    #[must_use]
+    #[inline]
+    pub fn with_time(self, time: Range<f64>) -> Self {
+        Self {
+            time: Some(time),
+            ..self
+        }
+    }
+
+    /// Sets the category of this result, see [`GpuTimerQueryResult::category`].
+    #[must_use]
+    #[inline]
+    pub fn with_category(self, category: &'static str) -> Self {
+        Self {
+            category: Some(category),
+            ..self
+        }
+    }
+
+    /// Sets the number of work units of this result, see [`GpuTimerQueryResult::unit_count`].
+    #[must_use]
+    #[inline]
+    pub fn with_unit_count(self, unit_count: u32) -> Self {
+        Self {
+            unit_count: Some(unit_count),
+            ..self
+        }
+    }
+
+    /// Sets the nested results of this result, see [`GpuTimerQueryResult::nested_queries`].
+    #[must_use]
+    #[inline]
+    pub fn with_nested_queries(self, nested_queries: impl Into<Vec<GpuTimerQueryResult>>) -> Self {
+        Self {
+            nested_queries: nested_queries.into(),
+            ..self
+        }
+    }
+
+    /// Adds a metric to this result, see [`GpuTimerQueryResult::extra_metrics`].
+    #[must_use]
+    #[inline]
+    pub fn with_extra_metric(mut self, name: impl Into<String>, value: f64) -> Self {
+        self.extra_metrics.insert(name.into(), value);
+        self
+    }
+
+    /// Wall clock time at which this scope started.
+    ///
+    /// `None` if this scope has no timing information or no [`GpuTimerQueryResult::clock_calibration`] is known.
+    pub fn absolute_time(&self) -> Option<SystemTime> {
+        let time = self.time.as_ref()?;
+        self.clock_calibration?.to_system_time(time.start)
+    }
+
+    /// Duration of this scope in seconds, `None` if this scope has no timing information.
+    #[inline]
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.time.as_ref().map(|time| time.end - time.start)
+    }
+
+    /// Duration of this scope in milliseconds, `None` if this scope has no timing information.
+    #[inline]
+    pub fn duration_ms(&self) -> Option<f64> {
+        self.duration_secs().map(|duration| duration * 1000.0)
+    }
+
+    /// Duration of this scope in microseconds, `None` if this scope has no timing information.
+    #[inline]
+    pub fn duration_us(&self) -> Option<f64> {
+        self.duration_secs()
+            .map(|duration| duration * 1000.0 * 1000.0)
+    }
+
+    /// Average duration in seconds of the regions of an accumulating scope, i.e. the duration divided by [`GpuTimerQueryResult::num_samples`].
+    ///
+    /// Measuring the same work several times within a frame via an accumulating scope gives stable numbers
+    /// even if a single run is close to the timer resolution.
+    /// Same as [`GpuTimerQueryResult::duration_secs`] for regular scopes.
+    pub fn average_duration_secs(&self) -> Option<f64> {
+        let duration = self.duration_secs()?;
+        match self.num_samples {
+            Some(num_samples) if num_samples > 0 => Some(duration / num_samples as f64),
+            _ => Some(duration),
+        }
+    }
+
+    /// Exclusive time of this scope in seconds, i.e. the time not covered by any nested scope.
+    ///
+    /// Computed as this scope's duration minus the union of all nested scope time ranges,
+    /// so that overlapping nested scopes aren't subtracted twice.
+    /// Nested scopes without timing information are looked through, taking their timed nested scopes into account instead.
+    ///
+    /// Returns `None` if this scope has no timing information.
+    pub fn self_time(&self) -> Option<f64> {
+        let time = self.time.as_ref()?;
+
+        let mut nested_ranges = Vec::new();
+        collect_timed_ranges(&self.nested_queries, &mut nested_ranges);
+        for range in &mut nested_ranges {
+            range.start = range.start.clamp(time.start, time.end);
+            range.end = range.end.clamp(time.start, time.end);
+        }
+        let covered = union_length(nested_ranges);
+
+        Some(((time.end - time.start) - covered).max(0.0))
+    }
+
+    /// Duration of this scope in seconds divided by the passed number of work units,
+    /// e.g. to get the average time per dispatch or draw.
+    ///
+    /// Returns `None` if this scope has no timing information or `count` is zero.
+    /// See also [`GpuTimerQueryResult::unit_count`].
+    pub fn time_per_unit(&self, count: u32) -> Option<f64> {
+        let duration = self.duration_secs()?;
+        (count > 0).then(|| duration / count as f64)
+    }
+
+    /// Time in seconds the GPU was busy with any of the passed scopes.
+    ///
+    /// Computed as the length of the union of all time ranges, so overlapping scopes (e.g. from
+    /// different command buffers) aren't counted twice, while gaps between scopes are not counted at all.
+    /// Scopes without timing information are looked through, taking their timed nested scopes into account instead.
+    pub fn gpu_busy_time(results: &[GpuTimerQueryResult]) -> f64 {
+        gpu_busy_time_of(results)
+    }
+
+    /// Fraction of a frame budget of `target_ms` milliseconds the GPU was busy with the passed scopes.
+    ///
+    /// Uses [`GpuTimerQueryResult::gpu_busy_time`], i.e. `1.0` means the GPU was busy for the entire budget.
+    /// Values above `1.0` indicate that the budget was exceeded.
+    pub fn utilization(results: &[GpuTimerQueryResult], target_ms: f32) -> f32 {
+        (Self::gpu_busy_time(results) * 1000.0 / target_ms as f64) as f32
+    }
+
+    /// Visits this result and all its nested results in depth-first pre-order.
+    ///
+    /// The visitor receives each result along with its depth (this result having depth 0)
+    /// and its index among its siblings. Does not allocate.
+    pub fn walk(&self, visitor: &mut impl FnMut(&GpuTimerQueryResult, usize, usize)) {
+        visitor(self, 0, 0);
+        walk_recursive(&self.nested_queries, 1, visitor);
+    }
+
+    /// Visits all passed results and their nested results in depth-first pre-order.
+    ///
+    /// Like [`GpuTimerQueryResult::walk`], but for a list of root results,
+    /// e.g. as returned by [`GpuProfiler::process_finished_frame`].
+    ///
+    /// [`GpuProfiler::process_finished_frame`]: crate::GpuProfiler::process_finished_frame
+    pub fn walk_all(
+        results: &[GpuTimerQueryResult],
+        visitor: &mut impl FnMut(&GpuTimerQueryResult, usize, usize),
+    ) {
+        walk_recursive(results, 0, visitor);
+    }
+
+    /// Returns the `k` longest scopes among the passed results and all their nested results, longest first.
+    ///
+    /// Scopes without timing information are skipped.
+    /// Useful for showing only the most expensive scopes of a frame, e.g. in a compact overlay.
+    pub fn top_k(results: &[GpuTimerQueryResult], k: usize) -> Vec<&GpuTimerQueryResult> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut timed = Vec::new();
+        collect_timed_results(results, &mut timed);
+        let by_duration_descending =
+            |a: &(&GpuTimerQueryResult, f64), b: &(&GpuTimerQueryResult, f64)| b.1.total_cmp(&a.1);
+        if k < timed.len() {
+            timed.select_nth_unstable_by(k - 1, by_duration_descending);
+            timed.truncate(k);
+        }
+        timed.sort_by(by_duration_descending);
+        timed.into_iter().map(|(result, _)| result).collect()
+    }
+
+    /// Finds sibling scopes whose time ranges overlap.
+    ///
+    /// Scopes recorded one after another on the same encoder or pass shouldn't overlap,
+    /// so an overlap hints at an instrumentation mistake or a driver anomaly.
+    /// Siblings opened on different threads are usually recorded on different encoders that may legitimately run
+    /// concurrently, so these are not checked against each other. Neither are accumulating scopes,
+    /// since their time range doesn't correspond to a single region.
+    pub fn check_no_overlap(results: &[GpuTimerQueryResult]) -> Vec<OverlapWarning> {
+        let mut warnings = Vec::new();
+        let mut label_path = String::new();
+        check_no_overlap_recursive(results, &mut label_path, &mut warnings);
+        warnings
+    }
+}
+
+/// Two sibling scopes with overlapping time ranges, found by [`GpuTimerQueryResult::check_no_overlap`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlapWarning {
+    /// Labels of all scopes enclosing the overlapping scopes, joined with [`LABEL_PATH_SEPARATOR`].
+    ///
+    /// Empty if the overlapping scopes are root scopes.
+    ///
+    /// [`LABEL_PATH_SEPARATOR`]: crate::LABEL_PATH_SEPARATOR
+    pub parent_label_path: String,
+
+    /// Label of the scope that started first.
+    pub first_label: String,
+
+    /// Label of the scope that started second.
+    pub second_label: String,
+
+    /// Duration of the overlap in seconds.
+    pub overlap: f64,
+}
+
+fn collect_timed_results<'a>(
+    results: &'a [GpuTimerQueryResult],
+    timed: &mut Vec<(&'a GpuTimerQueryResult, f64)>,
+) {
+    for result in results {
+        if let Some(duration) = result.duration_secs() {
+            timed.push((result, duration));
+        }
+        collect_timed_results(&result.nested_queries, timed);
+    }
+}
+
+fn check_no_overlap_recursive(
+    results: &[GpuTimerQueryResult],
+    label_path: &mut String,
+    warnings: &mut Vec<OverlapWarning>,
+) {
+    let mut timed = results
+        .iter()
+        .filter(|result| result.num_samples.is_none())
+        .filter_map(|result| result.time.as_ref().map(|time| (result, time)))
+        .collect::<Vec<_>>();
+    timed.sort_by(|(_, a), (_, b)| a.start.total_cmp(&b.start));
+
+    for (index, (first, first_time)) in timed.iter().enumerate() {
+        for (second, second_time) in timed[index + 1..]
+            .iter()
+            .take_while(|(_, second_time)| second_time.start < first_time.end)
+        {
+            if first.tid != second.tid {
+                continue;
+            }
+            warnings.push(OverlapWarning {
+                parent_label_path: label_path.clone(),
+                first_label: first.label.clone(),
+                second_label: second.label.clone(),
+                overlap: first_time.end.min(second_time.end) - second_time.start,
+            });
+        }
+    }
+
+    for result in results {
+        let parent_path_len = label_path.len();
+        if parent_path_len > 0 {
+            label_path.push_str(crate::LABEL_PATH_SEPARATOR);
+        }
+        label_path.push_str(&result.label);
+        check_no_overlap_recursive(&result.nested_queries, label_path, warnings);
+        label_path.truncate(parent_path_len);
+    }
+}
+
+/// Length of the union of all passed ranges.
+pub(crate) fn union_length(mut ranges: Vec<Range<f64>>) -> f64 {
+    ranges.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let mut covered = 0.0;
+    let mut current: Option<Range<f64>> = None;
+    for range in ranges {
+        match &mut current {
+            Some(current) if range.start <= current.end => {
+                current.end = current.end.max(range.end);
+            }
+            _ => {
+                if let Some(previous) = current.replace(range) {
+                    covered += previous.end - previous.start;
+                }
+            }
+        }
+    }
+    if let Some(last) = current {
+        covered += last.end - last.start;
+    }
+
+    covered
+}
+
+/// Collects the time ranges of all passed results, descending into results without timing information.
+/// Like [`GpuTimerQueryResult::gpu_busy_time`], but for results that aren't stored contiguously.
+pub(crate) fn gpu_busy_time_of<'a>(
+    results: impl IntoIterator<Item = &'a GpuTimerQueryResult>,
+) -> f64 {
+    let mut ranges = Vec::new();
+    collect_timed_ranges(results, &mut ranges);
+    union_length(ranges)
+}
+
+fn collect_timed_ranges<'a>(
+    results: impl IntoIterator<Item = &'a GpuTimerQueryResult>,
+    ranges: &mut Vec<Range<f64>>,
+) {
+    for result in results {
+        if let Some(time) = &result.time {
+            ranges.push(time.clone());
+        } else {
+            collect_timed_ranges(&result.nested_queries, ranges);
+        }
+    }
+}
+
+fn walk_recursive(
+    results: &[GpuTimerQueryResult],
+    depth: usize,
+    visitor: &mut impl FnMut(&GpuTimerQueryResult, usize, usize),
+) {
+    for (sibling_index, result) in results.iter().enumerate() {
+        visitor(result, depth, sibling_index);
+        walk_recursive(&result.nested_queries, depth + 1, visitor);
+    }
+}
+
+/// Label of a profiler scope.
+///
+/// Numeric labels avoid allocating a string for every scope on the hot path.
+/// They are turned into strings by the resolver registered with [`GpuProfiler::set_label_resolver`]
+/// once results are processed, so [`GpuTimerQueryResult::label`] always carries the resolved string.
+///
+/// [`GpuProfiler::set_label_resolver`]: crate::GpuProfiler::set_label_resolver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScopeLabel {
+    /// A regular string label.
+    Str(String),
+
+    /// A numeric label, resolved to a string later on.
+    Id(u32),
+
+    /// A pass registered with [`GpuProfiler::register_passes`], labeled with its registered name.
+    ///
+    /// Pass ids have their own id space, they never collide with [`ScopeLabel::Id`].
+    ///
+    /// [`GpuProfiler::register_passes`]: crate::GpuProfiler::register_passes
+    Pass(PassId),
+}
+
+impl ScopeLabel {
+    /// Splits the label into a string label and numeric labels, at most one of which is set.
+    pub(crate) fn into_parts(self) -> (String, Option<u32>, Option<PassId>) {
+        match self {
+            ScopeLabel::Str(label) => (label, None, None),
+            ScopeLabel::Id(id) => (String::new(), Some(id), None),
+            ScopeLabel::Pass(pass) => (String::new(), None, Some(pass)),
+        }
+    }
+}
+
+impl From<String> for ScopeLabel {
+    #[inline]
+    fn from(label: String) -> Self {
+        ScopeLabel::Str(label)
+    }
+}
+
+impl From<&str> for ScopeLabel {
+    #[inline]
+    fn from(label: &str) -> Self {
+        ScopeLabel::Str(label.to_owned())
+    }
+}
+
+impl From<&String> for ScopeLabel {
+    #[inline]
+    fn from(label: &String) -> Self {
+        ScopeLabel::Str(label.clone())
+    }
+}
+
+impl From<std::borrow::Cow<'_, str>> for ScopeLabel {
+    #[inline]
+    fn from(label: std::borrow::Cow<'_, str>) -> Self {
+        ScopeLabel::Str(label.into_owned())
+    }
+}
+
+impl From<u32> for ScopeLabel {
+    #[inline]
+    fn from(id: u32) -> Self {
+        ScopeLabel::Id(id)
+    }
+}
+
+impl From<PassId> for ScopeLabel {
+    #[inline]
+    fn from(pass: PassId) -> Self {
+        ScopeLabel::Pass(pass)
+    }
+}
+
+/// Stable identifier of a pass registered with [`GpuProfiler::register_passes`].
+///
+/// Opening a scope with a pass id doesn't do any string work,
+/// the registered name is looked up once results are processed.
+///
+/// [`GpuProfiler::register_passes`]: crate::GpuProfiler::register_passes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PassId(pub(crate) u32);
+
+impl PassId {
+    /// Index of this pass in the order of registration.
+    ///
+    /// Unrelated to numeric labels, i.e. [`ScopeLabel::Id`] with the same value labels a different scope.
+    #[inline]
+    pub fn id(self) -> u32 {
+        self.0
+    }
+}
+
+/// CPU-side marker of a queue submission, recorded with [`GpuProfiler::note_submit`].
+///
+/// [`GpuProfiler::note_submit`]: crate::GpuProfiler::note_submit
+#[derive(Debug, Clone)]
+pub struct SubmitMarker {
+    /// Label that was passed when noting the submit.
+    pub label: String,
+
+    /// CPU time at which the submit was noted.
+    pub time: std::time::Instant,
+}
+
+/// Maps numeric scope labels to strings, see [`ScopeLabel::Id`].
+pub(crate) type LabelResolver = dyn Fn(u32) -> String + Send + Sync;
+
+/// Token identifying a logical scope whose regions are accumulated into a single result.
+///
+/// Created by [`GpuProfiler::accumulating_scope`], regions are started with [`GpuProfiler::begin_accumulating_query`].
+/// The resulting [`GpuTimerQueryResult::time`] starts with the earliest region and is as long as all regions combined.
+///
+/// [`GpuProfiler::accumulating_scope`]: crate::GpuProfiler::accumulating_scope
+/// [`GpuProfiler::begin_accumulating_query`]: crate::GpuProfiler::begin_accumulating_query
+#[derive(Debug, Clone)]
+pub struct AccumulatingScopeToken {
+    /// Label used for all regions of this scope.
+    pub label: String,
+
+    /// Handle shared by all regions of this scope.
+    #[cfg_attr(feature = "disabled", allow(dead_code))]
+    pub(crate) handle: GpuTimerQueryTreeHandle,
+}
+
+/// Query set & indices for manually writing the timestamps of a query started with [`GpuProfiler::begin_raw_query`].
+///
+/// Write the start timestamp with `encoder.write_timestamp(query_set, start_index)` before the commands to be measured,
+/// and the end timestamp with `encoder.write_timestamp(query_set, end_index)` after them.
+///
+/// [`GpuProfiler::begin_raw_query`]: crate::GpuProfiler::begin_raw_query
+#[derive(Debug, Clone, Copy)]
+pub struct RawTimestampWrites<'a> {
+    /// Query set to write both timestamps to.
+    pub query_set: &'a wgpu::QuerySet,
+
+    /// Index of the start timestamp in [`Self::query_set`].
+    pub start_index: u32,
+
+    /// Index of the end timestamp in [`Self::query_set`].
+    pub end_index: u32,
+}
+
 /// An inflight query for the profiler.
 ///
 /// If timer queries are enabled, this represents a reserved timer query pair on
@@ -37,17 +573,36 @@ pub struct GpuTimerQueryResult {
 /// [`GpuProfiler::begin_pass_query`]: crate::GpuProfiler::begin_pass_query
 /// [`GpuProfiler::begin_query`]: crate::GpuProfiler::begin_query
 /// [`GpuProfiler::end_query`]: crate::GpuProfiler::end_query
+#[cfg(not(feature = "disabled"))]
 pub struct GpuProfilerQuery {
     /// The label assigned to this query.
-    /// Will be moved into [`GpuProfilerQuery::label`] once the query is fully processed.
+    /// Will be moved into [`GpuTimerQueryResult::label`] once the query is fully processed.
+    ///
+    /// Empty for numeric labels that haven't been resolved yet.
     pub label: String,
 
+    /// Numeric label of this query if it was opened with [`ScopeLabel::Id`].
+    pub label_id: Option<u32>,
+
+    /// Registered pass of this query if it was opened with [`ScopeLabel::Pass`].
+    pub pass_id: Option<PassId>,
+
     /// The process id of the process that opened this query.
     pub pid: u32,
 
     /// The thread id of the thread that opened this query.
     pub tid: ThreadId,
 
+    /// Number of work units (e.g. indirect dispatches or draws) known on the CPU to be recorded within this query.
+    ///
+    /// Purely informational, moved into [`GpuTimerQueryResult::unit_count`] once the query is fully processed.
+    pub unit_count: Option<u32>,
+
+    /// Category for grouping scopes independently of their labels, e.g. "geometry" or "lighting".
+    ///
+    /// Moved into [`GpuTimerQueryResult::category`] once the query is fully processed.
+    pub category: Option<&'static str>,
+
     /// The actual query on a query pool if any (none if disabled for this type of query).
     pub(crate) timer_query_pair: Option<ReservedTimerQueryPair>,
 
@@ -60,10 +615,34 @@ pub struct GpuProfilerQuery {
     /// Whether a debug group was opened for this scope.
     pub(crate) has_debug_group: bool,
 
+    /// Whether this query is a region of an accumulating scope, sharing its handle with other regions.
+    pub(crate) is_accumulating: bool,
+
+    /// Whether this query was cancelled via [`GpuProfiler::cancel_pass_query`], excluding it and its nested queries from the results.
+    ///
+    /// [`GpuProfiler::cancel_pass_query`]: crate::GpuProfiler::cancel_pass_query
+    pub(crate) is_cancelled: bool,
+
+    /// Whether this query was pushed to the opening thread's stack of open queries.
+    pub(crate) is_on_thread_stack: bool,
+
+    /// Hash of the label path the query was opened with, used for [`GpuProfilerSettings::debug_group_threshold`].
+    ///
+    /// Only computed if the threshold is set, zero otherwise.
+    ///
+    /// [`GpuProfilerSettings::debug_group_threshold`]: crate::GpuProfilerSettings::debug_group_threshold
+    pub(crate) label_path_hash: u64,
+
+    /// Encoder tag of this query, inherited by scopes nested via the scope types, see [`GpuProfiler::tagged_scope`].
+    ///
+    /// [`GpuProfiler::tagged_scope`]: crate::GpuProfiler::tagged_scope
+    pub(crate) encoder_tag: Option<u32>,
+
     #[cfg(feature = "tracy")]
     pub(crate) tracy_scope: Option<tracy_client::GpuSpan>,
 }
 
+#[cfg(not(feature = "disabled"))]
 impl GpuProfilerQuery {
     /// Use the reserved query for render pass timestamp writes if any.
     ///
@@ -71,7 +650,7 @@ impl GpuProfilerQuery {
     /// Only ever returns `Some` for queries that were created using [`GpuProfiler::begin_pass_query`].
     ///
     /// [`GpuProfiler::begin_pass_query`]: crate::GpuProfiler::begin_pass_query
-    pub fn render_pass_timestamp_writes(&self) -> Option<wgpu::RenderPassTimestampWrites> {
+    pub fn render_pass_timestamp_writes(&self) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
         self.timer_query_pair.as_ref().and_then(|query| {
             (query.usage_state == QueryPairUsageState::ReservedForPassTimestampWrites).then(|| {
                 wgpu::RenderPassTimestampWrites {
@@ -89,7 +668,7 @@ impl GpuProfilerQuery {
     /// Only ever returns `Some` for queries that were created using [`GpuProfiler::begin_pass_query`].
     ///
     /// [`GpuProfiler::begin_pass_query`]: crate::GpuProfiler::begin_pass_query
-    pub fn compute_pass_timestamp_writes(&self) -> Option<wgpu::ComputePassTimestampWrites> {
+    pub fn compute_pass_timestamp_writes(&self) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
         self.timer_query_pair.as_ref().and_then(|query| {
             (query.usage_state == QueryPairUsageState::ReservedForPassTimestampWrites).then(|| {
                 wgpu::ComputePassTimestampWrites {
@@ -101,7 +680,47 @@ impl GpuProfilerQuery {
         })
     }
 
+    /// Query set & indices for writing the timestamps of this query manually, if any.
+    ///
+    /// Only ever returns `Some` for queries that were created using [`GpuProfiler::begin_raw_query`].
+    ///
+    /// [`GpuProfiler::begin_raw_query`]: crate::GpuProfiler::begin_raw_query
+    pub fn raw_timestamp_writes(&self) -> Option<RawTimestampWrites<'_>> {
+        self.timer_query_pair.as_ref().and_then(|query| {
+            (query.usage_state == QueryPairUsageState::ReservedForRawTimestampWrites).then(|| {
+                RawTimestampWrites {
+                    query_set: &query.pool.query_set,
+                    start_index: query.start_query_idx,
+                    end_index: query.start_query_idx + 1,
+                }
+            })
+        })
+    }
+
+    /// Indices of the start & end timestamp of this query within the current profiler frame, if any.
+    ///
+    /// Gives the location of this query's raw timestamps in the destination buffer of
+    /// [`GpuProfiler::resolve_queries_into_buffer`].
+    ///
+    /// [`GpuProfiler::resolve_queries_into_buffer`]: crate::GpuProfiler::resolve_queries_into_buffer
+    pub fn frame_timestamp_indices(&self) -> Option<Range<u32>> {
+        self.timer_query_pair.as_ref().map(|query| {
+            let start = query.pool.frame_query_offset + query.start_query_idx;
+            start..(start + 2)
+        })
+    }
+
     /// Makes this scope a child of the passed scope.
+    ///
+    /// Overrides the parent inferred with [`GpuProfilerSettings::infer_parent_from_thread`].
+    /// Passing `None` makes this a root scope.
+    ///
+    /// [`GpuProfilerSettings::infer_parent_from_thread`]: crate::GpuProfilerSettings::infer_parent_from_thread
+    ///
+    /// The parent may live on a different encoder or thread than this query:
+    /// the tree of queries is only reconstructed once the frame is processed,
+    /// so a parent can be shared by reference with other threads that open children concurrently.
+    /// It is still required that the parent is closed within the same profiler frame.
     #[inline]
     pub fn with_parent(self, parent: Option<&GpuProfilerQuery>) -> Self {
         Self {
@@ -109,4 +728,105 @@ impl GpuProfilerQuery {
             ..self
         }
     }
+
+    /// Attaches the number of work units (e.g. indirect dispatches or draws) recorded within this query.
+    ///
+    /// See [`GpuProfilerQuery::unit_count`].
+    #[inline]
+    pub fn with_unit_count(self, count: u32) -> Self {
+        Self {
+            unit_count: Some(count),
+            ..self
+        }
+    }
+
+    /// Attaches a category to this query, for grouping scopes independently of their labels.
+    ///
+    /// See [`GpuProfilerQuery::category`].
+    #[inline]
+    pub fn with_category(self, category: &'static str) -> Self {
+        Self {
+            category: Some(category),
+            ..self
+        }
+    }
+}
+
+/// An inflight query for the profiler, compiled out via the `disabled` feature.
+///
+/// Keeps the label it was opened with, but never has any timer queries.
+/// *Must* still be closed by calling [`GpuProfiler::end_query`].
+///
+/// [`GpuProfiler::end_query`]: crate::GpuProfiler::end_query
+#[cfg(feature = "disabled")]
+pub struct GpuProfilerQuery {
+    /// The label assigned to this query.
+    ///
+    /// Empty for numeric labels.
+    pub label: String,
+
+    /// Numeric label of this query if it was opened with [`ScopeLabel::Id`].
+    pub label_id: Option<u32>,
+
+    /// Registered pass of this query if it was opened with [`ScopeLabel::Pass`].
+    pub pass_id: Option<PassId>,
+
+    /// The process id of the process that opened this query.
+    pub pid: u32,
+
+    /// The thread id of the thread that opened this query.
+    pub tid: ThreadId,
+
+    /// Number of work units (e.g. indirect dispatches or draws) known on the CPU to be recorded within this query.
+    pub unit_count: Option<u32>,
+
+    /// Category for grouping scopes independently of their labels, e.g. "geometry" or "lighting".
+    pub category: Option<&'static str>,
+}
+
+#[cfg(feature = "disabled")]
+impl GpuProfilerQuery {
+    /// Always `None`, since no timer queries are reserved.
+    pub fn render_pass_timestamp_writes(&self) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        None
+    }
+
+    /// Always `None`, since no timer queries are reserved.
+    pub fn compute_pass_timestamp_writes(&self) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
+        None
+    }
+
+    /// Always `None`, since no timer queries are reserved.
+    pub fn raw_timestamp_writes(&self) -> Option<RawTimestampWrites<'_>> {
+        None
+    }
+
+    /// Always `None`, since no timer queries are reserved.
+    pub fn frame_timestamp_indices(&self) -> Option<Range<u32>> {
+        None
+    }
+
+    /// Does nothing, since there is no tree of queries to build.
+    #[inline]
+    pub fn with_parent(self, _parent: Option<&GpuProfilerQuery>) -> Self {
+        self
+    }
+
+    /// See [`GpuProfilerQuery::unit_count`].
+    #[inline]
+    pub fn with_unit_count(self, count: u32) -> Self {
+        Self {
+            unit_count: Some(count),
+            ..self
+        }
+    }
+
+    /// See [`GpuProfilerQuery::category`].
+    #[inline]
+    pub fn with_category(self, category: &'static str) -> Self {
+        Self {
+            category: Some(category),
+            ..self
+        }
+    }
 }
@@ -1,5 +1,24 @@
+use std::sync::Arc;
+
 use crate::SettingsError;
 
+/// Function transforming scope labels, see [`GpuProfilerSettings::label_transform`].
+#[derive(Clone)]
+pub struct LabelTransform(pub Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl LabelTransform {
+    /// Creates a label transform from a function.
+    pub fn new(transform: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(transform))
+    }
+}
+
+impl std::fmt::Debug for LabelTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LabelTransform")
+    }
+}
+
 /// Settings passed on initialization of [`GpuProfiler`](crate::GpuProfiler).
 #[derive(Debug, Clone)]
 pub struct GpuProfilerSettings {
@@ -11,6 +30,16 @@ pub struct GpuProfilerSettings {
     /// without the need of special build configurations or code to handle enabled/disabled profiling.
     pub enable_timer_queries: bool,
 
+    /// Bitmask of channels whose scopes are timed, all channels are enabled by default.
+    ///
+    /// Scopes opened via e.g. [`GpuProfiler::scope_in_channel`](crate::GpuProfiler::scope_in_channel) are only timed
+    /// if any of their channel bits is set in this mask, otherwise they behave as if
+    /// [`GpuProfilerSettings::enable_timer_queries`] was false.
+    /// All other scopes use [`GpuProfiler::DEFAULT_CHANNEL`](crate::GpuProfiler::DEFAULT_CHANNEL).
+    /// This allows e.g. switching between coarse and detailed profiling at runtime via
+    /// [`GpuProfiler::change_settings`](crate::GpuProfiler::change_settings).
+    pub enabled_channels: u32,
+
     /// Enables/disables debug markers for all scopes on the respective encoder or pass.
     ///
     /// This is useful for debugging with tools like [RenderDoc](https://renderdoc.org/).
@@ -18,6 +47,21 @@ pub struct GpuProfilerSettings {
     /// [`GpuProfilerSettings::enable_timer_queries`].
     pub enable_debug_groups: bool,
 
+    /// Only push debug groups for scopes that took at least this many seconds, debug groups are pushed for all scopes if `None` (default).
+    ///
+    /// Since durations are only known after readback, scopes are matched by label path against the frame last processed by
+    /// [`GpuProfiler::process_finished_frame`](crate::GpuProfiler::process_finished_frame) or
+    /// [`GpuProfiler::process_finished_frame_flat`](crate::GpuProfiler::process_finished_frame_flat):
+    /// a debug group is pushed if the scope with the same labels of itself and all its parents exceeded the threshold in that frame.
+    /// Label paths consist of the labels scopes were opened with, i.e. before resolving numeric labels or applying [`Self::label_transform`].
+    /// Parents are known when opening a scope via the scope types, e.g. [`Scope::scope`](crate::Scope::scope),
+    /// or via [`Self::infer_parent_from_thread`]. Queries nested afterwards with
+    /// [`GpuProfilerQuery::with_parent`](crate::GpuProfilerQuery::with_parent) are matched as if they were top level scopes.
+    /// This focuses capture annotations in tools like [RenderDoc](https://renderdoc.org/) on hotspots.
+    /// Timings are unaffected, and no debug groups are pushed before the first frame was processed.
+    /// Has no effect if [`Self::enable_debug_groups`] is false.
+    pub debug_group_threshold: Option<f64>,
+
     /// The profiler queues up to `max_num_pending_frames` "profiler-frames" at a time.
     ///
     /// A profiler-frame is regarded as in-flight until its queries have been successfully
@@ -34,14 +78,205 @@ pub struct GpuProfilerSettings {
     /// and GPU-CPU syncing strategy.
     /// Must be greater than 0.
     pub max_num_pending_frames: usize,
+
+    /// If true, [`GpuProfiler::process_finished_frame`](crate::GpuProfiler::process_finished_frame) sorts
+    /// each level of the result tree by start timestamp.
+    ///
+    /// Without this, sibling order follows the order in which queries were closed,
+    /// which is non-deterministic if queries are recorded from several threads.
+    /// Queries without timing information are ordered before all timed queries.
+    pub sort_results_by_time: bool,
+
+    /// Enables/disables reading back timer query results on the CPU.
+    ///
+    /// If false, [`GpuProfiler::end_frame`](crate::GpuProfiler::end_frame) doesn't map any buffers
+    /// and [`GpuProfiler::process_finished_frame`](crate::GpuProfiler::process_finished_frame) never returns results.
+    /// Query pools are then created without a read buffer, saving a buffer and a mapping per frame.
+    /// This is useful for GPU-driven workflows that consume timestamps only on the GPU via
+    /// [`GpuProfiler::resolve_queries_into_buffer`](crate::GpuProfiler::resolve_queries_into_buffer).
+    pub cpu_readback: bool,
+
+    /// If true, new queries are automatically nested in the innermost query that is still open on the same thread.
+    ///
+    /// This applies to [`GpuProfiler::begin_query`](crate::GpuProfiler::begin_query) as well as
+    /// [`GpuProfiler::begin_pass_query`](crate::GpuProfiler::begin_pass_query), removing the need to call
+    /// [`GpuProfilerQuery::with_parent`](crate::GpuProfilerQuery::with_parent) for queries in manually created passes.
+    /// `with_parent` still overrides the inferred parent.
+    ///
+    /// Queries have to be closed on the thread they were opened on for this to work reliably.
+    /// Note that this also nests queries on different encoders that happen to be interleaved on the same thread.
+    pub infer_parent_from_thread: bool,
+
+    /// Enables/disables capturing process & thread id for every query.
+    ///
+    /// If false, all queries report a process id of 0 and the id of the thread the profiler was created on,
+    /// saving some overhead per query. Useful if there are many queries and per-thread tracks aren't needed.
+    pub capture_thread_info: bool,
+
+    /// If true, [`GpuProfiler::end_frame`](crate::GpuProfiler::end_frame) validates that the last query resolve
+    /// of the frame was submitted in the last submission of the frame.
+    ///
+    /// Requires all command buffers containing queries to be submitted via [`GpuProfiler::submit`](crate::GpuProfiler::submit),
+    /// which keeps track of the submission order.
+    /// Catches resolves that are submitted before command buffers with queries, which yields invalid timing results.
+    pub validate_submission_order: bool,
+
+    /// Minimum number of queries in newly created query pools.
+    ///
+    /// Query pools grow as more queries are needed in a frame, see [`GpuProfiler`](crate::GpuProfiler) for details.
+    /// Setting this to the number of queries typically needed per frame avoids the initial growing phase,
+    /// while small values avoid over-allocating for applications with very few scopes.
+    /// Each scope needs two queries, therefore this must be even, greater than 0 and
+    /// at most [`wgpu::QUERY_SET_MAX_QUERIES`].
+    pub min_pool_capacity: u32,
+
+    /// Fraction by which the size of new query pools moves towards the average number of queries of recent frames
+    /// on every [`GpuProfiler::end_frame`](crate::GpuProfiler::end_frame).
+    ///
+    /// Query pools always grow right away to fit all queries of a frame.
+    /// With the default of `0.0`, they never shrink again unless [`GpuProfiler::shrink_to_fit`](crate::GpuProfiler::shrink_to_fit) is called.
+    /// Values greater than `0.0` let the size decay towards the average of the last 64 frames instead,
+    /// such that bursts of queries don't bloat memory for the rest of the session.
+    /// Cached pools that are more than twice as big as needed are then released as well.
+    /// Small values shrink slowly, avoiding pool re-creation if bursts are frequent.
+    /// Must be between `0.0` and `1.0`.
+    pub pool_size_decay: f32,
+
+    /// Upper bound on the number of query sets the profiler keeps alive at once, unbounded if `None`.
+    ///
+    /// Each query pool owns one query set, so this bounds the GPU resources spent on profiling.
+    /// Once the budget is exhausted, new scopes are recorded without timer queries until pools are freed again.
+    /// This is reported via [`GpuProfilerStatistics::num_failed_query_pool_creations`](crate::GpuProfilerStatistics::num_failed_query_pool_creations)
+    /// and logged once as a warning.
+    pub max_num_query_sets: Option<u32>,
+
+    /// Upper bound on the number of timer queries per frame, unbounded if `None`.
+    ///
+    /// Each scope needs two queries. Scopes past the limit are still recorded, but without timer queries.
+    /// This is reported via [`GpuProfilerStatistics::num_dropped_queries_last_frame`](crate::GpuProfilerStatistics::num_dropped_queries_last_frame).
+    pub max_queries_per_frame: Option<u32>,
+
+    /// Upper bound on the number of scopes per frame, unbounded if `None`.
+    ///
+    /// Frames with more scopes are discarded by [`GpuProfiler::end_frame`](crate::GpuProfiler::end_frame)
+    /// with an error instead of building up a result tree of that size.
+    /// Scopes past the limit are already dropped when they are closed, so memory stays bounded while the frame is recorded.
+    /// This guards against pathological memory use, e.g. if a bug opens scopes in a runaway loop.
+    pub max_num_scopes_per_frame: Option<u32>,
+
+    /// Maximum nesting depth of reported scopes, top level scopes have a depth of 1. Defaults to 256.
+    ///
+    /// Scopes nested deeper are dropped when processing finished frames.
+    /// Processing & writing out results recurses once per nesting level,
+    /// so this guards against stack overflows caused by pathologically deep scope trees.
+    pub max_scope_depth: u32,
+
+    /// Only profile every n-th frame, all frames are profiled if `None` (default).
+    ///
+    /// Frames are counted by [`GpuProfiler::end_frame`](crate::GpuProfiler::end_frame), starting with a sampled frame.
+    /// On all other frames, scopes behave as if [`Self::enable_timer_queries`] was false
+    /// and no query pools are used, making them cheap statistical sampling without gating every call site.
+    /// Non-sampled frames aren't queued for processing at all, i.e. only sampled frames show up in
+    /// [`GpuProfiler::process_finished_frame`](crate::GpuProfiler::process_finished_frame)
+    /// and don't affect the sizing of query pools.
+    /// Must be greater than `0`.
+    pub sample_every_n_frames: Option<u32>,
+
+    /// Number of recently processed frames whose summary is retained, see [`GpuProfiler::recent_history`](crate::GpuProfiler::recent_history).
+    ///
+    /// Only a [`GpuFrameSummary`](crate::GpuFrameSummary) is kept per frame, so memory stays bounded regardless of the number of scopes.
+    /// Disabled with `0` (default).
+    pub history_len: usize,
+
+    /// Enables/disables measuring the CPU time spent in the profiler's own per-frame methods.
+    ///
+    /// Resolving queries, ending frames and processing finished frames are timed and summed up
+    /// in [`GpuProfilerStatistics`](crate::GpuProfilerStatistics), e.g. to verify that the profiler's overhead stays acceptable as scope counts grow.
+    /// Has no effect on wasm32, where there is no way to query the time.
+    pub measure_cpu_overhead: bool,
+
+    /// Number of [`GpuProfiler::end_frame`](crate::GpuProfiler::end_frame) calls after which a pending frame
+    /// whose buffers still aren't mapped is given up, disabled if `None` (default).
+    ///
+    /// If mapping callbacks never fire, e.g. because of a driver hang or a lost device,
+    /// [`GpuProfiler::process_finished_frame`](crate::GpuProfiler::process_finished_frame) would otherwise return `None` forever.
+    /// Timed out frames are recycled and counted in
+    /// [`GpuProfilerStatistics::num_timed_out_frames`](crate::GpuProfilerStatistics::num_timed_out_frames), surfacing the hang.
+    pub map_timeout_frames: Option<u32>,
+
+    /// Transforms every scope label when processing finished frames, e.g. to strip ids from labels.
+    ///
+    /// Applied by [`GpuProfiler::process_finished_frame`](crate::GpuProfiler::process_finished_frame),
+    /// after resolving numeric labels, so it doesn't add any overhead to opening scopes.
+    /// Debug groups, Tracy and the matching of slow scopes for [`Self::debug_group_threshold`] use the original labels.
+    pub label_transform: Option<LabelTransform>,
+
+    /// Minimum duration in seconds for a scope to show up in the results, all scopes are reported if `None`.
+    ///
+    /// Applied when processing finished frames, since durations aren't known before.
+    /// Scopes that are shorter are dropped along with all their nested scopes,
+    /// which are usually no longer than the enclosing scope anyways.
+    /// Scopes without timing information are always reported.
+    /// Useful for focusing on hotspots, e.g. when hunting for anomalies in otherwise fast frames.
+    pub min_report_duration: Option<f64>,
+
+    /// Treat scopes whose end timestamp lies before their start timestamp as spanning the range between both timestamps.
+    ///
+    /// If the start & end of a scope end up in different command buffers, e.g. when a logically single pass
+    /// is recorded across encoder boundaries, the command buffers may execute in a different order than they were recorded in.
+    /// The end timestamp is then written before the start timestamp.
+    /// By default (`false`) such scopes are reported without timing information and counted in
+    /// [`GpuProfilerStatistics::num_invalid_timestamps`](crate::GpuProfilerStatistics::num_invalid_timestamps),
+    /// negative durations are never reported.
+    /// If enabled, they are reported with the union of both timestamps instead and counted in
+    /// [`GpuProfilerStatistics::num_merged_reversed_timestamps`](crate::GpuProfilerStatistics::num_merged_reversed_timestamps).
+    ///
+    /// Note that a wraparound or reset of the GPU's timestamp counter can't be told apart from reversed timestamps,
+    /// so with this enabled, such scopes show up with bogus long durations.
+    pub merge_reversed_timestamps: bool,
+
+    /// Additional usages for the buffers that timer queries are resolved into.
+    ///
+    /// Useful if external tools need e.g. [`wgpu::BufferUsages::COPY_SRC`] to inspect buffer contents.
+    /// `QUERY_RESOLVE | COPY_SRC` are always set. Must not contain any map usages.
+    pub additional_resolve_buffer_usages: wgpu::BufferUsages,
+
+    /// Additional usages for the buffers that resolved timer queries are read back from on the CPU.
+    ///
+    /// `COPY_DST | MAP_READ` are always set. Must not contain [`wgpu::BufferUsages::MAP_WRITE`].
+    /// Note that without [`wgpu::Features::MAPPABLE_PRIMARY_BUFFERS`], wgpu doesn't allow
+    /// combining `MAP_READ` with usages other than `COPY_DST`.
+    pub additional_read_buffer_usages: wgpu::BufferUsages,
 }
 
 impl Default for GpuProfilerSettings {
     fn default() -> Self {
         Self {
             enable_timer_queries: true,
+            enabled_channels: u32::MAX,
             enable_debug_groups: true,
+            debug_group_threshold: None,
             max_num_pending_frames: 3,
+            sort_results_by_time: false,
+            cpu_readback: true,
+            infer_parent_from_thread: false,
+            capture_thread_info: true,
+            validate_submission_order: false,
+            min_pool_capacity: 32,
+            pool_size_decay: 0.0,
+            max_num_query_sets: None,
+            max_queries_per_frame: None,
+            max_num_scopes_per_frame: None,
+            max_scope_depth: 256,
+            sample_every_n_frames: None,
+            history_len: 0,
+            measure_cpu_overhead: false,
+            map_timeout_frames: None,
+            label_transform: None,
+            min_report_duration: None,
+            merge_reversed_timestamps: false,
+            additional_resolve_buffer_usages: wgpu::BufferUsages::empty(),
+            additional_read_buffer_usages: wgpu::BufferUsages::empty(),
         }
     }
 }
@@ -50,6 +285,23 @@ impl GpuProfilerSettings {
     pub fn validate(&self) -> Result<(), SettingsError> {
         if self.max_num_pending_frames == 0 {
             Err(SettingsError::InvalidMaxNumPendingFrames)
+        } else if self.min_pool_capacity == 0
+            || !self.min_pool_capacity.is_multiple_of(2)
+            || self.min_pool_capacity > wgpu::QUERY_SET_MAX_QUERIES
+        {
+            Err(SettingsError::InvalidMinPoolCapacity)
+        } else if !(0.0..=1.0).contains(&self.pool_size_decay) {
+            Err(SettingsError::InvalidPoolSizeDecay)
+        } else if self
+            .additional_resolve_buffer_usages
+            .intersects(wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::MAP_WRITE)
+            || self
+                .additional_read_buffer_usages
+                .contains(wgpu::BufferUsages::MAP_WRITE)
+        {
+            Err(SettingsError::InvalidBufferUsages)
+        } else if self.sample_every_n_frames == Some(0) {
+            Err(SettingsError::InvalidSampleEveryNFrames)
         } else {
             Ok(())
         }
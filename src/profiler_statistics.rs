@@ -0,0 +1,86 @@
+/// Statistics about the internal state of a [`GpuProfiler`](crate::GpuProfiler).
+///
+/// Retrieved via [`GpuProfiler::statistics`](crate::GpuProfiler::statistics).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GpuProfilerStatistics {
+    /// Number of times a new query pool couldn't be created over the lifetime of the profiler.
+    ///
+    /// A pool can't be created if it would exceed [`GpuProfilerSettings::max_num_query_sets`](crate::GpuProfilerSettings::max_num_query_sets).
+    /// The remaining queries of that frame are then recorded without timer queries.
+    pub num_failed_query_pool_creations: u32,
+
+    /// Number of queries over the lifetime of the profiler whose end timestamp was before their start timestamp.
+    ///
+    /// This happens if the GPU's timestamp counter wraps around or is reset between start and end of a scope,
+    /// or if the command buffers containing start and end were executed in a different order than recorded,
+    /// see [`GpuProfilerSettings::merge_reversed_timestamps`](crate::GpuProfilerSettings::merge_reversed_timestamps).
+    /// Also counts queries that weren't properly closed, i.e. whose timestamps may not have been written at all.
+    /// The affected results have no timing information, i.e. [`GpuTimerQueryResult::time`](crate::GpuTimerQueryResult::time) is `None`.
+    pub num_invalid_timestamps: u32,
+
+    /// Number of queries over the lifetime of the profiler whose start or end timestamp was never written by the GPU.
+    ///
+    /// Unwritten queries read back as zero, e.g. if a backend skipped a pass that still had timestamp writes.
+    /// The affected results have no timing information, like for [`Self::num_invalid_timestamps`].
+    pub num_unwritten_timestamps: u32,
+
+    /// Number of queries over the lifetime of the profiler whose end timestamp was before their start timestamp,
+    /// but were reported with the range between both timestamps.
+    ///
+    /// Only counted if [`GpuProfilerSettings::merge_reversed_timestamps`](crate::GpuProfilerSettings::merge_reversed_timestamps) is set,
+    /// otherwise such queries are counted in [`Self::num_invalid_timestamps`].
+    pub num_merged_reversed_timestamps: u32,
+
+    /// Number of query pools over the lifetime of the profiler that couldn't be reused since they were unexpectedly still referenced.
+    ///
+    /// This indicates an internal accounting error, in which case the pool is given up instead of crashing.
+    /// Debug builds panic instead.
+    pub num_discarded_referenced_pools: u32,
+
+    /// Number of frames over the lifetime of the profiler that were recycled since their buffers didn't get mapped in time.
+    ///
+    /// Only counted if [`GpuProfilerSettings::map_timeout_frames`](crate::GpuProfilerSettings::map_timeout_frames) is set.
+    /// A non-zero value usually indicates a GPU hang or a lost device.
+    pub num_timed_out_frames: u32,
+
+    /// Number of scopes in the last ended frame that didn't get timer queries since no query pool was available,
+    /// or since they exceeded [`GpuProfilerSettings::max_queries_per_frame`](crate::GpuProfilerSettings::max_queries_per_frame).
+    ///
+    /// These scopes are still reported, but without timing information.
+    /// See also [`Self::num_failed_query_pool_creations`].
+    pub num_dropped_queries_last_frame: u32,
+
+    /// Total CPU time spent in [`GpuProfiler::resolve_queries`](crate::GpuProfiler::resolve_queries)
+    /// and [`GpuProfiler::resolve_queries_into_buffer`](crate::GpuProfiler::resolve_queries_into_buffer).
+    ///
+    /// Only measured if [`GpuProfilerSettings::measure_cpu_overhead`](crate::GpuProfilerSettings::measure_cpu_overhead) is enabled.
+    pub cpu_time_resolve_queries: std::time::Duration,
+
+    /// Total CPU time spent in [`GpuProfiler::end_frame`](crate::GpuProfiler::end_frame) and its variants.
+    ///
+    /// Only measured if [`GpuProfilerSettings::measure_cpu_overhead`](crate::GpuProfilerSettings::measure_cpu_overhead) is enabled.
+    pub cpu_time_end_frame: std::time::Duration,
+
+    /// Total CPU time spent processing finished frames,
+    /// i.e. in [`GpuProfiler::process_finished_frame`](crate::GpuProfiler::process_finished_frame) and its variants.
+    ///
+    /// Only measured if [`GpuProfilerSettings::measure_cpu_overhead`](crate::GpuProfilerSettings::measure_cpu_overhead) is enabled.
+    pub cpu_time_process_finished_frame: std::time::Duration,
+}
+
+/// Snapshot of the state of a frame that hasn't been processed yet.
+///
+/// Retrieved via [`GpuProfiler::pending_frame_status`](crate::GpuProfiler::pending_frame_status).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PendingFrameStatus {
+    /// Number of query pools used by the frame.
+    pub pools: usize,
+
+    /// Number of query pools whose read buffer has been mapped already.
+    ///
+    /// The frame can be processed once all pools are mapped.
+    pub mapped: usize,
+
+    /// Number of scopes in the frame.
+    pub num_scopes: usize,
+}
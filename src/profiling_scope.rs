@@ -0,0 +1,26 @@
+/// Opens a GPU scope along with a CPU scope of the same name via the [`profiling`](https://docs.rs/profiling) crate.
+///
+/// Both scopes are bound to the enclosing block:
+/// the CPU scope measures the time spent recording commands for the GPU scope,
+/// and shows up next to the GPU timings in whichever backend `profiling` is configured for.
+/// The label has to be a string literal since some `profiling` backends require it.
+///
+/// ```ignore
+/// wgpu_profiler::profiling_scope!(scope = profiler, "shadows", &mut encoder, &device);
+/// let mut pass = scope.scoped_compute_pass("shadow cascades", &device);
+/// ```
+///
+/// Expands to:
+///
+/// ```ignore
+/// profiling::scope!("shadows");
+/// let mut scope = profiler.scope("shadows", &mut encoder, &device);
+/// ```
+#[macro_export]
+macro_rules! profiling_scope {
+    ($scope:ident = $profiler:expr, $label:literal, $encoder_or_pass:expr, $device:expr) => {
+        $crate::profiling::scope!($label);
+        #[allow(unused_mut)]
+        let mut $scope = $profiler.scope($label, $encoder_or_pass, $device);
+    };
+}
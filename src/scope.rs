@@ -1,18 +1,20 @@
 //! Scope types that wrap a `wgpu` encoder/pass and start a scope on creation. In most cases, they
 //! then allow automatically ending the scope on drop.
 
-use crate::{GpuProfiler, GpuProfilerQuery, ProfilerCommandRecorder};
+use crate::{
+    profiler::QueryOptions, GpuProfiler, GpuProfilerQuery, ProfilerCommandRecorder, ScopeLabel,
+};
 
 /// Scope that takes a (mutable) reference to the encoder/pass.
 ///
 /// Calls [`GpuProfiler::end_query()`] on drop.
-pub struct Scope<'a, Recorder: ProfilerCommandRecorder> {
-    pub profiler: &'a GpuProfiler,
+pub struct Scope<'a, Recorder: ProfilerCommandRecorder, UserData = ()> {
+    pub profiler: &'a GpuProfiler<UserData>,
     pub recorder: &'a mut Recorder,
     pub scope: Option<GpuProfilerQuery>,
 }
 
-impl<'a, R: ProfilerCommandRecorder> Drop for Scope<'a, R> {
+impl<'a, R: ProfilerCommandRecorder, U> Drop for Scope<'a, R, U> {
     #[inline]
     fn drop(&mut self) {
         if let Some(scope) = self.scope.take() {
@@ -24,13 +26,13 @@ impl<'a, R: ProfilerCommandRecorder> Drop for Scope<'a, R> {
 /// Scope that takes ownership of the encoder/pass.
 ///
 /// Calls [`GpuProfiler::end_query()`] on drop.
-pub struct OwningScope<'a, Recorder: ProfilerCommandRecorder> {
-    pub profiler: &'a GpuProfiler,
+pub struct OwningScope<'a, Recorder: ProfilerCommandRecorder, UserData = ()> {
+    pub profiler: &'a GpuProfiler<UserData>,
     pub recorder: Recorder,
     pub scope: Option<GpuProfilerQuery>,
 }
 
-impl<'a, R: ProfilerCommandRecorder> Drop for OwningScope<'a, R> {
+impl<'a, R: ProfilerCommandRecorder, U> Drop for OwningScope<'a, R, U> {
     #[inline]
     fn drop(&mut self) {
         if let Some(scope) = self.scope.take() {
@@ -45,13 +47,13 @@ impl<'a, R: ProfilerCommandRecorder> Drop for OwningScope<'a, R> {
 /// This construct is just for completeness in cases where working with scopes is preferred but one can't rely on the Drop call in the right place.
 /// This is useful when the owned value needs to be recovered after the end of the scope.
 /// In particular, to submit a [`wgpu::CommandEncoder`] to a queue, ownership of the encoder is necessary.
-pub struct ManualOwningScope<'a, Recorder: ProfilerCommandRecorder> {
-    pub profiler: &'a GpuProfiler,
+pub struct ManualOwningScope<'a, Recorder: ProfilerCommandRecorder, UserData = ()> {
+    pub profiler: &'a GpuProfiler<UserData>,
     pub recorder: Recorder,
     pub scope: Option<GpuProfilerQuery>,
 }
 
-impl<'a, R: ProfilerCommandRecorder> ManualOwningScope<'a, R> {
+impl<'a, R: ProfilerCommandRecorder, U> ManualOwningScope<'a, R, U> {
     /// Ends the scope allowing the extraction of the owned [`ProfilerCommandRecorder`].
     #[track_caller]
     #[inline]
@@ -63,6 +65,71 @@ impl<'a, R: ProfilerCommandRecorder> ManualOwningScope<'a, R> {
     }
 }
 
+/// Opens many sibling scopes nested in the same parent scope, e.g. one per item in a loop.
+///
+/// Created by e.g. [`Scope::batch`], borrowing the parent scope for as long as the batch lives.
+pub struct ScopeBatch<'a, Recorder: ProfilerCommandRecorder, UserData = ()> {
+    profiler: &'a GpuProfiler<UserData>,
+    recorder: &'a mut Recorder,
+    parent: Option<&'a GpuProfilerQuery>,
+    device: &'a wgpu::Device,
+}
+
+impl<'a, R: ProfilerCommandRecorder, U> ScopeBatch<'a, R, U> {
+    /// Runs `record` within a new scope nested in the batch's parent scope.
+    ///
+    /// The scope is closed once `record` returns.
+    #[track_caller]
+    pub fn measure<T>(
+        &mut self,
+        label: impl Into<ScopeLabel>,
+        record: impl FnOnce(&mut Scope<'_, R, U>) -> T,
+    ) -> T {
+        let query = self.profiler.begin_encoder_or_pass_query(
+            label,
+            QueryOptions::nested_in(self.parent),
+            self.recorder,
+            self.device,
+        );
+        let mut scope = Scope {
+            profiler: self.profiler,
+            recorder: &mut *self.recorder,
+            scope: Some(query),
+        };
+        record(&mut scope)
+    }
+}
+
+/// Scope that only pushes a debug group on the encoder/pass, never reserving any timer queries.
+///
+/// Created by [`GpuProfiler::debug_scope`], pops the debug group on drop.
+pub struct DebugScope<'a, Recorder: ProfilerCommandRecorder> {
+    pub recorder: &'a mut Recorder,
+}
+
+impl<'a, R: ProfilerCommandRecorder> Drop for DebugScope<'a, R> {
+    #[inline]
+    fn drop(&mut self) {
+        self.recorder.pop_debug_group();
+    }
+}
+
+impl<'a, R: ProfilerCommandRecorder> std::ops::Deref for DebugScope<'a, R> {
+    type Target = R;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.recorder
+    }
+}
+
+impl<'a, R: ProfilerCommandRecorder> std::ops::DerefMut for DebugScope<'a, R> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.recorder
+    }
+}
+
 /// Most implementation code of the different scope types is exactly the same.
 ///
 /// This macro allows to avoid code duplication.
@@ -70,34 +137,86 @@ impl<'a, R: ProfilerCommandRecorder> ManualOwningScope<'a, R> {
 /// import the extension trait to use all methods of the scope types which I found a bit annoying.
 macro_rules! impl_scope_ext {
     ($scope:ident, $recorder_type:ty) => {
-        impl<'a, R: ProfilerCommandRecorder> $scope<'a, R> {
+        impl<'a, R: ProfilerCommandRecorder, U> $scope<'a, R, U> {
             /// Starts a new profiler scope nested within this one.
             #[must_use]
             #[track_caller]
             #[inline]
             pub fn scope(
                 &mut self,
-                label: impl Into<String>,
+                label: impl Into<ScopeLabel>,
                 device: &wgpu::Device,
-            ) -> Scope<'_, R> {
+            ) -> Scope<'_, R, U> {
+                self.scope_in_channel(label, GpuProfiler::DEFAULT_CHANNEL, device)
+            }
+
+            /// Starts a new profiler scope nested within this one that is only timed if any of the bits of `channel`
+            /// is set in [`GpuProfilerSettings::enabled_channels`](crate::GpuProfilerSettings::enabled_channels).
+            #[must_use]
+            #[track_caller]
+            #[inline]
+            pub fn scope_in_channel(
+                &mut self,
+                label: impl Into<ScopeLabel>,
+                channel: u32,
+                device: &wgpu::Device,
+            ) -> Scope<'_, R, U> {
+                let options = QueryOptions {
+                    channel,
+                    ..QueryOptions::nested_in(self.scope.as_ref())
+                };
                 let recorder: &mut R = &mut self.recorder;
                 let scope = self
                     .profiler
-                    .begin_query(label, recorder, device)
-                    .with_parent(self.scope.as_ref());
+                    .begin_encoder_or_pass_query(label, options, recorder, device);
                 Scope {
                     profiler: self.profiler,
                     recorder,
                     scope: Some(scope),
                 }
             }
+
+            /// Creates a [`ScopeBatch`] for opening many scopes nested within this one, e.g. in a loop.
+            ///
+            /// Unlike repeatedly calling [`Self::scope`], this scope is borrowed only once for all nested scopes.
+            #[must_use]
+            #[inline]
+            pub fn batch<'b>(&'b mut self, device: &'b wgpu::Device) -> ScopeBatch<'b, R, U> {
+                ScopeBatch {
+                    profiler: self.profiler,
+                    recorder: &mut self.recorder,
+                    parent: self.scope.as_ref(),
+                    device,
+                }
+            }
         }
 
-        impl<'a> $scope<'a, wgpu::CommandEncoder> {
+        impl<'a, U> $scope<'a, wgpu::CommandEncoder, U> {
+            /// Records commands directly on the encoder, e.g. buffer/texture copies or clears, within a new profiler scope nested within this one.
+            ///
+            /// Returns whatever `record` returns.
+            ///
+            /// Unlike passes, commands recorded directly on an encoder can only be timed with timestamps written on the encoder itself.
+            /// So in order to take measurements, this requires the [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS`] feature
+            /// in addition to [`wgpu::Features::TIMESTAMP_QUERY`]. Without it, the scope shows up in the results without timing information.
+            #[track_caller]
+            pub fn measure_copy<T>(
+                &mut self,
+                label: impl Into<ScopeLabel>,
+                device: &wgpu::Device,
+                record: impl FnOnce(&mut wgpu::CommandEncoder) -> T,
+            ) -> T {
+                let mut scope = self.scope(label, device);
+                record(&mut scope)
+            }
+
             /// Start a render pass wrapped in a [`OwningScope`].
             ///
-            /// Ignores passed `wgpu::RenderPassDescriptor::timestamp_writes` and replaces it with
+            /// **Discards** the passed `wgpu::RenderPassDescriptor::timestamp_writes` and replaces them with
             /// `timestamp_writes` managed by `GpuProfiler` if profiling is enabled.
+            /// Since a pass can only have a single set of timestamp writes, passing any is considered a usage error
+            /// and panics in debug builds. Use [`GpuProfiler::begin_pass_query`] with a manually created pass
+            /// if you need to control the pass' timestamp writes yourself.
             ///
             /// This also sets the `wgpu::RenderPassDescriptor::label` if it's `None` (default).
             ///
@@ -106,21 +225,28 @@ macro_rules! impl_scope_ext {
             #[track_caller]
             pub fn scoped_render_pass<'b>(
                 &'b mut self,
-                label: impl Into<String>,
+                label: impl Into<ScopeLabel>,
                 device: &wgpu::Device,
                 pass_descriptor: wgpu::RenderPassDescriptor<'_>,
-            ) -> OwningScope<'b, wgpu::RenderPass<'b>> {
-                let child_scope = self
-                    .profiler
-                    .begin_pass_query(label, &mut self.recorder, device)
-                    .with_parent(self.scope.as_ref());
-                let render_pass = self
-                    .recorder
-                    .begin_render_pass(&wgpu::RenderPassDescriptor {
+            ) -> OwningScope<'b, wgpu::RenderPass<'b>, U> {
+                debug_assert!(
+                    pass_descriptor.timestamp_writes.is_none(),
+                    "timestamp_writes passed to scoped_render_pass are replaced by the profiler's own timestamp writes"
+                );
+                let child_scope = self.profiler.begin_pass_query_with_options(
+                    label,
+                    QueryOptions::nested_in(self.scope.as_ref()),
+                    &mut self.recorder,
+                    device,
+                );
+                let recorder: &'b mut wgpu::CommandEncoder = &mut self.recorder;
+                let render_pass = self.profiler.with_label_str(&child_scope, |label| {
+                    recorder.begin_render_pass(&wgpu::RenderPassDescriptor {
                         timestamp_writes: child_scope.render_pass_timestamp_writes(),
-                        label: pass_descriptor.label.or(Some(&child_scope.label)),
+                        label: pass_descriptor.label.or(Some(label)),
                         ..pass_descriptor
-                    });
+                    })
+                });
 
                 OwningScope {
                     profiler: self.profiler,
@@ -139,20 +265,23 @@ macro_rules! impl_scope_ext {
             #[track_caller]
             pub fn scoped_compute_pass<'b>(
                 &'b mut self,
-                label: impl Into<String>,
+                label: impl Into<ScopeLabel>,
                 device: &wgpu::Device,
-            ) -> OwningScope<'b, wgpu::ComputePass<'b>> {
-                let child_scope = self
-                    .profiler
-                    .begin_pass_query(label, &mut self.recorder, device)
-                    .with_parent(self.scope.as_ref());
+            ) -> OwningScope<'b, wgpu::ComputePass<'b>, U> {
+                let child_scope = self.profiler.begin_pass_query_with_options(
+                    label,
+                    QueryOptions::nested_in(self.scope.as_ref()),
+                    &mut self.recorder,
+                    device,
+                );
 
-                let render_pass = self
-                    .recorder
-                    .begin_compute_pass(&wgpu::ComputePassDescriptor {
-                        label: Some(&child_scope.label),
+                let recorder: &'b mut wgpu::CommandEncoder = &mut self.recorder;
+                let render_pass = self.profiler.with_label_str(&child_scope, |label| {
+                    recorder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some(label),
                         timestamp_writes: child_scope.compute_pass_timestamp_writes(),
-                    });
+                    })
+                });
 
                 OwningScope {
                     profiler: self.profiler,
@@ -162,7 +291,23 @@ macro_rules! impl_scope_ext {
             }
         }
 
-        impl<'a, R: ProfilerCommandRecorder> std::ops::Deref for $scope<'a, R> {
+        impl<'a, 'p, U> $scope<'a, wgpu::RenderPass<'p>, U> {
+            /// Executes render bundles within a new profiler scope nested within this one.
+            ///
+            /// Note that in order to take measurements, this requires the [`wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES`] feature.
+            #[track_caller]
+            pub fn scope_bundles<'b, I: IntoIterator<Item = &'b wgpu::RenderBundle>>(
+                &mut self,
+                label: impl Into<ScopeLabel>,
+                render_bundles: I,
+                device: &wgpu::Device,
+            ) {
+                let mut bundle_scope = self.scope(label, device);
+                bundle_scope.execute_bundles(render_bundles);
+            }
+        }
+
+        impl<'a, R: ProfilerCommandRecorder, U> std::ops::Deref for $scope<'a, R, U> {
             type Target = R;
 
             #[inline]
@@ -171,7 +316,7 @@ macro_rules! impl_scope_ext {
             }
         }
 
-        impl<'a, R: ProfilerCommandRecorder> std::ops::DerefMut for $scope<'a, R> {
+        impl<'a, R: ProfilerCommandRecorder, U> std::ops::DerefMut for $scope<'a, R, U> {
             #[inline]
             fn deref_mut(&mut self) -> &mut Self::Target {
                 &mut self.recorder
@@ -0,0 +1,50 @@
+use std::{fs::File, io::Write, path::Path};
+
+use crate::GpuTimerQueryAggregator;
+
+/// Writes a .json file with a flat array of per-scope timing statistics.
+///
+/// Unlike [`crate::chrometrace::write_chrometrace`], this isn't a timeline but a compact summary
+/// meant for ingestion by tools like perf tracking dashboards:
+/// `[{ "label_path": "frame/shadows", "count": 120, "avg_us": 412.3, "min_us": 398.0, "max_us": 530.2 }, ...]`
+pub fn write_summary_json(
+    target: &Path,
+    aggregator: &GpuTimerQueryAggregator,
+) -> std::io::Result<()> {
+    let mut file = File::create(target)?;
+
+    writeln!(file, "[")?;
+    let mut entries = aggregator.iter().peekable();
+    while let Some((label_path, statistics)) = entries.next() {
+        write!(
+            file,
+            r#"{{ "label_path":"{}", "count":{}, "avg_us":{}, "min_us":{}, "max_us":{} }}"#,
+            escape_json_string(label_path),
+            statistics.num_samples,
+            statistics.average() * 1000.0 * 1000.0,
+            statistics.min * 1000.0 * 1000.0,
+            statistics.max * 1000.0 * 1000.0,
+        )?;
+        writeln!(file, "{}", if entries.peek().is_some() { "," } else { "" })?;
+    }
+    writeln!(file, "]")?;
+
+    Ok(())
+}
+
+/// Escapes a string for use within a JSON string literal.
+pub(crate) fn escape_json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
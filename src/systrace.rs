@@ -0,0 +1,76 @@
+use std::{fs::File, io::Write, path::Path};
+
+use crate::{chrometrace::thread_id_to_u64, GpuTimerQueryResult};
+
+/// Writes a systrace/atrace text file that can be loaded in [Perfetto](https://ui.perfetto.dev/)
+/// or merged with on-device system traces.
+///
+/// Every timed scope is emitted as a pair of `tracing_mark_write` begin/end markers, just like
+/// `ATrace_beginSection`/`ATrace_endSection` would produce them.
+/// Scopes without timing information are skipped, but their nested scopes are still written.
+pub fn write_systrace(target: &Path, profile_data: &[GpuTimerQueryResult]) -> std::io::Result<()> {
+    let mut events = Vec::new();
+    collect_events_recursive(&mut events, profile_data);
+    // Stable sort, so that begin/end markers with identical timestamps keep their nesting order.
+    events.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+    let mut file = File::create(target)?;
+
+    writeln!(file, "# tracer: nop")?;
+    writeln!(file, "#")?;
+    for event in events {
+        let SystraceEvent {
+            timestamp,
+            pid,
+            tid,
+            label,
+        } = event;
+        write!(
+            file,
+            "wgpu-profiler-{tid} ({pid}) [000] ...1 {timestamp:.6}: tracing_mark_write: "
+        )?;
+        if let Some(label) = label {
+            writeln!(file, "B|{pid}|{label}")?;
+        } else {
+            writeln!(file, "E|{pid}")?;
+        }
+    }
+
+    Ok(())
+}
+
+struct SystraceEvent<'a> {
+    /// Timestamp in seconds.
+    timestamp: f64,
+    pid: u32,
+    tid: u64,
+
+    /// Label for begin events, `None` for end events.
+    label: Option<&'a str>,
+}
+
+fn collect_events_recursive<'a>(
+    events: &mut Vec<SystraceEvent<'a>>,
+    results: &'a [GpuTimerQueryResult],
+) {
+    for result in results {
+        let tid = thread_id_to_u64(result.tid);
+        if let Some(time) = &result.time {
+            events.push(SystraceEvent {
+                timestamp: time.start,
+                pid: result.pid,
+                tid,
+                label: Some(&result.label),
+            });
+        }
+        collect_events_recursive(events, &result.nested_queries);
+        if let Some(time) = &result.time {
+            events.push(SystraceEvent {
+                timestamp: time.end,
+                pid: result.pid,
+                tid,
+                label: None,
+            });
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Helpers for testing code that is instrumented with profiler scopes.
+//!
+//! Available with the `test-support` feature.
+
+use crate::{GpuProfiler, GpuProfilerSettings, GpuTimerQueryResult};
+
+/// Expected shape of a profiler scope, used with [`assert_scope_tree`], [`validate_results`] and [`validate_results_unordered`].
+#[derive(Debug, Clone)]
+pub struct ExpectedScope {
+    /// Expected label of the scope.
+    pub label: String,
+
+    /// If set, the scope is expected to have timing information iff all of these features are enabled.
+    ///
+    /// If `None`, timing information isn't checked.
+    pub requires: Option<wgpu::Features>,
+
+    /// Expected nested scopes, in order.
+    pub nested_scopes: Vec<ExpectedScope>,
+}
+
+impl ExpectedScope {
+    /// Creates an [`ExpectedScope`] without nested scopes, ignoring timings.
+    pub fn new(label: impl Into<String>) -> Self {
+        expected_scope(label, [])
+    }
+
+    /// Sets the expected nested scopes, in order.
+    #[must_use]
+    pub fn with_nested_scopes(
+        mut self,
+        nested_scopes: impl IntoIterator<Item = ExpectedScope>,
+    ) -> Self {
+        self.nested_scopes = nested_scopes.into_iter().collect();
+        self
+    }
+
+    /// Expects timing information for this scope iff all of the given features are enabled.
+    #[must_use]
+    pub fn requires(mut self, features: wgpu::Features) -> Self {
+        self.requires = Some(features);
+        self
+    }
+}
+
+/// Creates an [`ExpectedScope`] with the given label and nested scopes, ignoring timings.
+pub fn expected_scope(
+    label: impl Into<String>,
+    nested_scopes: impl Into<Vec<ExpectedScope>>,
+) -> ExpectedScope {
+    ExpectedScope {
+        label: label.into(),
+        requires: None,
+        nested_scopes: nested_scopes.into(),
+    }
+}
+
+/// Asserts that the results have exactly the expected shape and labels.
+///
+/// `features` are the features enabled on the device, used to check for the presence of timing information
+/// for scopes with [`ExpectedScope::requires`].
+#[track_caller]
+pub fn assert_scope_tree(
+    results: &[GpuTimerQueryResult],
+    expected: &[ExpectedScope],
+    features: wgpu::Features,
+) {
+    validate_results(results, expected, &|expected: &ExpectedScope| {
+        expected
+            .requires
+            .map(|requires| features.contains(requires))
+    });
+}
+
+/// Asserts that the results have exactly the expected shape and labels, in order.
+///
+/// `expects_time` decides whether a scope is expected to have timing information,
+/// e.g. depending on the features of the device or on [`ExpectedScope::requires`].
+/// Timing information isn't checked for scopes it returns `None` for.
+#[track_caller]
+pub fn validate_results(
+    results: &[GpuTimerQueryResult],
+    expected: &[ExpectedScope],
+    expects_time: &impl Fn(&ExpectedScope) -> Option<bool>,
+) {
+    assert_eq!(
+        results.len(),
+        expected.len(),
+        "results: {results:?}\nexpected: {expected:?}"
+    );
+    for (result, expected) in results.iter().zip(expected.iter()) {
+        assert_eq!(result.label, expected.label);
+        validate_result(result, expected, expects_time);
+    }
+}
+
+/// Like [`validate_results`], but top level results may be in any order.
+///
+/// Useful if scopes were recorded from several threads, in which case the order of the top level results isn't deterministic.
+/// Nested results are still expected in order. Labels of top level scopes have to be unique.
+#[track_caller]
+pub fn validate_results_unordered(
+    results: &[GpuTimerQueryResult],
+    expected: &[ExpectedScope],
+    expects_time: &impl Fn(&ExpectedScope) -> Option<bool>,
+) {
+    assert_eq!(
+        results.len(),
+        expected.len(),
+        "results: {results:?}\nexpected: {expected:?}"
+    );
+    let mut remaining_expected = expected.iter().collect::<Vec<_>>();
+    for result in results {
+        let Some(index) = remaining_expected
+            .iter()
+            .position(|expected| expected.label == result.label)
+        else {
+            panic!("unexpected result for label: {}", result.label);
+        };
+        validate_result(result, remaining_expected.swap_remove(index), expects_time);
+    }
+}
+
+#[track_caller]
+fn validate_result(
+    result: &GpuTimerQueryResult,
+    expected: &ExpectedScope,
+    expects_time: &impl Fn(&ExpectedScope) -> Option<bool>,
+) {
+    if let Some(expects_time) = expects_time(expected) {
+        assert_eq!(
+            result.time.is_some(),
+            expects_time,
+            "label: {}",
+            result.label
+        );
+    }
+    validate_results(
+        &result.nested_queries,
+        &expected.nested_scopes,
+        expects_time,
+    );
+}
+
+/// A scope with raw timestamps, used with [`build_results`].
+#[derive(Debug, Clone, Default)]
+pub struct RawScope {
+    /// Label of the scope.
+    pub label: String,
+
+    /// Raw start & end timestamps in device ticks, as read back from the GPU.
+    ///
+    /// `None` for scopes without timer queries.
+    pub timestamps: Option<(u64, u64)>,
+
+    /// Category of the scope, see [`GpuTimerQueryResult::category`].
+    pub category: Option<&'static str>,
+
+    /// Scopes that were opened while this scope was open.
+    pub nested_scopes: Vec<RawScope>,
+}
+
+/// Builds results from raw timestamps without a device,
+/// validating & converting them the same way as [`GpuProfiler::process_finished_frame`] does.
+///
+/// Allows testing how edge cases like timestamp wraparound show up in results.
+/// Labels are transformed, short scopes dropped and results sorted according to the passed settings.
+pub fn build_results(
+    scopes: &[RawScope],
+    timestamp_period: f32,
+    settings: &GpuProfilerSettings,
+) -> Vec<GpuTimerQueryResult> {
+    <GpuProfiler>::build_results_from_raw(scopes, timestamp_period, settings)
+}
+
+/// Returns results of a representative frame without a device, e.g. for testing overlays or exporters.
+///
+/// The frame consists of a few nested passes with realistic timings of a 60Hz frame,
+/// including a scope without timing information and scopes with categories & unit counts.
+/// Always returns the same labels & timings, process & thread id are those of the calling thread.
+pub fn mock_results() -> Vec<GpuTimerQueryResult> {
+    const MS: f64 = 0.001;
+    let start = 1.0;
+    let ms = |from: f64, to: f64| (start + from * MS)..(start + to * MS);
+
+    vec![
+        GpuTimerQueryResult::new("shadows")
+            .with_time(ms(0.0, 2.4))
+            .with_category("shadows")
+            .with_nested_queries([
+                GpuTimerQueryResult::new("cascade 0")
+                    .with_time(ms(0.05, 1.1))
+                    .with_unit_count(412),
+                GpuTimerQueryResult::new("cascade 1")
+                    .with_time(ms(1.15, 2.35))
+                    .with_unit_count(655),
+            ]),
+        GpuTimerQueryResult::new("main pass")
+            .with_time(ms(2.5, 9.8))
+            .with_category("geometry")
+            .with_nested_queries([
+                GpuTimerQueryResult::new("opaque")
+                    .with_time(ms(2.55, 8.1))
+                    .with_unit_count(1873),
+                GpuTimerQueryResult::new("sky").with_time(ms(8.15, 8.4)),
+                GpuTimerQueryResult::new("transparent")
+                    .with_time(ms(8.45, 9.75))
+                    .with_unit_count(96),
+            ]),
+        GpuTimerQueryResult::new("post processing")
+            .with_time(ms(10.0, 12.2))
+            .with_category("post")
+            .with_nested_queries([
+                GpuTimerQueryResult::new("bloom").with_time(ms(10.05, 11.3)),
+                GpuTimerQueryResult::new("tonemapping").with_time(ms(11.35, 12.15)),
+            ]),
+        GpuTimerQueryResult::new("ui")
+            .with_nested_queries([GpuTimerQueryResult::new("text").with_time(ms(12.3, 12.9))]),
+    ]
+}
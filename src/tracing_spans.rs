@@ -0,0 +1,34 @@
+//! Reporting of results to the [`tracing`] ecosystem.
+//!
+//! Available with the `tracing` feature.
+
+use crate::GpuTimerQueryResult;
+
+/// Target of all spans emitted by [`report_to_tracing_nested`].
+pub const TRACING_TARGET: &str = "wgpu_profiler";
+
+/// Reports results as nested `tracing` spans, preserving the parent-child structure of the scopes.
+///
+/// Every scope becomes a span named `gpu_scope` that is entered while its nested scopes are reported,
+/// so subscribers like `tracing-chrome` see the same hierarchy as the profiler.
+/// The spans themselves are entered & exited right away on the calling thread, since `tracing` has no way
+/// to specify when a span happened. The GPU timings are instead recorded as span fields:
+/// `label`, `gpu_start_us` & `gpu_duration_us`. The timing fields are left empty for scopes without timing information.
+pub fn report_to_tracing_nested(results: &[GpuTimerQueryResult]) {
+    for result in results {
+        let span = tracing::info_span!(
+            target: TRACING_TARGET,
+            "gpu_scope",
+            label = result.label.as_str(),
+            gpu_start_us = tracing::field::Empty,
+            gpu_duration_us = tracing::field::Empty,
+        );
+        if let Some(time) = &result.time {
+            span.record("gpu_start_us", time.start * 1000.0 * 1000.0);
+            span.record("gpu_duration_us", (time.end - time.start) * 1000.0 * 1000.0);
+        }
+
+        let _entered = span.enter();
+        report_to_tracing_nested(&result.nested_queries);
+    }
+}
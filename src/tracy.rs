@@ -1,46 +1,61 @@
-use crate::CreationError;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+use parking_lot::Mutex;
+
+use crate::{clock_calibration::submit_calibration_timestamp, CreationError};
 
 pub fn create_tracy_gpu_client(
     backend: wgpu::Backend,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
 ) -> Result<tracy_client::GpuContext, CreationError> {
-    let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
-        label: Some("wgpu-profiler gpu -> cpu sync query_set"),
-        ty: wgpu::QueryType::Timestamp,
-        count: 1,
-    });
+    let map_buffer = submit_calibration_timestamp(device, queue);
+
+    map_buffer.slice(..).map_async(wgpu::MapMode::Read, |_| ());
+    device.poll(wgpu::Maintain::Wait);
 
-    let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("wgpu-profiler gpu -> cpu resolve buffer"),
-        size: wgpu::QUERY_SIZE as _,
-        usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
-        mapped_at_creation: false,
-    });
+    create_gpu_context(backend, queue, &map_buffer)
+}
 
-    let map_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("wgpu-profiler gpu -> cpu map buffer"),
-        size: wgpu::QUERY_SIZE as _,
-        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
+/// Like [`create_tracy_gpu_client`], but awaits the readback of the calibration timestamp instead of blocking on the device.
+///
+/// The device needs to be polled elsewhere for this to complete on native backends.
+pub async fn create_tracy_gpu_client_async(
+    backend: wgpu::Backend,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> Result<tracy_client::GpuContext, CreationError> {
+    let map_buffer = submit_calibration_timestamp(device, queue);
 
-    let mut timestamp_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-        label: Some("wgpu-profiler gpu -> cpu query timestamp"),
-    });
-    timestamp_encoder.write_timestamp(&query_set, 0);
-    timestamp_encoder.resolve_query_set(&query_set, 0..1, &resolve_buffer, 0);
-    // Workaround for https://github.com/gfx-rs/wgpu/issues/6406
-    // TODO when that bug is fixed, merge these encoders together again
-    let mut copy_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-        label: Some("wgpu-profiler gpu -> cpu copy timestamp"),
-    });
-    copy_encoder.copy_buffer_to_buffer(&resolve_buffer, 0, &map_buffer, 0, wgpu::QUERY_SIZE as _);
-    queue.submit([timestamp_encoder.finish(), copy_encoder.finish()]);
+    let mapping = MapFuture::default();
+    let mapping_state = mapping.state.clone();
+    map_buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, move |mapping_result| {
+            let mut state = mapping_state.lock();
+            state.result = Some(mapping_result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+    mapping
+        .await
+        .map_err(CreationError::TracyCalibrationReadbackFailed)?;
 
-    map_buffer.slice(..).map_async(wgpu::MapMode::Read, |_| ());
-    device.poll(wgpu::Maintain::Wait);
+    create_gpu_context(backend, queue, &map_buffer)
+}
 
+/// Creates the Tracy GPU context from the calibration timestamp in the already mapped `map_buffer`.
+fn create_gpu_context(
+    backend: wgpu::Backend,
+    queue: &wgpu::Queue,
+    map_buffer: &wgpu::Buffer,
+) -> Result<tracy_client::GpuContext, CreationError> {
     let view = map_buffer.slice(..).get_mapped_range();
     let timestamp: i64 = i64::from_le_bytes((*view).try_into().unwrap());
 
@@ -63,3 +78,30 @@ pub fn create_tracy_gpu_client(
         )
         .map_err(CreationError::from)
 }
+
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+/// Future that completes once the `map_async` callback sharing its state has been called.
+#[derive(Default)]
+struct MapFuture {
+    state: Arc<Mutex<MapState>>,
+}
+
+impl Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
+
+use crate::src::{expected_scope, validate_results, Requires};
+
+use super::create_device;
+
+#[test]
+fn accumulating_scope_merges_regions() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let particle_update = profiler.accumulating_scope("particle update");
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut frame_scope = profiler.scope("frame", &mut encoder, &device);
+        for i in 0..3 {
+            let region = profiler
+                .begin_accumulating_query(&particle_update, &mut *frame_scope, &device)
+                .with_parent(frame_scope.scope.as_ref());
+            if i == 1 {
+                let nested = profiler
+                    .begin_query("emit", &mut *frame_scope, &device)
+                    .with_parent(Some(&region));
+                profiler.end_query(&mut *frame_scope, nested);
+            }
+            profiler.end_query(&mut *frame_scope, region);
+
+            drop(frame_scope.scope(format!("between {i}"), &device));
+        }
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+
+    // Print entire tree. Useful for debugging the test if it fails!
+    println!("{:#?}", frame);
+
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "frame",
+            Requires::TimestampsInEncoders,
+            [
+                expected_scope(
+                    "particle update",
+                    Requires::TimestampsInEncoders,
+                    [expected_scope("emit", Requires::TimestampsInEncoders, [])],
+                ),
+                expected_scope("between 0", Requires::TimestampsInEncoders, []),
+                expected_scope("between 1", Requires::TimestampsInEncoders, []),
+                expected_scope("between 2", Requires::TimestampsInEncoders, []),
+            ],
+        )],
+    );
+
+    let expected_num_samples =
+        if Requires::TimestampsInEncoders.expect_time_result(device.features()) {
+            3
+        } else {
+            0
+        };
+    assert_eq!(frame[0].num_samples, None);
+    assert_eq!(
+        frame[0].nested_queries[0].num_samples,
+        Some(expected_num_samples)
+    );
+}
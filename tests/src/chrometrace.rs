@@ -0,0 +1,264 @@
+use wgpu_profiler::{
+    chrometrace::{ChromeTraceOptions, TimestampMarkers},
+    FrameInfo, GpuProfiler, GpuProfilerSettings,
+};
+
+use crate::src::{create_device, synthetic_result};
+
+#[test]
+fn sub_microsecond_scopes_keep_distinct_timestamps() {
+    let results = vec![
+        synthetic_result("a", Some(1.0000001..1.0000002), vec![]),
+        synthetic_result("b", Some(1.0000002..1.0000003), vec![]),
+        synthetic_result("c", Some(1.0000003..1.0000004), vec![]),
+    ];
+
+    let path = std::env::temp_dir().join(format!(
+        "wgpu-profiler-chrometrace-test-{}.json",
+        std::process::id()
+    ));
+    wgpu_profiler::chrometrace::write_chrometrace(&path, &results).unwrap();
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let timestamps = trace
+        .split("\"ts\":")
+        .skip(1)
+        .map(|s| s.split(',').next().unwrap().parse::<f64>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(timestamps.len(), 3);
+    for (timestamp, expected) in timestamps.iter().zip([1000000.1, 1000000.2, 1000000.3]) {
+        assert!(
+            (timestamp - expected).abs() < 1e-3,
+            "{timestamp} != {expected}"
+        );
+    }
+}
+
+#[test]
+fn submit_markers_are_aligned_to_gpu_start() {
+    let results = vec![synthetic_result("a", Some(2.0..3.0), vec![])];
+    let first_submit = std::time::Instant::now();
+    let markers = [
+        wgpu_profiler::SubmitMarker {
+            label: "first".to_owned(),
+            time: first_submit,
+        },
+        wgpu_profiler::SubmitMarker {
+            label: "second".to_owned(),
+            time: first_submit + std::time::Duration::from_millis(500),
+        },
+    ];
+
+    let path = std::env::temp_dir().join(format!(
+        "wgpu-profiler-submit-markers-test-{}.json",
+        std::process::id()
+    ));
+    let options = ChromeTraceOptions {
+        submit_markers: &markers,
+        ..Default::default()
+    };
+    wgpu_profiler::chrometrace::write_chrometrace_with_options(&path, &results, &options).unwrap();
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(trace.contains("CPU submits"));
+    let timestamps = trace
+        .split("\"ts\":")
+        .skip(1)
+        .map(|s| s.split(',').next().unwrap().parse::<f64>().unwrap())
+        .collect::<Vec<_>>();
+    // Two markers followed by the scope.
+    assert_eq!(timestamps.len(), 3);
+    for (timestamp, expected) in timestamps.iter().zip([2000000.0, 2500000.0, 2000000.0]) {
+        assert!(
+            (timestamp - expected).abs() < 1e-3,
+            "{timestamp} != {expected}"
+        );
+    }
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_trace_matches_uncompressed_trace() {
+    use std::io::Read as _;
+
+    let results = vec![synthetic_result(
+        "a",
+        Some(1.0..2.0),
+        vec![synthetic_result("b", Some(1.0..1.5), vec![])],
+    )];
+
+    let path = std::env::temp_dir().join(format!(
+        "wgpu-profiler-chrometrace-test-{}.json",
+        std::process::id()
+    ));
+    let gz_path = path.with_extension("json.gz");
+    wgpu_profiler::chrometrace::write_chrometrace(&path, &results).unwrap();
+    let options = ChromeTraceOptions {
+        gzip: true,
+        ..Default::default()
+    };
+    wgpu_profiler::chrometrace::write_chrometrace_with_options(&gz_path, &results, &options)
+        .unwrap();
+    let trace = std::fs::read_to_string(&path).unwrap();
+    let mut decompressed = String::new();
+    flate2::read::GzDecoder::new(std::fs::File::open(&gz_path).unwrap())
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&gz_path).unwrap();
+
+    assert_eq!(decompressed, trace);
+}
+
+#[test]
+fn threads_are_named_after_their_gpu_busy_time() {
+    let other_thread_id = std::thread::spawn(|| std::thread::current().id())
+        .join()
+        .unwrap();
+    let results = vec![
+        synthetic_result("a", Some(0.0..0.002), vec![]),
+        synthetic_result("b", Some(0.001..0.003), vec![]),
+        wgpu_profiler::GpuTimerQueryResult {
+            tid: other_thread_id,
+            ..synthetic_result("c", Some(0.0..0.0005), vec![])
+        },
+    ];
+
+    let path = std::env::temp_dir().join(format!(
+        "wgpu-profiler-thread-summary-test-{}.json",
+        std::process::id()
+    ));
+    wgpu_profiler::chrometrace::write_chrometrace(&path, &results).unwrap();
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(trace.matches("\"thread_name\"").count(), 2);
+    assert!(trace.contains("(3.000 ms GPU busy)"));
+    assert!(trace.contains("(0.500 ms GPU busy)"));
+}
+
+#[test]
+fn frame_label_is_written_as_global_instant_event() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("a", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler
+        .end_frame_with(FrameInfo {
+            label: Some("steady \"state\"".to_owned()),
+            ..Default::default()
+        })
+        .unwrap();
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frames = profiler.process_all_finished_frames(queue.get_timestamp_period());
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].label, None);
+    let frame_label = frames[0].label.as_deref().unwrap();
+    assert_eq!(frame_label, "steady \"state\"");
+    let results = &frames[0].results;
+
+    let path = std::env::temp_dir().join(format!(
+        "wgpu-profiler-frame-label-test-{}.json",
+        std::process::id()
+    ));
+    let options = ChromeTraceOptions {
+        frame_label: Some(frame_label),
+        submit_markers: &frames[0].submit_markers,
+        ..Default::default()
+    };
+    wgpu_profiler::chrometrace::write_chrometrace_with_options(&path, results, &options).unwrap();
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(trace.contains(r#""s":"g", "name":"steady \"state\"""#));
+}
+
+#[test]
+fn extra_metrics_are_written_as_args() {
+    let results = vec![
+        synthetic_result("a", Some(0.0..1.0), vec![])
+            .with_extra_metric("occupancy", 0.5)
+            .with_extra_metric("invocations", 1024.0),
+        synthetic_result("b", Some(1.0..2.0), vec![]),
+    ];
+
+    let path = std::env::temp_dir().join(format!(
+        "wgpu-profiler-extra-metrics-test-{}.json",
+        std::process::id()
+    ));
+    wgpu_profiler::chrometrace::write_chrometrace(&path, &results).unwrap();
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(trace.contains(r#""args":{ "invocations":1024, "occupancy":0.5 }"#));
+}
+
+#[test]
+fn timestamp_markers_are_written_as_instant_events() {
+    let results = vec![synthetic_result(
+        "a",
+        Some(1.0..2.0),
+        vec![synthetic_result("b", Some(1.5..1.75), vec![])],
+    )];
+
+    let path = std::env::temp_dir().join(format!(
+        "wgpu-profiler-timestamp-markers-test-{}.json",
+        std::process::id()
+    ));
+    let mut traces = Vec::new();
+    for timestamp_markers in [
+        TimestampMarkers::None,
+        TimestampMarkers::WithDuration,
+        TimestampMarkers::InsteadOfDuration,
+    ] {
+        let options = ChromeTraceOptions {
+            timestamp_markers,
+            ..Default::default()
+        };
+        wgpu_profiler::chrometrace::write_chrometrace_with_options(&path, &results, &options)
+            .unwrap();
+        traces.push(std::fs::read_to_string(&path).unwrap());
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    let count = |trace: &str, pattern: &str| trace.matches(pattern).count();
+    assert_eq!(count(&traces[0], r#""ph":"X""#), 2);
+    assert_eq!(count(&traces[0], r#""ph":"i""#), 0);
+
+    assert_eq!(count(&traces[1], r#""ph":"X""#), 2);
+    assert_eq!(count(&traces[1], r#""ph":"i""#), 4);
+    assert!(traces[1].contains(r#""ts":1500000, "ph":"i", "s":"t", "name":"b begin""#));
+    assert!(traces[1].contains(r#""ts":1750000, "ph":"i", "s":"t", "name":"b end""#));
+
+    assert_eq!(count(&traces[2], r#""ph":"X""#), 0);
+    assert_eq!(count(&traces[2], r#""ph":"i""#), 4);
+    // Last event must not be followed by a comma.
+    assert!(traces[2].contains("\"name\":\"b end\" }\n]"));
+}
+
+#[test]
+fn labels_and_categories_are_escaped() {
+    let results = vec![wgpu_profiler::GpuTimerQueryResult {
+        category: Some(r#"cat "quoted""#),
+        ..synthetic_result(r#"label "quoted""#, Some(0.0..1.0), vec![])
+    }];
+
+    let path = std::env::temp_dir().join(format!(
+        "wgpu-profiler-escape-test-{}.json",
+        std::process::id()
+    ));
+    wgpu_profiler::chrometrace::write_chrometrace(&path, &results).unwrap();
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(trace.contains(r#""name":"label \"quoted\"""#));
+    assert!(trace.contains(r#""cat":"cat \"quoted\"""#));
+}
@@ -0,0 +1,74 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
+
+use crate::src::{expected_scope, validate_results, Requires};
+
+use super::create_device;
+
+fn copy_timing(device: &wgpu::Device, queue: &wgpu::Queue) {
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let buffer_descriptor = wgpu::BufferDescriptor {
+        label: None,
+        size: 1024,
+        usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    };
+    let source = device.create_buffer(&buffer_descriptor);
+    let destination = device.create_buffer(&buffer_descriptor);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("frame", &mut encoder, device);
+        scope.measure_copy("clear", device, |encoder| {
+            encoder.clear_buffer(&source, 0, None);
+        });
+        let copied = scope.measure_copy("copy", device, |encoder| {
+            encoder.copy_buffer_to_buffer(&source, 0, &destination, 0, buffer_descriptor.size);
+            buffer_descriptor.size
+        });
+        assert_eq!(copied, 1024);
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "frame",
+            Requires::TimestampsInEncoders,
+            [
+                expected_scope("clear", Requires::TimestampsInEncoders, []),
+                expected_scope("copy", Requires::TimestampsInEncoders, []),
+            ],
+        )],
+    );
+}
+
+#[test]
+fn copy_timing_timestamp_in_encoders_enabled() {
+    let Ok((_, device, queue)) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+    ) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY_INSIDE_ENCODERS");
+        return;
+    };
+    copy_timing(&device, &queue);
+}
+
+#[test]
+fn copy_timing_timestamp_enabled() {
+    let Ok((_, device, queue)) = create_device(wgpu::Features::TIMESTAMP_QUERY) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY");
+        return;
+    };
+    copy_timing(&device, &queue);
+}
@@ -0,0 +1,118 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings, LabelTransform, ProfilerCommandRecorder};
+
+use super::create_device;
+
+/// Forwards to an encoder, remembering all pushed debug groups.
+struct DebugGroupRecorder<'a> {
+    encoder: &'a mut wgpu::CommandEncoder,
+    debug_groups: Vec<String>,
+}
+
+impl ProfilerCommandRecorder for DebugGroupRecorder<'_> {
+    fn is_pass(&self) -> bool {
+        false
+    }
+
+    fn write_timestamp(&mut self, query_set: &wgpu::QuerySet, query_index: u32) {
+        self.encoder.write_timestamp(query_set, query_index);
+    }
+
+    fn push_debug_group(&mut self, label: &str) {
+        self.debug_groups.push(label.to_owned());
+        self.encoder.push_debug_group(label);
+    }
+
+    fn pop_debug_group(&mut self) {
+        self.encoder.pop_debug_group();
+    }
+}
+
+#[test]
+fn debug_groups_only_for_slow_scopes() {
+    let Ok((_, device, queue)) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+    ) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY_INSIDE_ENCODERS");
+        return;
+    };
+
+    // Every timed scope is slow enough.
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        debug_group_threshold: Some(0.0),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let record_frame = |labels: &[&str]| {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let mut recorder = DebugGroupRecorder {
+            encoder: &mut encoder,
+            debug_groups: Vec::new(),
+        };
+        for label in labels {
+            let query = profiler.begin_query(*label, &mut recorder, &device);
+            profiler.end_query(&mut recorder, query);
+        }
+        let debug_groups = recorder.debug_groups;
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+        device.poll(wgpu::Maintain::Wait);
+        profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .unwrap();
+        debug_groups
+    };
+
+    // Nothing is known to be slow before the first frame was processed.
+    assert!(record_frame(&["a"]).is_empty());
+    assert_eq!(record_frame(&["a", "b"]), ["a"]);
+    assert_eq!(record_frame(&["a", "b"]), ["a", "b"]);
+}
+
+#[test]
+fn debug_groups_match_untransformed_label_paths() {
+    let Ok((_, device, queue)) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+    ) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY_INSIDE_ENCODERS");
+        return;
+    };
+
+    // Reported labels are all the same, matching has to happen on the original labels.
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        debug_group_threshold: Some(0.0),
+        label_transform: Some(LabelTransform::new(|_| "scope".to_owned())),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let record_frame = |nested: bool| {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let mut recorder = DebugGroupRecorder {
+            encoder: &mut encoder,
+            debug_groups: Vec::new(),
+        };
+        {
+            let mut scope = profiler.scope("a", &mut recorder, &device);
+            if nested {
+                drop(scope.scope("b", &device));
+            }
+        }
+        drop(profiler.scope("b", &mut recorder, &device));
+        let debug_groups = recorder.debug_groups;
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+        device.poll(wgpu::Maintain::Wait);
+        profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .unwrap();
+        debug_groups
+    };
+
+    assert!(record_frame(false).is_empty());
+    // Only the top level "b" was slow before, not "a/b".
+    assert_eq!(record_frame(true), ["a", "b"]);
+    assert_eq!(record_frame(true), ["a", "b", "b"]);
+}
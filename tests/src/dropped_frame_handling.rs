@@ -1,4 +1,4 @@
-use wgpu_profiler::GpuProfilerSettings;
+use wgpu_profiler::{FrameInfo, GpuProfilerSettings};
 
 use super::create_device;
 
@@ -11,7 +11,7 @@ fn handle_dropped_frames_gracefully() {
     .unwrap();
 
     // max_num_pending_frames is one!
-    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
         max_num_pending_frames: 1,
         ..Default::default()
     })
@@ -43,3 +43,420 @@ fn handle_dropped_frames_gracefully() {
         .process_finished_frame(queue.get_timestamp_period())
         .is_none());
 }
+
+#[test]
+fn capture_frames_does_not_drop_frames() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        max_num_pending_frames: 1,
+        ..Default::default()
+    })
+    .unwrap();
+
+    const NUM_CAPTURED_FRAMES: usize = 3;
+    profiler.capture_frames(NUM_CAPTURED_FRAMES);
+    assert!(profiler.is_capturing());
+
+    // More frames than pending frames allowed without device poll, none should be dropped while capturing.
+    for _ in 0..NUM_CAPTURED_FRAMES {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope("testscope", &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+
+    device.poll(wgpu::Maintain::Wait);
+
+    for _ in 0..NUM_CAPTURED_FRAMES {
+        assert!(profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .is_some());
+    }
+    assert!(!profiler.is_capturing());
+
+    let capture = profiler.take_capture();
+    assert_eq!(capture.len(), NUM_CAPTURED_FRAMES);
+    for frame in capture {
+        assert_eq!(frame.len(), 1);
+        assert_eq!(frame[0].label, "testscope");
+    }
+    assert!(profiler.take_capture().is_empty());
+}
+
+#[test]
+fn detect_and_clear_stuck_frames() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    // Never poll the device, so frames can't finish.
+    for _ in 0..2 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope("testscope", &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        profiler.end_frame().unwrap();
+    }
+
+    let status = profiler.pending_frame_status();
+    assert_eq!(status.len(), 2);
+    for frame in status {
+        assert_eq!(frame.pools, 1);
+        assert!(frame.mapped <= frame.pools);
+        assert_eq!(frame.num_scopes, 1);
+    }
+
+    assert!(profiler.has_stuck_frames(1));
+    assert!(!profiler.has_stuck_frames(2));
+
+    profiler.clear_pending_frames();
+    assert!(!profiler.has_stuck_frames(0));
+    assert!(profiler.pending_frame_status().is_empty());
+
+    device.poll(wgpu::Maintain::Wait);
+    assert!(profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .is_none());
+}
+
+#[test]
+fn process_all_finished_frames_in_order() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        max_num_pending_frames: 2,
+        ..Default::default()
+    })
+    .unwrap();
+
+    // Three frames without device poll, the second one is dropped to make room for the third.
+    for _ in 0..3 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope("testscope", &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frames = profiler.process_all_finished_frames(queue.get_timestamp_period());
+    let frame_indices = frames.iter().map(|frame| frame.index).collect::<Vec<_>>();
+    assert_eq!(frame_indices, [0, 2]);
+    assert!(frames.iter().all(|frame| frame.results.len() == 1));
+    assert!(profiler
+        .process_all_finished_frames(queue.get_timestamp_period())
+        .is_empty());
+}
+
+#[test]
+fn recent_history_keeps_last_frames() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        history_len: 2,
+        ..Default::default()
+    })
+    .unwrap();
+
+    for frame in 0..3 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope("testscope", &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+
+        device.poll(wgpu::Maintain::Wait);
+        if frame == 2 {
+            profiler
+                .process_finished_frame_flat(queue.get_timestamp_period())
+                .unwrap();
+        } else {
+            profiler
+                .process_finished_frame(queue.get_timestamp_period())
+                .unwrap();
+        }
+    }
+
+    let history = profiler.recent_history();
+    let frame_indices = history
+        .iter()
+        .map(|summary| summary.frame_index)
+        .collect::<Vec<_>>();
+    assert_eq!(frame_indices, [1, 2]);
+    let expect_time = device
+        .features()
+        .contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+    for summary in &history {
+        assert_eq!(summary.top_level_scopes.len(), expect_time as usize);
+        if let Some((label, duration)) = summary.top_level_scopes.first() {
+            assert_eq!(label, "testscope");
+            assert!(*duration <= summary.gpu_time);
+        }
+    }
+}
+
+#[test]
+fn cpu_overhead_is_measured_if_enabled() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    for measure_cpu_overhead in [false, true] {
+        let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+            measure_cpu_overhead,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope("testscope", &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+
+        device.poll(wgpu::Maintain::Wait);
+        profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .unwrap();
+
+        let statistics = profiler.statistics();
+        let cpu_times = [
+            statistics.cpu_time_resolve_queries,
+            statistics.cpu_time_end_frame,
+            statistics.cpu_time_process_finished_frame,
+        ];
+        if measure_cpu_overhead {
+            assert!(cpu_times.iter().all(|time| !time.is_zero()));
+        } else {
+            assert!(cpu_times.iter().all(|time| time.is_zero()));
+        }
+    }
+}
+
+#[test]
+fn frames_without_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    // An empty frame has nothing to wait for.
+    profiler.end_frame().unwrap();
+    let frame = profiler.process_finished_frame(queue.get_timestamp_period());
+    assert!(frame.is_some_and(|frame| frame.results.is_empty()));
+    assert!(profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .is_none());
+
+    // But it doesn't overtake frames ended before it.
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let _ = profiler.scope("testscope", &mut encoder, &device);
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frames = profiler.process_all_finished_frames(queue.get_timestamp_period());
+    let frame_indices = frames.iter().map(|frame| frame.index).collect::<Vec<_>>();
+    assert_eq!(frame_indices, [1, 2]);
+    assert_eq!(frames[0].results.len(), 1);
+    assert!(frames[1].results.is_empty());
+}
+
+#[test]
+fn frames_are_recycled_after_map_timeout() {
+    let (_, device, _queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        max_num_pending_frames: 10,
+        map_timeout_frames: Some(1),
+        ..Default::default()
+    })
+    .unwrap();
+
+    // Without polling the device, buffers never get mapped, just like with a hung GPU.
+    for _ in 0..3 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope("testscope", &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        profiler.end_frame().unwrap();
+    }
+
+    // The first frame timed out while ending the third one.
+    assert_eq!(profiler.statistics().num_timed_out_frames, 1);
+    assert_eq!(profiler.pending_frame_status().len(), 2);
+}
+
+#[test]
+fn only_sampled_frames_are_profiled() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        sample_every_n_frames: Some(3),
+        ..Default::default()
+    })
+    .unwrap();
+
+    for frame in 0..6 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope(format!("frame {frame}"), &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+    device.poll(wgpu::Maintain::Wait);
+
+    let mut labels = Vec::new();
+    while let Some(frame) = profiler.process_finished_frame(queue.get_timestamp_period()) {
+        labels.extend(frame.results.into_iter().map(|result| result.label));
+    }
+    assert_eq!(labels, ["frame 0", "frame 3"]);
+}
+
+#[test]
+fn dropped_frame_indices_are_reported() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        max_num_pending_frames: 1,
+        ..Default::default()
+    })
+    .unwrap();
+
+    // Without polling in between, every new frame evicts the previous one.
+    for _ in 0..3 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope("testscope", &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame_indices = profiler
+        .process_all_finished_frames(queue.get_timestamp_period())
+        .into_iter()
+        .map(|frame| frame.index)
+        .collect::<Vec<_>>();
+    assert_eq!(frame_indices, [2]);
+    assert_eq!(profiler.take_dropped_frame_indices(), [0, 1]);
+    assert!(profiler.take_dropped_frame_indices().is_empty());
+}
+
+#[test]
+fn user_data_is_returned_with_results() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    #[derive(Debug, PartialEq)]
+    struct CaptureConditions {
+        quality_level: u32,
+    }
+
+    let profiler = wgpu_profiler::GpuProfiler::<CaptureConditions>::new_with_user_data(
+        GpuProfilerSettings::default(),
+    )
+    .unwrap();
+
+    for quality_level in 0..2 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope("testscope", &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler
+            .end_frame_with(FrameInfo {
+                user_data: Some(CaptureConditions { quality_level }),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+    device.poll(wgpu::Maintain::Wait);
+
+    // Every processing entry point returns the data.
+    let period = queue.get_timestamp_period();
+    let frame = profiler.process_finished_frame(period).unwrap();
+    assert_eq!(
+        frame.user_data,
+        Some(CaptureConditions { quality_level: 0 })
+    );
+    assert_eq!(frame.results.len(), 1);
+    let frame = profiler.process_finished_frame_flat(period).unwrap();
+    assert_eq!(
+        frame.user_data,
+        Some(CaptureConditions { quality_level: 1 })
+    );
+    assert_eq!(frame.results.len(), 1);
+    // Frames without user data.
+    let frames = profiler.process_all_finished_frames(period);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].user_data, None);
+    assert!(frames[0].results.is_empty());
+}
+
+#[test]
+fn submit_markers_are_returned_with_their_frame() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    for frame in 0..2 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let _ = profiler.scope("testscope", &mut encoder, &device);
+        }
+        profiler.resolve_queries(&mut encoder);
+        profiler.note_submit(format!("submit {frame}"));
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+    device.poll(wgpu::Maintain::Wait);
+
+    // Processing a later frame must not affect the markers of an earlier one.
+    let frames = profiler.process_all_finished_frames(queue.get_timestamp_period());
+    let labels = frames
+        .iter()
+        .map(|frame| {
+            frame
+                .submit_markers
+                .iter()
+                .map(|marker| marker.label.as_str())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(labels, [["submit 0"], ["submit 1"]]);
+}
@@ -16,6 +16,72 @@ fn invalid_pending_frame_count() {
     ));
 }
 
+#[test]
+fn invalid_min_pool_capacity() {
+    for min_pool_capacity in [0, 33, wgpu::QUERY_SET_MAX_QUERIES + 2] {
+        let profiler = wgpu_profiler::GpuProfiler::new(wgpu_profiler::GpuProfilerSettings {
+            min_pool_capacity,
+            ..Default::default()
+        });
+        assert!(matches!(
+            profiler,
+            Err(wgpu_profiler::CreationError::InvalidSettings(
+                wgpu_profiler::SettingsError::InvalidMinPoolCapacity
+            ))
+        ));
+    }
+}
+
+#[test]
+fn invalid_pool_size_decay() {
+    for pool_size_decay in [-0.5, 1.5, f32::NAN] {
+        let profiler = wgpu_profiler::GpuProfiler::new(wgpu_profiler::GpuProfilerSettings {
+            pool_size_decay,
+            ..Default::default()
+        });
+        assert!(matches!(
+            profiler,
+            Err(wgpu_profiler::CreationError::InvalidSettings(
+                wgpu_profiler::SettingsError::InvalidPoolSizeDecay
+            ))
+        ));
+    }
+}
+
+#[test]
+fn invalid_buffer_usages() {
+    for (additional_resolve_buffer_usages, additional_read_buffer_usages) in [
+        (wgpu::BufferUsages::MAP_READ, wgpu::BufferUsages::empty()),
+        (wgpu::BufferUsages::empty(), wgpu::BufferUsages::MAP_WRITE),
+    ] {
+        let profiler = wgpu_profiler::GpuProfiler::new(wgpu_profiler::GpuProfilerSettings {
+            additional_resolve_buffer_usages,
+            additional_read_buffer_usages,
+            ..Default::default()
+        });
+        assert!(matches!(
+            profiler,
+            Err(wgpu_profiler::CreationError::InvalidSettings(
+                wgpu_profiler::SettingsError::InvalidBufferUsages
+            ))
+        ));
+    }
+}
+
+#[test]
+fn invalid_sample_every_n_frames() {
+    let profiler = wgpu_profiler::GpuProfiler::new(wgpu_profiler::GpuProfilerSettings {
+        sample_every_n_frames: Some(0),
+        ..Default::default()
+    });
+    assert_eq!(
+        profiler.err(),
+        Some(wgpu_profiler::CreationError::InvalidSettings(
+            wgpu_profiler::SettingsError::InvalidSampleEveryNFrames
+        ))
+    );
+}
+
 #[test]
 fn end_frame_unclosed_query() {
     let (_, device, _queue) = create_device(
@@ -23,7 +89,7 @@ fn end_frame_unclosed_query() {
     )
     .unwrap();
 
-    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
     let unclosed_query = {
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
         let query = profiler.begin_query("open query", &mut encoder, &device);
@@ -31,10 +97,23 @@ fn end_frame_unclosed_query() {
         query
     };
 
-    assert_eq!(
-        profiler.end_frame(),
-        Err(wgpu_profiler::EndFrameError::UnclosedQueries(1))
-    );
+    match profiler.end_frame() {
+        Err(wgpu_profiler::EndFrameError::UnclosedQueries(1, open_scopes)) => {
+            if cfg!(debug_assertions) {
+                assert_eq!(open_scopes.len(), 1);
+                assert_eq!(open_scopes[0].label, "open query");
+                // Reports the call site of `begin_query`.
+                assert!(open_scopes[0]
+                    .location
+                    .unwrap()
+                    .file()
+                    .ends_with("errors.rs"));
+            } else {
+                assert!(open_scopes.is_empty());
+            }
+        }
+        result => panic!("unexpected end_frame result: {result:?}"),
+    }
 
     // Make sure we can recover from this.
     {
@@ -52,7 +131,7 @@ fn end_frame_unresolved_query() {
     )
     .unwrap();
 
-    let mut profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
     {
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
         let query = profiler.begin_query("open query", &mut encoder, &device);
@@ -74,6 +153,118 @@ fn end_frame_unresolved_query() {
     device.poll(wgpu::MaintainBase::Wait);
 }
 
+#[test]
+fn end_frame_too_many_scopes() {
+    let (_, device, _queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        max_num_scopes_per_frame: Some(2),
+        ..Default::default()
+    })
+    .unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    for i in 0..3_u32 {
+        drop(profiler.scope(i, &mut encoder, &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+
+    assert_eq!(
+        profiler.end_frame(),
+        Err(wgpu_profiler::EndFrameError::TooManyScopes(3))
+    );
+
+    // Make sure we can recover from this, the limit applies to every frame on its own.
+    drop(profiler.scope("scope 0", &mut encoder, &device));
+    drop(profiler.scope("scope 1", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    assert_eq!(profiler.end_frame(), Ok(()));
+
+    device.poll(wgpu::MaintainBase::Wait);
+}
+
+#[test]
+fn query_set_budget_exhausted() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    // A single pool with room for a single scope.
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        min_pool_capacity: 2,
+        max_num_query_sets: Some(1),
+        ..Default::default()
+    })
+    .unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    for i in 0..3_u32 {
+        drop(profiler.scope(i, &mut encoder, &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    assert_eq!(profiler.end_frame(), Ok(()));
+
+    let statistics = profiler.statistics();
+    assert_eq!(statistics.num_failed_query_pool_creations, 1);
+    assert_eq!(statistics.num_dropped_queries_last_frame, 2);
+
+    // Scopes past the budget are still reported, just without timings.
+    queue.submit([encoder.finish()]);
+    device.poll(wgpu::MaintainBase::Wait);
+    let results = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(results.len(), 3);
+    assert!(results[0].time.is_some());
+    assert!(results[1].time.is_none());
+    assert!(results[2].time.is_none());
+}
+
+#[test]
+fn max_queries_per_frame_exceeded() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings {
+        max_queries_per_frame: Some(2),
+        ..Default::default()
+    })
+    .unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    for i in 0..3_u32 {
+        drop(profiler.scope(i, &mut encoder, &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    assert_eq!(profiler.end_frame(), Ok(()));
+
+    let statistics = profiler.statistics();
+    assert_eq!(statistics.num_dropped_queries_last_frame, 2);
+    assert_eq!(statistics.num_failed_query_pool_creations, 0);
+
+    queue.submit([encoder.finish()]);
+    device.poll(wgpu::MaintainBase::Wait);
+    let results = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(results.len(), 3);
+    assert!(results[0].time.is_some());
+    assert!(results[1].time.is_none());
+    assert!(results[2].time.is_none());
+
+    // The limit applies to every frame on its own.
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("scope", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    assert_eq!(profiler.end_frame(), Ok(()));
+    assert_eq!(profiler.statistics().num_dropped_queries_last_frame, 0);
+}
+
 #[test]
 fn change_settings_while_query_open() {
     let (_, device, _queue) = create_device(
@@ -93,3 +284,20 @@ fn change_settings_while_query_open() {
 
     profiler.end_query(&mut encoder, query);
 }
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "GpuProfiler used with a different wgpu::Device than before")]
+fn different_device() {
+    // Devices can only be told apart by their features & limits.
+    let (_, device0, _queue0) = create_device(wgpu::Features::empty()).unwrap();
+    let (_, device1, _queue1) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = wgpu_profiler::GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder0 = device0.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("device0", &mut encoder0, &device0));
+
+    let mut encoder1 = device1.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("device1", &mut encoder1, &device1));
+}
@@ -0,0 +1,86 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
+
+use crate::src::{expected_scope, validate_results, Requires};
+
+use super::create_device;
+
+#[test]
+fn flat_results_match_nested_results() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("s0", &mut encoder, &device);
+        {
+            let mut scope = scope.scope("s0_s0", &device);
+            drop(scope.scope("s0_s0_s0", &device));
+        }
+        drop(scope.scoped_compute_pass("s0_c1", &device));
+    }
+    drop(profiler.scope("s1", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let results = profiler
+        .process_finished_frame_flat(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+
+    // Breadth-first order.
+    let labels = results
+        .nodes()
+        .iter()
+        .map(|node| node.label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["s0", "s1", "s0_s0", "s0_c1", "s0_s0_s0"]);
+    assert_eq!(results.roots().len(), 2);
+    assert_eq!(results.nested_queries(&results.roots()[0]).len(), 2);
+    assert!(results.nested_queries(&results.roots()[1]).is_empty());
+
+    let edges = results
+        .edges()
+        .iter()
+        .map(|edge| (edge.parent, edge.child, edge.label))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        edges,
+        [
+            (None, 0, "s0"),
+            (None, 1, "s1"),
+            (Some(0), 2, "s0_s0"),
+            (Some(0), 3, "s0_c1"),
+            (Some(2), 4, "s0_s0_s0"),
+        ]
+    );
+
+    validate_results(
+        device.features(),
+        &results.to_nested(),
+        &[
+            expected_scope(
+                "s0",
+                Requires::TimestampsInEncoders,
+                [
+                    expected_scope(
+                        "s0_s0",
+                        Requires::TimestampsInEncoders,
+                        [expected_scope(
+                            "s0_s0_s0",
+                            Requires::TimestampsInEncoders,
+                            [],
+                        )],
+                    ),
+                    expected_scope("s0_c1", Requires::Timestamps, []),
+                ],
+            ),
+            expected_scope("s1", Requires::TimestampsInEncoders, []),
+        ],
+    );
+}
@@ -0,0 +1,35 @@
+use crate::src::synthetic_result;
+
+#[test]
+fn one_line_per_leaf_path() {
+    let results = vec![
+        synthetic_result(
+            "frame",
+            Some(0.0..0.01),
+            vec![
+                synthetic_result(
+                    "shadows",
+                    Some(0.0..0.004),
+                    vec![
+                        synthetic_result("cascade;0", Some(0.0..0.001), vec![]),
+                        synthetic_result("cascade;1", Some(0.001..0.0025), vec![]),
+                    ],
+                ),
+                synthetic_result("no timing", None, vec![]),
+                synthetic_result("main", Some(0.004..0.0095), vec![]),
+            ],
+        ),
+        synthetic_result("ui", Some(0.01..0.0100004), vec![]),
+    ];
+
+    let mut output = Vec::new();
+    wgpu_profiler::folded_stacks::write_folded_stacks(&mut output, &results).unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "frame;shadows;cascade_0 1000\n\
+         frame;shadows;cascade_1 1500\n\
+         frame;main 5500\n\
+         ui 0\n"
+    );
+}
@@ -0,0 +1,70 @@
+use wgpu_profiler::{EndFrameError, GpuProfiler, GpuProfilerSettings};
+
+use super::create_device;
+
+#[test]
+fn frame_guard_lifecycle() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let mut frame = profiler.begin_frame();
+    {
+        let mut scope = frame.scope("frame", &mut encoder, &device);
+        drop(scope.scoped_compute_pass("pass", &device));
+    }
+    assert!(!frame.is_resolved());
+    frame.resolve(&mut encoder);
+    assert!(frame.is_resolved());
+    queue.submit([encoder.finish()]);
+    frame.end().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let results = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].nested_queries[0].label, "pass");
+}
+
+#[test]
+fn frame_guard_reports_missing_resolve() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let frame = profiler.begin_frame();
+    drop(frame.scope("unresolved", &mut encoder, &device));
+    queue.submit([encoder.finish()]);
+    assert!(matches!(
+        frame.end(),
+        Err(EndFrameError::UnresolvedQueries(1))
+    ));
+}
+
+#[test]
+fn frame_guard_ends_frame_on_drop() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut frame = profiler.begin_frame();
+        drop(frame.scope("frame", &mut encoder, &device));
+        frame.resolve(&mut encoder);
+        queue.submit([encoder.finish()]);
+    }
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let results = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(results[0].label, "frame");
+}
@@ -0,0 +1,60 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
+
+use super::create_device;
+
+#[test]
+fn resolve_into_buffer_without_cpu_readback() {
+    let Ok((_, device, queue)) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    ) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY_INSIDE_ENCODERS");
+        return;
+    };
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        cpu_readback: false,
+        ..Default::default()
+    })
+    .unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let query = profiler.begin_query("scope", &mut encoder, &device);
+    let timestamp_indices = query.frame_timestamp_indices().unwrap();
+    profiler.end_query(&mut encoder, query);
+
+    let buffer_size = (profiler.num_frame_timestamps() * wgpu::QUERY_SIZE) as u64;
+    let destination = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: buffer_size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: buffer_size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    profiler.resolve_queries_into_buffer(&mut encoder, &destination);
+    encoder.copy_buffer_to_buffer(&destination, 0, &staging, 0, buffer_size);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    staging.slice(..).map_async(wgpu::MapMode::Read, |_| ());
+    device.poll(wgpu::Maintain::Wait);
+
+    // Without CPU readback, the profiler itself never produces results.
+    assert!(profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .is_none());
+
+    let timestamps = staging
+        .slice(..)
+        .get_mapped_range()
+        .chunks_exact(wgpu::QUERY_SIZE as usize)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    let start = timestamps[timestamp_indices.start as usize];
+    let end = timestamps[timestamp_indices.start as usize + 1];
+    assert!(start <= end, "start: {start}, end: {end}");
+}
@@ -8,7 +8,7 @@ use super::create_device;
 fn interleaved_scopes() {
     let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
 
-    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
 
     let mut encoder0 = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
     let mut encoder1 = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
@@ -31,7 +31,8 @@ fn interleaved_scopes() {
     // Single frame should now be available.
     let frame = profiler
         .process_finished_frame(queue.get_timestamp_period())
-        .unwrap();
+        .unwrap()
+        .results;
 
     // Print entire tree. Useful for debugging the test if it fails!
     println!("{:#?}", frame);
@@ -66,7 +67,7 @@ fn interleaved_scopes() {
 fn multithreaded_scopes() {
     let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
 
-    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
 
     const NUM_SCOPES_PER_THREAD: usize = 1000;
 
@@ -107,7 +108,8 @@ fn multithreaded_scopes() {
     // Single frame should now be available.
     let frame = profiler
         .process_finished_frame(queue.get_timestamp_period())
-        .unwrap();
+        .unwrap()
+        .results;
 
     // Print entire tree. Useful for debugging the test if it fails!
     println!("{:#?}", frame);
@@ -126,3 +128,206 @@ fn multithreaded_scopes() {
             .collect::<Vec<_>>(),
     );
 }
+
+#[test]
+fn multithreaded_nested_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    const NUM_SCOPES_PER_THREAD: usize = 100;
+
+    let mut encoder0 = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let parent = profiler.begin_query("e0_s0", &mut encoder0, &device);
+
+    // Children are opened on different threads & encoders, all referring to the same parent.
+    let barrier = std::sync::Barrier::new(2);
+    let (command_buffer1, command_buffer2) = std::thread::scope(|thread_scope| {
+        let spawn_child_thread = |encoder_index: usize| {
+            let (profiler, device, barrier, parent) = (&profiler, &device, &barrier, &parent);
+            thread_scope.spawn(move || {
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                barrier.wait();
+
+                for i in 0..NUM_SCOPES_PER_THREAD {
+                    let query = profiler
+                        .begin_query(format!("e{encoder_index}_s{i}"), &mut encoder, device)
+                        .with_parent(Some(parent));
+                    let nested_query = profiler
+                        .begin_query(format!("e{encoder_index}_s{i}_s0"), &mut encoder, device)
+                        .with_parent(Some(&query));
+                    profiler.end_query(&mut encoder, nested_query);
+                    profiler.end_query(&mut encoder, query);
+                }
+                encoder.finish()
+            })
+        };
+        let join_handle1 = spawn_child_thread(1);
+        let join_handle2 = spawn_child_thread(2);
+
+        (join_handle1.join().unwrap(), join_handle2.join().unwrap())
+    });
+
+    profiler.end_query(&mut encoder0, parent);
+    profiler.resolve_queries(&mut encoder0);
+    queue.submit([command_buffer1, command_buffer2, encoder0.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    // Single frame should now be available.
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+
+    // Print entire tree. Useful for debugging the test if it fails!
+    println!("{:#?}", frame);
+
+    // All children end up below the shared parent, albeit in arbitrary order.
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "e0_s0");
+    assert_eq!(
+        frame[0].time.is_some(),
+        Requires::TimestampsInEncoders.expect_time_result(device.features())
+    );
+    validate_results_unordered(
+        device.features(),
+        &frame[0].nested_queries,
+        &[1, 2]
+            .into_iter()
+            .flat_map(|encoder_index| {
+                (0..NUM_SCOPES_PER_THREAD).map(move |i| {
+                    expected_scope(
+                        format!("e{encoder_index}_s{i}"),
+                        Requires::TimestampsInEncoders,
+                        [expected_scope(
+                            format!("e{encoder_index}_s{i}_s0"),
+                            Requires::TimestampsInEncoders,
+                            [],
+                        )],
+                    )
+                })
+            })
+            .collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn multithreaded_scopes_sorted_by_time() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        sort_results_by_time: true,
+        ..Default::default()
+    })
+    .unwrap();
+
+    const NUM_SCOPES_PER_THREAD: usize = 100;
+
+    let (command_buffer0, command_buffer1) = std::thread::scope(|thread_scope| {
+        let spawn_thread = |encoder_index: usize| {
+            let (profiler, device) = (&profiler, &device);
+            thread_scope.spawn(move || {
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                for i in 0..NUM_SCOPES_PER_THREAD {
+                    let mut scope =
+                        profiler.scope(format!("e{encoder_index}_s{i}"), &mut encoder, device);
+                    drop(scope.scope(format!("e{encoder_index}_s{i}_s0"), device));
+                    drop(scope.scope(format!("e{encoder_index}_s{i}_s1"), device));
+                }
+                encoder.finish()
+            })
+        };
+        let join_handle0 = spawn_thread(0);
+        let join_handle1 = spawn_thread(1);
+
+        (join_handle0.join().unwrap(), join_handle1.join().unwrap())
+    });
+
+    let mut resolve_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    profiler.resolve_queries(&mut resolve_encoder);
+    queue.submit([command_buffer1, command_buffer0, resolve_encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+
+    fn assert_sorted_by_time(results: &[wgpu_profiler::GpuTimerQueryResult]) {
+        let start_times = results
+            .iter()
+            .filter_map(|result| result.time.as_ref().map(|time| time.start))
+            .collect::<Vec<_>>();
+        assert!(
+            start_times.windows(2).all(|pair| pair[0] <= pair[1]),
+            "results not sorted: {results:?}"
+        );
+        for result in results {
+            assert_sorted_by_time(&result.nested_queries);
+        }
+    }
+    assert_eq!(frame.len(), NUM_SCOPES_PER_THREAD * 2);
+    assert_sorted_by_time(&frame);
+}
+
+#[test]
+fn shared_profiler_frame_handling() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+    let device = std::sync::Arc::new(device);
+
+    // Frames are recorded on a worker thread, but resolved, ended & processed on the main thread
+    // without ever needing exclusive access to the profiler.
+    let profiler = std::sync::Arc::new(GpuProfiler::new(GpuProfilerSettings::default()).unwrap());
+
+    for frame_index in 0..2 {
+        let command_buffer = {
+            let (profiler, device) = (profiler.clone(), device.clone());
+            std::thread::spawn(move || {
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                let mut scope = profiler.scope(format!("f{frame_index}"), &mut encoder, &device);
+                drop(scope.scope(format!("f{frame_index}_s0"), &device));
+                drop(scope);
+                encoder.finish()
+            })
+            .join()
+            .unwrap()
+        };
+
+        let mut resolve_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        profiler.resolve_queries(&mut resolve_encoder);
+        queue.submit([command_buffer, resolve_encoder.finish()]);
+        profiler.end_frame().unwrap();
+
+        device.poll(wgpu::Maintain::Wait);
+
+        let frame = profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .unwrap()
+            .results;
+        validate_results(
+            device.features(),
+            &frame,
+            &[expected_scope(
+                format!("f{frame_index}"),
+                Requires::TimestampsInEncoders,
+                [expected_scope(
+                    format!("f{frame_index}_s0"),
+                    Requires::TimestampsInEncoders,
+                    [],
+                )],
+            )],
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
+
+use crate::src::Requires;
+
+use super::create_device;
+
+fn record_frame(profiler: &GpuProfiler, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("s0", &mut encoder, device);
+        drop(scope.scope("s0_s0", device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+    device.poll(wgpu::Maintain::Wait);
+}
+
+#[test]
+fn scopes_below_min_report_duration_are_dropped() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    // No scope can be this long, so every scope with timing information is dropped.
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        min_report_duration: Some(f64::INFINITY),
+        ..Default::default()
+    })
+    .unwrap();
+
+    // Scopes without timing information are always reported.
+    let expected_num_roots = if Requires::TimestampsInEncoders.expect_time_result(device.features())
+    {
+        0
+    } else {
+        1
+    };
+
+    record_frame(&profiler, &device, &queue);
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame.len(), expected_num_roots);
+
+    record_frame(&profiler, &device, &queue);
+    let frame = profiler
+        .process_finished_frame_flat(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame.roots().len(), expected_num_roots);
+    assert_eq!(frame.len(), expected_num_roots * 2);
+}
+
+#[test]
+fn zero_min_report_duration_keeps_all_scopes() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        min_report_duration: Some(0.0),
+        ..Default::default()
+    })
+    .unwrap();
+
+    record_frame(&profiler, &device, &queue);
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].nested_queries.len(), 1);
+}
@@ -1,8 +1,28 @@
+mod accumulating_scopes;
+mod chrometrace;
+mod copy_timing;
+mod debug_group_threshold;
 mod dropped_frame_handling;
 mod errors;
+mod flat_results;
+mod folded_stacks;
+mod frame_guard;
+mod gpu_readback;
 mod interleaved_command_buffer;
+mod min_report_duration;
 mod multiple_resolves_per_frame;
 mod nested_scopes;
+mod numeric_labels;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+#[cfg(feature = "pprof")]
+mod pprof;
+mod reserved_scopes;
+mod result_backend;
+mod result_helpers;
+mod submission_order;
+#[cfg(feature = "tracing")]
+mod tracing_spans;
 
 pub fn create_device(
     features: wgpu::Features,
@@ -33,6 +53,27 @@ pub fn create_device(
     futures_lite::future::block_on(create_default_device_async(features))
 }
 
+/// Creates a result by hand, for testing result processing without a device.
+fn synthetic_result(
+    label: &str,
+    time: Option<std::ops::Range<f64>>,
+    nested_queries: Vec<wgpu_profiler::GpuTimerQueryResult>,
+) -> wgpu_profiler::GpuTimerQueryResult {
+    wgpu_profiler::GpuTimerQueryResult {
+        label: label.to_owned(),
+        pid: 0,
+        tid: std::thread::current().id(),
+        backend: None,
+        clock_calibration: None,
+        time,
+        unit_count: None,
+        category: None,
+        num_samples: None,
+        extra_metrics: Default::default(),
+        nested_queries,
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum Requires {
     Disabled,
@@ -10,7 +10,7 @@ fn multiple_resolves_per_frame() {
     )
     .unwrap();
 
-    let mut profiler =
+    let profiler =
         wgpu_profiler::GpuProfiler::new(wgpu_profiler::GpuProfilerSettings::default()).unwrap();
 
     {
@@ -23,22 +23,49 @@ fn multiple_resolves_per_frame() {
             {
                 let _ = profiler.scope(format!("{i}"), &mut encoder, &device);
             }
-            profiler.resolve_queries(&mut encoder);
+            let resolved_ranges = profiler.resolve_queries(&mut encoder);
+
+            // Only the queries of the new scope are resolved & copied.
+            if device
+                .features()
+                .contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS)
+            {
+                assert_eq!(resolved_ranges.len(), 1);
+                let scope_size = (wgpu::QUERY_SIZE * 2) as u64;
+                let resolved_range = &resolved_ranges[0];
+                assert_eq!(resolved_range.resolve_byte_range, 0..scope_size);
+                let read_byte_range = resolved_range.read_byte_range.clone().unwrap();
+                assert_eq!(read_byte_range.end - read_byte_range.start, scope_size);
+                assert_eq!(resolved_range.destination_byte_range, None);
+            } else {
+                assert!(resolved_ranges.is_empty());
+            }
         }
 
         // And an extra resolve for good measure (this should be a no-op).
-        profiler.resolve_queries(&mut encoder);
+        assert!(profiler.resolve_queries(&mut encoder).is_empty());
 
         profiler.end_frame().unwrap();
     }
 
+    let expected_peak_queries = if device
+        .features()
+        .contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS)
+    {
+        NUM_SCOPES as u32 * 2
+    } else {
+        0
+    };
+    assert_eq!(profiler.peak_queries_per_frame(), expected_peak_queries);
+
     // Poll to explicitly trigger mapping callbacks.
     device.poll(wgpu::Maintain::Wait);
 
     // Frame should now be available and contain all the scopes.
     let scopes = profiler
         .process_finished_frame(queue.get_timestamp_period())
-        .unwrap();
+        .unwrap()
+        .results;
     assert_eq!(scopes.len(), NUM_SCOPES);
     for (i, scope) in scopes.iter().enumerate() {
         assert_eq!(scope.label, format!("{i}"));
@@ -65,7 +65,8 @@ fn nested_scopes(device: &wgpu::Device, queue: &wgpu::Queue) {
     // Single frame should now be available.
     let frame = profiler
         .process_finished_frame(queue.get_timestamp_period())
-        .unwrap();
+        .unwrap()
+        .results;
 
     // Print entire tree. Useful for debugging the test if it fails!
     println!("{:#?}", frame);
@@ -185,3 +186,556 @@ fn nested_scopes_no_features() {
     let (_, device, queue) = create_device(wgpu::Features::empty()).unwrap();
     nested_scopes(&device, &queue);
 }
+
+#[test]
+fn nested_scopes_inferred_from_thread() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        infer_parent_from_thread: true,
+        ..Default::default()
+    })
+    .unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let outer = profiler.begin_query("e0_s0", &mut encoder, &device);
+        {
+            // Manually created pass, no `with_parent` needed.
+            let pass_query = profiler.begin_pass_query("e0_s0_c0", &mut encoder, &device);
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: pass_query.compute_pass_timestamp_writes(),
+            });
+            let inner = profiler.begin_query("e0_s0_c0_s0", &mut pass, &device);
+            profiler.end_query(&mut pass, inner);
+            // Explicit parent overrides the inferred one.
+            let root = profiler
+                .begin_query("e0_s1", &mut pass, &device)
+                .with_parent(None);
+            profiler.end_query(&mut pass, root);
+            drop(pass);
+            profiler.end_query(&mut encoder, pass_query);
+        }
+        profiler.end_query(&mut encoder, outer);
+    }
+    drop(profiler.scope("e0_s2", &mut encoder, &device));
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+
+    // Print entire tree. Useful for debugging the test if it fails!
+    println!("{:#?}", frame);
+
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope("e0_s1", Requires::TimestampsInPasses, []),
+            expected_scope(
+                "e0_s0",
+                Requires::TimestampsInEncoders,
+                [expected_scope(
+                    "e0_s0_c0",
+                    Requires::Timestamps,
+                    [expected_scope(
+                        "e0_s0_c0_s0",
+                        Requires::TimestampsInPasses,
+                        [],
+                    )],
+                )],
+            ),
+            expected_scope("e0_s2", Requires::TimestampsInEncoders, []),
+        ],
+    );
+}
+
+#[test]
+fn scoped_render_bundles() {
+    let Ok((_, device, queue)) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES,
+    ) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY_INSIDE_PASSES");
+        return;
+    };
+
+    let format = wgpu::TextureFormat::Rgba8Unorm;
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let bundle = device
+        .create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+            label: None,
+            color_formats: &[Some(format)],
+            depth_stencil: None,
+            sample_count: 1,
+            multiview: None,
+        })
+        .finish(&wgpu::RenderBundleDescriptor::default());
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        let mut render_pass = scope.scoped_render_pass(
+            "e0_s0_r0",
+            &device,
+            wgpu::RenderPassDescriptor {
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations::default(),
+                })],
+                ..Default::default()
+            },
+        );
+        render_pass.scope_bundles("e0_s0_r0_b0", [&bundle], &device);
+    }
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::TimestampsInEncoders,
+            [expected_scope(
+                "e0_s0_r0",
+                Requires::Timestamps,
+                [expected_scope(
+                    "e0_s0_r0_b0",
+                    Requires::TimestampsInPasses,
+                    [],
+                )],
+            )],
+        )],
+    );
+}
+
+#[cfg(feature = "profiling")]
+#[test]
+fn profiling_scopes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        wgpu_profiler::profiling_scope!(scope = profiler, "e0_s0", &mut encoder, &device);
+        drop(scope.scoped_compute_pass("e0_s0_c0", &device));
+    }
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::TimestampsInEncoders,
+            [expected_scope("e0_s0_c0", Requires::Timestamps, [])],
+        )],
+    );
+}
+
+#[test]
+fn raw_queries() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+
+        // Timestamps written by hand, e.g. around commands recorded by a third-party library.
+        let raw_query = profiler
+            .begin_raw_query("e0_s0_raw", &mut scope, &device)
+            .with_parent(scope.scope.as_ref());
+        if let Some(writes) = raw_query.raw_timestamp_writes() {
+            scope.write_timestamp(writes.query_set, writes.start_index);
+            scope.write_timestamp(writes.query_set, writes.end_index);
+        }
+        profiler.end_raw_query(raw_query);
+    }
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::TimestampsInEncoders,
+            [expected_scope(
+                "e0_s0_raw",
+                Requires::TimestampsInEncoders,
+                [],
+            )],
+        )],
+    );
+}
+
+#[test]
+fn disabled_channels() {
+    const DETAILED_CHANNEL: u32 = 1 << 1;
+
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        enabled_channels: GpuProfiler::DEFAULT_CHANNEL,
+        ..Default::default()
+    })
+    .unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        drop(scope.scope_in_channel("e0_s0_s0", DETAILED_CHANNEL, &device));
+        drop(scope.scope_in_channel(
+            "e0_s0_s1",
+            DETAILED_CHANNEL | GpuProfiler::DEFAULT_CHANNEL,
+            &device,
+        ));
+    }
+    drop(profiler.scope_in_channel("e0_s1", DETAILED_CHANNEL, &mut encoder, &device));
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope(
+                "e0_s0",
+                Requires::TimestampsInEncoders,
+                [
+                    expected_scope("e0_s0_s0", Requires::Disabled, []),
+                    expected_scope("e0_s0_s1", Requires::TimestampsInEncoders, []),
+                ],
+            ),
+            expected_scope("e0_s1", Requires::Disabled, []),
+        ],
+    );
+}
+
+#[test]
+fn cancelled_pass_query() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+
+        // Pass that turns out to be skipped after its query was opened.
+        let skipped_pass_query = profiler
+            .begin_pass_query("e0_s0_skipped", &mut scope, &device)
+            .with_parent(scope.scope.as_ref());
+        // Queries nested in the cancelled query are excluded as well.
+        let skipped_child_query = profiler
+            .begin_query("e0_s0_skipped_child", &mut *scope, &device)
+            .with_parent(Some(&skipped_pass_query));
+        profiler.end_query(&mut *scope, skipped_child_query);
+        profiler.cancel_pass_query(skipped_pass_query);
+
+        drop(scope.scoped_compute_pass("e0_s0_c0", &device));
+    }
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::TimestampsInEncoders,
+            [expected_scope("e0_s0_c0", Requires::Timestamps, [])],
+        )],
+    );
+}
+
+#[test]
+fn scope_batch() {
+    let (_, device, queue) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("e0_s0", &mut encoder, &device);
+        let mut batch = scope.batch(&device);
+        for i in 0..3 {
+            let label = format!("e0_s0_s{i}");
+            let nested = batch.measure(label, |scope| {
+                drop(scope.scope("nested", &device));
+                i
+            });
+            assert_eq!(nested, i);
+        }
+    }
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    let nested = || [expected_scope("nested", Requires::TimestampsInEncoders, [])];
+    validate_results(
+        device.features(),
+        &frame,
+        &[expected_scope(
+            "e0_s0",
+            Requires::TimestampsInEncoders,
+            [
+                expected_scope("e0_s0_s0", Requires::TimestampsInEncoders, nested()),
+                expected_scope("e0_s0_s1", Requires::TimestampsInEncoders, nested()),
+                expected_scope("e0_s0_s2", Requires::TimestampsInEncoders, nested()),
+            ],
+        )],
+    );
+}
+
+#[test]
+fn timer_queries_disabled_mid_frame() {
+    let Ok((_, device, queue)) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+    ) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY_INSIDE_ENCODERS");
+        return;
+    };
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+    // Boundary case: a query that is still open while timer queries get disabled.
+    let open_across_change = profiler.begin_query("open across change", &mut encoder, &device);
+    drop(profiler.scope("before", &mut encoder, &device));
+    profiler
+        .change_settings(GpuProfilerSettings {
+            enable_timer_queries: false,
+            ..Default::default()
+        })
+        .unwrap();
+    drop(profiler.scope("after", &mut encoder, &device));
+    profiler.end_query(&mut encoder, open_across_change);
+
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope("open across change", Requires::TimestampsInEncoders, []),
+            expected_scope("before", Requires::TimestampsInEncoders, []),
+            expected_scope("after", Requires::Disabled, []),
+        ],
+    );
+    assert_eq!(profiler.statistics().num_invalid_timestamps, 0);
+}
+
+#[test]
+fn encoder_tags() {
+    let Ok((_, device, queue)) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+    ) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY_INSIDE_ENCODERS");
+        return;
+    };
+
+    const SUBSYSTEM_TAG: u32 = 7;
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    profiler.set_active_encoder_tag(Some(SUBSYSTEM_TAG));
+
+    let mut tagged_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let mut other_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope =
+            profiler.tagged_scope(SUBSYSTEM_TAG, "tagged", &mut tagged_encoder, &device);
+        drop(scope.scoped_compute_pass("tagged pass", &device));
+        drop(scope.scope("tagged nested", &device));
+    }
+    {
+        let mut scope = profiler.scope("untagged", &mut other_encoder, &device);
+        drop(scope.scope("untagged nested", &device));
+    }
+    drop(profiler.tagged_scope(1, "other tag", &mut other_encoder, &device));
+
+    profiler.resolve_queries(&mut other_encoder);
+    queue.submit([tagged_encoder.finish(), other_encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    validate_results(
+        device.features(),
+        &frame,
+        &[
+            expected_scope(
+                "tagged",
+                Requires::TimestampsInEncoders,
+                [
+                    expected_scope("tagged pass", Requires::Timestamps, []),
+                    expected_scope("tagged nested", Requires::TimestampsInEncoders, []),
+                ],
+            ),
+            expected_scope(
+                "untagged",
+                Requires::Disabled,
+                [expected_scope("untagged nested", Requires::Disabled, [])],
+            ),
+            expected_scope("other tag", Requires::Disabled, []),
+        ],
+    );
+}
+
+#[test]
+fn open_scope_depth() {
+    let (_, device, _queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    assert_eq!(profiler.open_scope_depth(), 0);
+    {
+        let mut scope = profiler.scope("s0", &mut encoder, &device);
+        assert_eq!(profiler.open_scope_depth(), 1);
+        {
+            let _nested = scope.scoped_compute_pass("s0_c0", &device);
+            assert_eq!(profiler.open_scope_depth(), 2);
+        }
+        assert_eq!(profiler.open_scope_depth(), 1);
+    }
+    assert_eq!(profiler.open_scope_depth(), 0);
+}
+
+#[test]
+fn very_deep_nesting_is_capped() {
+    let (_, device, queue) = create_device(wgpu::Features::empty()).unwrap();
+    let profiler = GpuProfiler::new(GpuProfilerSettings {
+        infer_parent_from_thread: true,
+        enable_debug_groups: false,
+        max_scope_depth: 100,
+        ..Default::default()
+    })
+    .unwrap();
+
+    for _ in 0..2 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let queries = (0..10_000)
+            .map(|_| profiler.begin_query("deep", &mut encoder, &device))
+            .collect::<Vec<_>>();
+        for query in queries.into_iter().rev() {
+            profiler.end_query(&mut encoder, query);
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+    }
+    device.poll(wgpu::Maintain::Wait);
+
+    let results = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    let mut max_depth = 0;
+    wgpu_profiler::GpuTimerQueryResult::walk_all(&results, &mut |_, depth, _| {
+        max_depth = max_depth.max(depth);
+    });
+    assert_eq!(results.len(), 1);
+    assert_eq!(max_depth, 99);
+
+    let flat_results = profiler
+        .process_finished_frame_flat(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(flat_results.len(), 100);
+}
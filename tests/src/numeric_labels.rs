@@ -0,0 +1,175 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings, LabelTransform};
+
+use super::create_device;
+
+const SCOPE_NAMES: [&str; 2] = ["shadows", "lighting"];
+
+#[test]
+fn numeric_labels_are_resolved() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    for enable_debug_groups in [false, true] {
+        let mut profiler = GpuProfiler::new(GpuProfilerSettings {
+            enable_debug_groups,
+            ..Default::default()
+        })
+        .unwrap();
+        profiler.set_label_resolver(|id| SCOPE_NAMES[id as usize].to_owned());
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut scope = profiler.scope(0u32, &mut encoder, &device);
+            drop(scope.scoped_compute_pass(1u32, &device));
+            drop(scope.scope("string label", &device));
+        }
+        profiler.resolve_queries(&mut encoder);
+        queue.submit([encoder.finish()]);
+        profiler.end_frame().unwrap();
+
+        device.poll(wgpu::Maintain::Wait);
+
+        let frame = profiler
+            .process_finished_frame(queue.get_timestamp_period())
+            .unwrap()
+            .results;
+        assert_eq!(frame.len(), 1);
+        assert_eq!(frame[0].label, "shadows");
+        assert_eq!(frame[0].nested_queries[0].label, "lighting");
+        assert_eq!(frame[0].nested_queries[1].label, "string label");
+    }
+}
+
+#[test]
+fn numeric_labels_without_resolver() {
+    let (_, device, queue) = create_device(wgpu::Features::empty()).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope(42u32, &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame[0].label, "42");
+}
+
+#[test]
+fn label_transform_is_applied() {
+    let (_, device, queue) = create_device(wgpu::Features::empty()).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings {
+        label_transform: Some(LabelTransform::new(|label| {
+            label.split('#').next().unwrap().to_owned()
+        })),
+        ..Default::default()
+    })
+    .unwrap();
+    profiler.set_label_resolver(|id| format!("resolved#{id}"));
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("draw#4823", &mut encoder, &device));
+    drop(profiler.scope(7u32, &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame[0].label, "draw");
+    assert_eq!(frame[1].label, "resolved");
+}
+
+#[test]
+fn label_overridden_at_end() {
+    let (_, device, queue) = create_device(wgpu::Features::empty()).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    profiler.set_label_resolver(|id| format!("config {id}"));
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let query = profiler.begin_query("variant", &mut encoder, &device);
+    profiler.end_query_labeled(&mut encoder, query, "variant A");
+    let query = profiler.begin_query("variant", &mut encoder, &device);
+    profiler.end_query_labeled(&mut encoder, query, 2u32);
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame[0].label, "variant A");
+    assert_eq!(frame[1].label, "config 2");
+}
+
+#[test]
+fn registered_passes() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    profiler.set_label_resolver(|id| format!("resolved {id}"));
+    let passes = profiler.register_passes(["shadow", "gbuffer"]);
+    assert_eq!(passes.len(), 2);
+    assert_eq!(profiler.pass_name(passes[1]), Some("gbuffer"));
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope(passes[0], &mut encoder, &device);
+        drop(scope.scoped_compute_pass(passes[1], &device));
+        drop(scope.scope(7u32, &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame[0].label, "shadow");
+    assert_eq!(frame[0].nested_queries[0].label, "gbuffer");
+    assert_eq!(frame[0].nested_queries[1].label, "resolved 7");
+}
+
+#[test]
+fn registered_passes_dont_collide_with_numeric_labels() {
+    let (_, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let mut profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    profiler.set_label_resolver(|id| format!("resolved {id}"));
+    let passes = profiler.register_passes(["shadow", "gbuffer"]);
+    assert_eq!(passes[0].id(), 0);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope(passes[0], &mut encoder, &device));
+    drop(profiler.scope(0u32, &mut encoder, &device));
+    drop(profiler.scope(1u32, &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame[0].label, "shadow");
+    assert_eq!(frame[1].label, "resolved 0");
+    assert_eq!(frame[2].label, "resolved 1");
+}
@@ -0,0 +1,59 @@
+use std::time::{Duration, SystemTime};
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::{testing::trace::InMemorySpanExporterBuilder, trace::TracerProvider};
+use wgpu_profiler::{ClockCalibration, GpuTimerQueryResult};
+
+use crate::src::synthetic_result;
+
+#[test]
+fn results_are_exported_as_spans_with_gpu_timestamps() {
+    let clock_calibration = ClockCalibration {
+        gpu_time: 1.0,
+        system_time: SystemTime::UNIX_EPOCH + Duration::from_secs(1000),
+    };
+    let calibrated = |result: GpuTimerQueryResult| GpuTimerQueryResult {
+        clock_calibration: Some(clock_calibration),
+        ..result
+    };
+    let results = vec![calibrated(synthetic_result(
+        "a",
+        Some(1.0..2.0),
+        vec![
+            calibrated(synthetic_result(
+                "untimed",
+                None,
+                vec![calibrated(synthetic_result("a0", Some(1.25..1.5), vec![]))],
+            )),
+            // Can't be placed in time.
+            synthetic_result("uncalibrated", Some(1.5..1.75), vec![]),
+        ],
+    ))];
+
+    let exporter = InMemorySpanExporterBuilder::new().build();
+    let provider = TracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    wgpu_profiler::otel::export_otel(&results, &provider.tracer("wgpu-profiler-test"));
+
+    let spans = exporter.get_finished_spans().unwrap();
+    assert_eq!(spans.len(), 2);
+    let a = spans.iter().find(|span| span.name == "a").unwrap();
+    let a0 = spans.iter().find(|span| span.name == "a0").unwrap();
+
+    assert_eq!(a0.parent_span_id, a.span_context.span_id());
+    assert_eq!(a0.span_context.trace_id(), a.span_context.trace_id());
+    assert_eq!(a.start_time, clock_calibration.system_time);
+    assert_eq!(
+        a.end_time,
+        clock_calibration.system_time + Duration::from_secs(1)
+    );
+    assert_eq!(
+        a0.start_time,
+        clock_calibration.system_time + Duration::from_millis(250)
+    );
+    assert_eq!(
+        a0.end_time,
+        clock_calibration.system_time + Duration::from_millis(500)
+    );
+}
@@ -0,0 +1,136 @@
+use wgpu_profiler::GpuTimerQueryAggregator;
+
+use crate::src::synthetic_result;
+
+fn read_varint(bytes: &mut &[u8]) -> u64 {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[0];
+        *bytes = &bytes[1..];
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+/// Minimal protobuf reader, returns `(field, varint or bytes)` for every field of a message.
+fn read_fields(mut bytes: &[u8]) -> Vec<(u64, Result<u64, &[u8]>)> {
+    let mut fields = Vec::new();
+    while !bytes.is_empty() {
+        let key = read_varint(&mut bytes);
+        match key & 7 {
+            0 => fields.push((key >> 3, Ok(read_varint(&mut bytes)))),
+            2 => {
+                let len = read_varint(&mut bytes) as usize;
+                fields.push((key >> 3, Err(&bytes[..len])));
+                bytes = &bytes[len..];
+            }
+            wire_type => panic!("unexpected wire type {wire_type}"),
+        }
+    }
+    fields
+}
+
+fn read_packed(bytes: &[u8]) -> Vec<u64> {
+    let mut bytes = bytes;
+    let mut values = Vec::new();
+    while !bytes.is_empty() {
+        values.push(read_varint(&mut bytes));
+    }
+    values
+}
+
+#[test]
+fn pprof_samples_hold_self_time_per_label_path() {
+    let mut aggregator = GpuTimerQueryAggregator::new();
+    for _ in 0..2 {
+        aggregator.add_frame(&[synthetic_result(
+            "frame",
+            Some(0.0..0.004),
+            vec![
+                synthetic_result("shadows", Some(0.0..0.001), vec![]),
+                synthetic_result("lighting", Some(0.001..0.003), vec![]),
+            ],
+        )]);
+    }
+
+    let path = std::env::temp_dir().join(format!(
+        "wgpu-profiler-pprof-test-{}.pb",
+        std::process::id()
+    ));
+    wgpu_profiler::pprof::write_pprof(&path, &aggregator).unwrap();
+    let profile = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let fields = read_fields(&profile);
+    let strings = fields
+        .iter()
+        .filter(|(field, _)| *field == 6)
+        .map(|(_, value)| std::str::from_utf8(value.unwrap_err()).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(strings[0], "");
+
+    let function_names = fields
+        .iter()
+        .filter(|(field, _)| *field == 5)
+        .map(|(_, function)| {
+            let function = read_fields(function.unwrap_err());
+            let id = function
+                .iter()
+                .find(|(field, _)| *field == 1)
+                .unwrap()
+                .1
+                .unwrap();
+            let name = function
+                .iter()
+                .find(|(field, _)| *field == 2)
+                .unwrap()
+                .1
+                .unwrap();
+            (id, strings[name as usize])
+        })
+        .collect::<std::collections::HashMap<_, _>>();
+
+    let mut samples = fields
+        .iter()
+        .filter(|(field, _)| *field == 2)
+        .map(|(_, sample)| {
+            let sample = read_fields(sample.unwrap_err());
+            let stack = read_packed(
+                sample
+                    .iter()
+                    .find(|(field, _)| *field == 1)
+                    .unwrap()
+                    .1
+                    .unwrap_err(),
+            )
+            .into_iter()
+            .rev()
+            .map(|id| function_names[&id])
+            .collect::<Vec<_>>()
+            .join(";");
+            let values = read_packed(
+                sample
+                    .iter()
+                    .find(|(field, _)| *field == 2)
+                    .unwrap()
+                    .1
+                    .unwrap_err(),
+            );
+            (stack, values)
+        })
+        .collect::<Vec<_>>();
+    samples.sort();
+
+    assert_eq!(
+        samples,
+        vec![
+            ("frame".to_owned(), vec![2_000_000, 2]),
+            ("frame;lighting".to_owned(), vec![4_000_000, 2]),
+            ("frame;shadows".to_owned(), vec![2_000_000, 2]),
+        ]
+    );
+}
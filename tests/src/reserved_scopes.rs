@@ -0,0 +1,91 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
+
+use crate::src::{expected_scope, validate_results, Requires};
+
+use super::create_device;
+
+fn reserved_scopes(device: &wgpu::Device, queue: &wgpu::Queue) {
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    let has_timestamps = device.features().contains(wgpu::Features::TIMESTAMP_QUERY);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let mut reserved = profiler.reserve_scopes(2, device);
+    assert_eq!(reserved.remaining(), if has_timestamps { 2 } else { 0 });
+    {
+        let query = profiler.begin_reserved_query(&mut reserved, "a", &mut encoder, device);
+        profiler.end_query(&mut encoder, query);
+        let query = profiler.begin_reserved_query(&mut reserved, "b", &mut encoder, device);
+        profiler.end_query(&mut encoder, query);
+        // Exceeds the reservation, falls back to reserving queries individually.
+        let query = profiler.begin_reserved_query(&mut reserved, "c", &mut encoder, device);
+        profiler.end_query(&mut encoder, query);
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    // Leftover reservations of previous frames are never used.
+    let mut stale_reserved = profiler.reserve_scopes(1, device);
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let query = profiler.begin_reserved_query(&mut stale_reserved, "d", &mut encoder, device);
+        profiler.end_query(&mut encoder, query);
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let mut frames = Vec::new();
+    while let Some(frame) = profiler.process_finished_frame(queue.get_timestamp_period()) {
+        frames.push(frame.results);
+    }
+    assert_eq!(frames.len(), 3);
+
+    validate_results(
+        device.features(),
+        &frames[0],
+        &[
+            expected_scope("a", Requires::TimestampsInEncoders, []),
+            expected_scope("b", Requires::TimestampsInEncoders, []),
+            expected_scope("c", Requires::TimestampsInEncoders, []),
+        ],
+    );
+    validate_results(device.features(), &frames[1], &[]);
+    validate_results(
+        device.features(),
+        &frames[2],
+        &[expected_scope("d", Requires::TimestampsInEncoders, [])],
+    );
+}
+
+#[test]
+fn reserved_scopes_timestamp_in_encoders_enabled() {
+    let Ok((_, device, queue)) = create_device(
+        wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+    ) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY_INSIDE_ENCODERS");
+        return;
+    };
+    reserved_scopes(&device, &queue);
+}
+
+#[test]
+fn reserved_scopes_timestamp_enabled() {
+    let Ok((_, device, queue)) = create_device(wgpu::Features::TIMESTAMP_QUERY) else {
+        println!("Skipping test because device doesn't support TIMESTAMP_QUERY");
+        return;
+    };
+    reserved_scopes(&device, &queue);
+}
+
+#[test]
+fn reserved_scopes_disabled() {
+    let (_, device, queue) = create_device(wgpu::Features::empty()).unwrap();
+    reserved_scopes(&device, &queue);
+}
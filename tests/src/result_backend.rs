@@ -0,0 +1,44 @@
+use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
+
+use super::create_device;
+
+#[test]
+fn results_carry_backend() {
+    let (backend, device, queue) = create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default())
+        .unwrap()
+        .with_backend(backend);
+    assert_eq!(profiler.backend(), Some(backend));
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut scope = profiler.scope("outer", &mut encoder, &device);
+        drop(scope.scoped_compute_pass("inner", &device));
+    }
+    profiler.resolve_queries(&mut encoder);
+    queue.submit([encoder.finish()]);
+    profiler.end_frame().unwrap();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    let frame = profiler
+        .process_finished_frame(queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].backend, Some(backend));
+    assert_eq!(frame[0].nested_queries[0].backend, Some(backend));
+}
+
+#[test]
+fn timestamp_resolution_hint() {
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+    assert_eq!(profiler.timestamp_resolution_hint(), None);
+
+    let profiler = profiler.with_backend(wgpu::Backend::Vulkan);
+    assert_eq!(profiler.timestamp_resolution_hint(), None);
+
+    let profiler = profiler.with_backend(wgpu::Backend::BrowserWebGpu);
+    assert!(profiler.timestamp_resolution_hint().unwrap() >= 100.0 / 1000.0 / 1000.0);
+}
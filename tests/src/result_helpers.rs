@@ -0,0 +1,389 @@
+use wgpu_profiler::GpuTimerQueryResult;
+
+use crate::src::synthetic_result;
+
+fn result(label: &str, nested_queries: Vec<GpuTimerQueryResult>) -> GpuTimerQueryResult {
+    synthetic_result(label, None, nested_queries)
+}
+
+#[test]
+fn walk_reports_depth_and_sibling_index() {
+    let results = vec![
+        result(
+            "a",
+            vec![
+                result("a0", vec![]),
+                result("a1", vec![result("a1_0", vec![])]),
+            ],
+        ),
+        result("b", vec![]),
+    ];
+
+    let mut visited = Vec::new();
+    GpuTimerQueryResult::walk_all(&results, &mut |result, depth, sibling_index| {
+        visited.push((result.label.clone(), depth, sibling_index));
+    });
+    assert_eq!(
+        visited,
+        [
+            ("a".to_owned(), 0, 0),
+            ("a0".to_owned(), 1, 0),
+            ("a1".to_owned(), 1, 1),
+            ("a1_0".to_owned(), 2, 0),
+            ("b".to_owned(), 0, 1),
+        ]
+    );
+
+    let mut visited = Vec::new();
+    results[0].nested_queries[1].walk(&mut |result, depth, sibling_index| {
+        visited.push((result.label.clone(), depth, sibling_index));
+    });
+    assert_eq!(
+        visited,
+        [("a1".to_owned(), 0, 0), ("a1_0".to_owned(), 1, 0)]
+    );
+}
+
+#[test]
+fn self_time_subtracts_union_of_nested_scopes() {
+    let result = synthetic_result(
+        "parent",
+        Some(0.0..10.0),
+        vec![
+            // Overlapping nested scopes, covering 1..4
+            synthetic_result("a", Some(1.0..3.0), vec![]),
+            synthetic_result("b", Some(2.0..4.0), vec![]),
+            // Untimed scope whose nested scope covers 5..6
+            synthetic_result(
+                "c",
+                None,
+                vec![synthetic_result("c0", Some(5.0..6.0), vec![])],
+            ),
+            // Partially outside of the parent, covering 9..10
+            synthetic_result("d", Some(9.0..12.0), vec![]),
+        ],
+    );
+    assert_eq!(result.self_time(), Some(5.0));
+    assert_eq!(result.nested_queries[0].self_time(), Some(2.0));
+    assert_eq!(result.nested_queries[2].self_time(), None);
+}
+
+#[test]
+fn utilization_of_overlapping_scopes() {
+    let results = vec![
+        synthetic_result("a", Some(0.000..0.004), vec![]),
+        // Overlaps with "a".
+        synthetic_result("b", Some(0.002..0.006), vec![]),
+        synthetic_result(
+            "c",
+            None,
+            vec![synthetic_result("c0", Some(0.010..0.012), vec![])],
+        ),
+    ];
+    assert!((GpuTimerQueryResult::gpu_busy_time(&results) - 0.008).abs() < 1e-9);
+    assert!((GpuTimerQueryResult::utilization(&results, 16.0) - 0.5).abs() < 1e-6);
+    assert_eq!(GpuTimerQueryResult::gpu_busy_time(&[]), 0.0);
+}
+
+#[test]
+fn duration_helpers() {
+    let timed = synthetic_result("a", Some(1.0..1.5), vec![]);
+    assert_eq!(timed.duration_secs(), Some(0.5));
+    assert_eq!(timed.duration_ms(), Some(500.0));
+    assert_eq!(timed.duration_us(), Some(500000.0));
+    assert_eq!(result("b", vec![]).duration_ms(), None);
+}
+
+#[test]
+fn time_per_unit() {
+    let timed = synthetic_result("a", Some(1.0..5.0), vec![]);
+    assert_eq!(timed.time_per_unit(4), Some(1.0));
+    assert_eq!(timed.time_per_unit(0), None);
+    assert_eq!(result("b", vec![]).time_per_unit(4), None);
+}
+
+#[test]
+fn aggregator_collects_statistics_by_label_path() {
+    let mut aggregator = wgpu_profiler::GpuTimerQueryAggregator::new();
+    for duration in [1.0, 3.0] {
+        aggregator.add_frame(&[synthetic_result(
+            "frame",
+            None,
+            vec![synthetic_result("shadows", Some(0.0..duration), vec![])],
+        )]);
+    }
+
+    // Untimed scopes are skipped.
+    assert!(aggregator.get("frame").is_none());
+
+    let shadows = aggregator.get("frame/shadows").unwrap();
+    assert_eq!(shadows.num_samples, 2);
+    assert_eq!(shadows.min, 1.0);
+    assert_eq!(shadows.max, 3.0);
+    assert_eq!(shadows.average(), 2.0);
+    assert_eq!(aggregator.iter().count(), 1);
+}
+
+#[cfg(feature = "test-support")]
+#[test]
+fn build_results_from_raw_timestamps() {
+    use wgpu_profiler::test_support::{build_results, RawScope};
+
+    let scopes = [RawScope {
+        label: "frame".to_owned(),
+        timestamps: Some((1000, 5000)),
+        nested_scopes: vec![
+            RawScope {
+                label: "tiny backwards step".to_owned(),
+                timestamps: Some((2000, 1999)),
+                ..Default::default()
+            },
+            RawScope {
+                label: "wraparound".to_owned(),
+                timestamps: Some((u64::MAX - 10, 100)),
+                ..Default::default()
+            },
+            RawScope {
+                label: "untimed".to_owned(),
+                ..Default::default()
+            },
+            RawScope {
+                label: "unwritten".to_owned(),
+                timestamps: Some((0, 0)),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    }];
+
+    // One tick per microsecond.
+    let results = build_results(&scopes, 1000.0, &Default::default());
+    assert_eq!(results.len(), 1);
+    assert!((results[0].duration_us().unwrap() - 4000.0).abs() < 1e-6);
+
+    let nested = &results[0].nested_queries;
+    assert_eq!(nested.len(), 4);
+    assert_eq!(nested[0].duration_us(), Some(0.0));
+    assert_eq!(nested[1].time, None);
+    assert_eq!(nested[2].time, None);
+    assert_eq!(nested[3].time, None);
+}
+
+#[cfg(feature = "test-support")]
+#[test]
+fn reversed_timestamps_are_merged_if_enabled() {
+    use wgpu_profiler::test_support::{build_results, RawScope};
+
+    // End lands before the start, e.g. because start & end were recorded in separately submitted command buffers.
+    let scopes = [RawScope {
+        label: "split pass".to_owned(),
+        timestamps: Some((5000, 3000)),
+        ..Default::default()
+    }];
+
+    // One tick per microsecond.
+    let results = build_results(&scopes, 1000.0, &Default::default());
+    assert_eq!(results[0].time, None);
+
+    let settings = wgpu_profiler::GpuProfilerSettings {
+        merge_reversed_timestamps: true,
+        ..Default::default()
+    };
+    let results = build_results(&scopes, 1000.0, &settings);
+    let time = results[0].time.clone().unwrap();
+    assert!((time.start - 0.003).abs() < 1e-9);
+    assert!((results[0].duration_us().unwrap() - 2000.0).abs() < 1e-6);
+}
+
+#[test]
+fn aggregator_totals_by_category() {
+    fn categorized(
+        label: &str,
+        time: std::ops::Range<f64>,
+        category: &'static str,
+        nested_queries: Vec<GpuTimerQueryResult>,
+    ) -> GpuTimerQueryResult {
+        GpuTimerQueryResult {
+            category: Some(category),
+            ..synthetic_result(label, Some(time), nested_queries)
+        }
+    }
+
+    let mut aggregator = wgpu_profiler::GpuTimerQueryAggregator::new();
+    aggregator.add_frame(&[
+        categorized(
+            "gbuffer",
+            0.0..2.0,
+            "geometry",
+            // Already covered by the enclosing scope.
+            vec![categorized("terrain", 0.0..1.0, "geometry", vec![])],
+        ),
+        categorized("sun", 2.0..2.5, "lighting", vec![]),
+        categorized("points", 2.5..3.5, "lighting", vec![]),
+        synthetic_result("uncategorized", Some(3.5..4.0), vec![]),
+    ]);
+
+    let totals = aggregator.totals_by_category();
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals["geometry"], 2.0);
+    assert_eq!(totals["lighting"], 1.5);
+}
+
+#[test]
+fn average_duration() {
+    let regular = synthetic_result("a", Some(1.0..5.0), vec![]);
+    assert_eq!(regular.average_duration_secs(), Some(4.0));
+
+    let accumulated = GpuTimerQueryResult {
+        num_samples: Some(4),
+        ..regular
+    };
+    assert_eq!(accumulated.average_duration_secs(), Some(1.0));
+    assert_eq!(result("b", vec![]).average_duration_secs(), None);
+}
+
+#[test]
+fn overlapping_siblings_are_detected() {
+    let other_thread_id = std::thread::spawn(|| std::thread::current().id())
+        .join()
+        .unwrap();
+
+    let results = [synthetic_result(
+        "frame",
+        Some(0.0..10.0),
+        vec![
+            synthetic_result("a", Some(0.0..2.0), vec![]),
+            synthetic_result("b", Some(1.5..3.0), vec![]),
+            synthetic_result("c", Some(3.0..4.0), vec![]),
+            // Different threads may record to encoders that run concurrently.
+            GpuTimerQueryResult {
+                tid: other_thread_id,
+                ..synthetic_result("other thread", Some(0.0..4.0), vec![])
+            },
+        ],
+    )];
+
+    let warnings = GpuTimerQueryResult::check_no_overlap(&results);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].parent_label_path, "frame");
+    assert_eq!(warnings[0].first_label, "a");
+    assert_eq!(warnings[0].second_label, "b");
+    assert_eq!(warnings[0].overlap, 0.5);
+}
+
+#[test]
+fn top_k_longest_scopes() {
+    let results = [
+        synthetic_result(
+            "a",
+            Some(0.0..4.0),
+            vec![
+                synthetic_result("a0", Some(0.0..1.0), vec![]),
+                synthetic_result("a1", Some(1.0..3.5), vec![]),
+            ],
+        ),
+        synthetic_result(
+            "untimed",
+            None,
+            vec![synthetic_result("b0", Some(4.0..7.0), vec![])],
+        ),
+    ];
+
+    let labels = |k| {
+        GpuTimerQueryResult::top_k(&results, k)
+            .into_iter()
+            .map(|result| result.label.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(labels(0), Vec::<&str>::new());
+    assert_eq!(labels(2), ["a", "b0"]);
+    assert_eq!(labels(10), ["a", "b0", "a1", "a0"]);
+}
+
+#[cfg(feature = "test-support")]
+#[test]
+fn mock_results_are_plausible() {
+    let results = wgpu_profiler::test_support::mock_results();
+
+    assert!(GpuTimerQueryResult::check_no_overlap(&results).is_empty());
+    let frame_time = GpuTimerQueryResult::gpu_busy_time(&results);
+    assert!(frame_time > 0.0 && frame_time < 1.0 / 60.0);
+    GpuTimerQueryResult::walk_all(&results, &mut |result, _, _| {
+        for nested in &result.nested_queries {
+            if let (Some(time), Some(nested_time)) = (&result.time, &nested.time) {
+                assert!(time.start <= nested_time.start && nested_time.end <= time.end);
+            }
+        }
+    });
+}
+
+#[cfg(feature = "test-support")]
+#[test]
+fn validate_results_with_closure() {
+    use wgpu_profiler::test_support::{
+        expected_scope, validate_results, validate_results_unordered, ExpectedScope,
+    };
+
+    let results = wgpu_profiler::test_support::mock_results();
+    let expected = [
+        ExpectedScope::new("ui").with_nested_scopes([ExpectedScope::new("text")]),
+        expected_scope(
+            "post processing",
+            [
+                ExpectedScope::new("bloom"),
+                ExpectedScope::new("tonemapping"),
+            ],
+        ),
+        expected_scope(
+            "main pass",
+            [
+                ExpectedScope::new("opaque"),
+                ExpectedScope::new("sky"),
+                ExpectedScope::new("transparent"),
+            ],
+        ),
+        expected_scope(
+            "shadows",
+            [
+                ExpectedScope::new("cascade 0"),
+                ExpectedScope::new("cascade 1"),
+            ],
+        ),
+    ];
+    let expects_time = |expected: &ExpectedScope| Some(expected.label != "ui");
+
+    validate_results_unordered(&results, &expected, &expects_time);
+    let mut ordered = expected.to_vec();
+    ordered.reverse();
+    validate_results(&results, &ordered, &expects_time);
+
+    let result = std::panic::catch_unwind(|| {
+        validate_results(&results, &expected, &expects_time);
+    });
+    assert!(result.is_err());
+}
+
+#[test]
+fn absolute_time_from_clock_calibration() {
+    let system_time = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1000);
+    let clock_calibration = wgpu_profiler::ClockCalibration {
+        gpu_time: 10.0,
+        system_time,
+    };
+
+    let uncalibrated = synthetic_result("a", Some(12.5..13.0), vec![]);
+    assert_eq!(uncalibrated.absolute_time(), None);
+
+    let calibrated = GpuTimerQueryResult {
+        clock_calibration: Some(clock_calibration),
+        ..uncalibrated
+    };
+    assert_eq!(
+        calibrated.absolute_time(),
+        Some(system_time + std::time::Duration::from_millis(2500))
+    );
+    assert_eq!(
+        clock_calibration.to_system_time(9.0),
+        Some(system_time - std::time::Duration::from_secs(1))
+    );
+}
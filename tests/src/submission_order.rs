@@ -0,0 +1,126 @@
+use wgpu_profiler::{EndFrameError, FrameInfo, GpuProfiler, GpuProfilerSettings};
+
+fn profiler_with_submission_validation() -> GpuProfiler {
+    GpuProfiler::new(GpuProfilerSettings {
+        validate_submission_order: true,
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn resolve_submitted_last() {
+    let (_, device, queue) = super::create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+    let profiler = profiler_with_submission_validation();
+
+    let mut scope_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let _ = profiler.scope("scope", &mut scope_encoder, &device);
+    let mut resolve_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    profiler.resolve_queries(&mut resolve_encoder);
+
+    profiler.submit(&queue, [scope_encoder.finish()]);
+    profiler.submit(&queue, [resolve_encoder.finish()]);
+    assert_eq!(profiler.end_frame(), Ok(()));
+}
+
+#[test]
+fn resolve_submitted_before_queries() {
+    let (_, device, queue) = super::create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+    let profiler = profiler_with_submission_validation();
+
+    let mut scope_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let _ = profiler.scope("scope", &mut scope_encoder, &device);
+    let mut resolve_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    profiler.resolve_queries(&mut resolve_encoder);
+
+    profiler.submit(&queue, [resolve_encoder.finish()]);
+    profiler.submit(&queue, [scope_encoder.finish()]);
+    assert_eq!(
+        profiler.end_frame(),
+        Err(EndFrameError::SubmissionsAfterResolve(1))
+    );
+}
+
+#[test]
+fn resolve_not_submitted() {
+    let (_, device, _queue) = super::create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+    let profiler = profiler_with_submission_validation();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let _ = profiler.scope("scope", &mut encoder, &device);
+    profiler.resolve_queries(&mut encoder);
+
+    assert_eq!(
+        profiler.end_frame(),
+        Err(EndFrameError::ResolveNotSubmitted)
+    );
+}
+
+#[test]
+fn frame_after_invalid_submission_order_is_clean() {
+    let (_, device, queue) = super::create_device(
+        wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    )
+    .unwrap();
+    let profiler = profiler_with_submission_validation();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("discarded", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    assert_eq!(
+        profiler.end_frame(),
+        Err(EndFrameError::ResolveNotSubmitted)
+    );
+    assert_eq!(profiler.take_dropped_frame_indices(), vec![0]);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("kept", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    profiler.submit(&queue, [encoder.finish()]);
+    assert_eq!(profiler.end_frame(), Ok(()));
+
+    device.poll(wgpu::MaintainBase::Wait);
+    let frames = profiler.process_all_finished_frames(queue.get_timestamp_period());
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].index, 1);
+    assert_eq!(frames[0].results.len(), 1);
+    assert_eq!(frames[0].results[0].label, "kept");
+}
+
+#[test]
+fn process_frame_blocking_on_submission_index() {
+    let (_, device, queue) = super::create_device(wgpu::Features::TIMESTAMP_QUERY).unwrap();
+    let profiler = GpuProfiler::new(GpuProfilerSettings::default()).unwrap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    drop(profiler.scope("scope", &mut encoder, &device));
+    profiler.resolve_queries(&mut encoder);
+    let submission_index = queue.submit([encoder.finish()]);
+    profiler
+        .end_frame_with(FrameInfo {
+            submission_index: Some(submission_index),
+            ..Default::default()
+        })
+        .unwrap();
+
+    // No explicit device poll needed.
+    let frame = profiler
+        .process_finished_frame_blocking(&device, queue.get_timestamp_period())
+        .unwrap()
+        .results;
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].label, "scope");
+}
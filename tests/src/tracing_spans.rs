@@ -0,0 +1,101 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+use crate::src::synthetic_result;
+
+/// Records the label of every span along with the label of the span that was entered when it was created.
+#[derive(Default)]
+struct SpanTreeRecorder {
+    next_id: AtomicU64,
+    labels: Mutex<Vec<String>>,
+    parents: Mutex<Vec<Option<u64>>>,
+    entered: Mutex<Vec<u64>>,
+}
+
+struct LabelVisitor(Option<String>);
+
+impl Visit for LabelVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "label" {
+            self.0 = Some(value.to_owned());
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+impl Subscriber for SpanTreeRecorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        let mut visitor = LabelVisitor(None);
+        span.record(&mut visitor);
+        self.labels.lock().unwrap().push(visitor.0.unwrap());
+        self.parents
+            .lock()
+            .unwrap()
+            .push(self.entered.lock().unwrap().last().copied());
+        span::Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, span: &span::Id) {
+        self.entered.lock().unwrap().push(span.into_u64());
+    }
+
+    fn exit(&self, span: &span::Id) {
+        assert_eq!(self.entered.lock().unwrap().pop(), Some(span.into_u64()));
+    }
+}
+
+#[test]
+fn results_are_reported_as_nested_spans() {
+    let results = vec![
+        synthetic_result(
+            "a",
+            Some(0.0..1.0),
+            vec![
+                synthetic_result("a0", Some(0.0..0.5), vec![]),
+                synthetic_result("a1", None, vec![]),
+            ],
+        ),
+        synthetic_result("b", Some(1.0..2.0), vec![]),
+    ];
+
+    let recorder = std::sync::Arc::new(SpanTreeRecorder::default());
+    tracing::subscriber::with_default(recorder.clone(), || {
+        wgpu_profiler::tracing_spans::report_to_tracing_nested(&results);
+    });
+
+    let labels = recorder.labels.lock().unwrap().clone();
+    let label_of = |id: Option<u64>| id.map(|id| labels[id as usize - 1].as_str());
+    let spans = labels
+        .iter()
+        .zip(recorder.parents.lock().unwrap().iter())
+        .map(|(label, parent)| (label.as_str(), label_of(*parent)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        [
+            ("a", None),
+            ("a0", Some("a")),
+            ("a1", Some("a")),
+            ("b", None)
+        ]
+    );
+    assert!(recorder.entered.lock().unwrap().is_empty());
+}